@@ -1,29 +1,154 @@
 use axum::{
     Json, Router,
     extract::{
-        Path, State,
+        Path, Query, State,
         ws::{Message, WebSocket, WebSocketUpgrade},
     },
+    http::StatusCode,
     response::IntoResponse,
     routing::{get, post},
 };
 use callbreak::{
     Host,
-    agent::{AgentKind, Bot, ClientMessage, Human, Transport},
-    game::Call,
+    agent::{AgentKind, Bot, ClientMessage, Difficulty, Human, Results, Style, Transport},
+    duplicate::Duplicate,
+    game::{Call, Rules},
+    matches::Match,
+    stats::{Meter, Summary},
 };
 use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::{
     collections::HashMap,
     sync::{Arc, Mutex},
 };
 use tokio::sync::mpsc;
+use tracing::info;
 use tracing_subscriber::{EnvFilter, fmt};
 
 #[derive(Clone)]
 struct AppState {
-    hosts: Arc<Mutex<HashMap<usize, Host>>>,
+    hosts: Arc<Mutex<HashMap<usize, Room>>>,
+    /// Statistics of the rooms whose games are over
+    statistics: Arc<Mutex<HashMap<usize, Statistics>>>,
+}
+
+/// What is kept of a room once its games are over
+#[derive(Debug, Default, Clone, Serialize)]
+struct Statistics {
+    /// Luck of every player in every game of the room, see [`Meter`]
+    luck: Vec<Vec<Summary>>,
+}
+
+/// Kind of game a room plays
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum RoomKind {
+    #[default]
+    Standard,
+    /// Same deals at every table with the players rotated through the seats
+    Duplicate,
+    /// Several games between the same players, with their points added up
+    Match,
+}
+
+#[derive(Debug)]
+enum Table {
+    Standard(Box<Host>),
+    Duplicate(Duplicate),
+    Match(Match),
+}
+
+/// A game waiting for its players, along with the features it was opened with
+#[derive(Debug)]
+struct Room {
+    table: Table,
+    /// Whether humans may ask for a suggested call or card
+    hints: bool,
+    /// Seats at the table, and whether a bot asked for each of them
+    asked: Vec<bool>,
+}
+
+impl Table {
+    fn new(options: &RoomOptions) -> Self {
+        let (rules, seed) = (options.rules, options.seed);
+        match (options.kind, seed) {
+            (RoomKind::Standard, None) => Self::Standard(Box::new(Host::new().with_rules(rules))),
+            (RoomKind::Standard, Some(seed)) => {
+                Self::Standard(Box::new(Host::with_seed(seed).with_rules(rules)))
+            }
+            (RoomKind::Duplicate, seed) => {
+                Self::Duplicate(Duplicate::new(seed.unwrap_or_else(rand::random)).with_rules(rules))
+            }
+            (RoomKind::Match, seed) => {
+                let mut series = Match::new(options.games).with_rules(rules);
+                if let Some(seed) = seed {
+                    series = series.with_seed(seed);
+                }
+                if options.reshuffle {
+                    series = series.reshuffled();
+                }
+                Self::Match(series)
+            }
+        }
+    }
+
+    fn add_agent(
+        &mut self,
+        id: String,
+        seat: Option<usize>,
+        agent: AgentKind,
+    ) -> Result<(), callbreak::Error> {
+        match self {
+            Self::Standard(host) => host.add_agent_at(id, seat, agent),
+            // INFO: the players go round the seats of a duplicate and keep the ones of the first
+            // game of a match, so there is no seat to ask for
+            Self::Duplicate(duplicate) => duplicate.add_agent(id, agent),
+            Self::Match(series) => series.add_agent(id, agent),
+        }
+    }
+
+    fn is_ready(&self) -> bool {
+        match self {
+            Self::Standard(host) => host.is_ready(),
+            Self::Duplicate(duplicate) => duplicate.is_ready(),
+            Self::Match(series) => series.is_ready(),
+        }
+    }
+
+    fn run(&mut self) -> Statistics {
+        let records = match self {
+            Self::Standard(host) => {
+                host.run();
+                vec![host.record()]
+            }
+            Self::Duplicate(duplicate) => {
+                duplicate.run();
+                // INFO: the players are sent the standings and the luck after every table
+                info!(standings = ?duplicate.standings(), "duplicate is over");
+                duplicate.records().to_vec()
+            }
+            Self::Match(series) => {
+                series.run();
+                // INFO: the players are sent the standings and the luck after every game
+                info!(standings = ?series.standings(), "match is over");
+                series.records().to_vec()
+            }
+        };
+        let luck: Vec<Vec<Summary>> = records
+            .iter()
+            .map(|record| Meter::default().measure(record).summaries)
+            .collect();
+        info!(?luck, "luck of the games");
+        if let (Self::Standard(host), Some(luck)) = (self, luck.first()) {
+            host.announce(Results {
+                luck: luck.clone(),
+                ..Results::default()
+            });
+        }
+        Statistics { luck }
+    }
 }
 
 #[derive(Debug)]
@@ -43,6 +168,8 @@ impl AxumTransport {
             while let Some(msg) = rx_out.recv().await {
                 let _ = tx.send(Message::text(msg)).await;
             }
+            // the human is gone, so nothing more is going to be sent
+            let _ = tx.send(Message::Close(None)).await;
         });
 
         // reader
@@ -75,9 +202,119 @@ impl Transport for AxumTransport {
     }
 }
 
+/// Bot to seat in a new room
+#[derive(Debug, Default, Deserialize)]
+struct BotOptions {
+    #[serde(default)]
+    difficulty: Difficulty,
+    #[serde(default)]
+    style: Style,
+    /// Send the reasons behind every decision of the bot along with the next message
+    #[serde(default)]
+    explain: bool,
+    /// Seat the bot asks for, any free one if left out
+    seat: Option<usize>,
+}
+
+/// Options of a player joining a room
+#[derive(Debug, Deserialize)]
+struct JoinOptions {
+    /// Seat the player asks for, any free one if left out
+    seat: Option<usize>,
+}
+
+/// Options for a new room, all of which may be left out
+#[derive(Debug, Default, Deserialize)]
+struct RoomOptions {
+    #[serde(default)]
+    bots: Vec<BotOptions>,
+    #[serde(default)]
+    kind: RoomKind,
+    /// House rules to play by, e.g. `{"nil": true}` or `{"target": 30}`
+    #[serde(default)]
+    rules: Rules,
+    /// Games in a match room
+    #[serde(default = "games")]
+    games: usize,
+    /// Draw the seats again before every game of a match
+    #[serde(default)]
+    reshuffle: bool,
+    /// Seed for the seating and the deals, so that a game can be replayed
+    seed: Option<u64>,
+    /// Let the humans ask the bots for a hint
+    #[serde(default)]
+    hints: bool,
+    /// Rated games never give hints, whatever else was asked for
+    #[serde(default)]
+    rated: bool,
+}
+
+/// Highest target score a room may play to, far beyond what any table plays to
+const MAX_TARGET: f32 = 10_000.0;
+
+fn games() -> usize {
+    3
+}
+
 // FIXME: at some point when games are over, I will need to dump the game somewhere
 // and release the id for a new game to start with the same id
-async fn new(State(state): State<AppState>) -> Json<serde_json::Value> {
+async fn new(
+    State(state): State<AppState>,
+    options: Option<Json<RoomOptions>>,
+) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
+    let Json(mut options) = options.unwrap_or_default();
+    // one seat is left for the human
+    let seats = options.rules.seats.count();
+    options.bots.resize_with(seats - 1, BotOptions::default);
+    let standard = matches!(options.kind, RoomKind::Standard);
+    if !standard && options.bots.iter().any(|bot| bot.seat.is_some()) {
+        return Err(bad_request(
+            "seats can only be asked for at a standard table".to_string(),
+        ));
+    }
+    // INFO: duplicates and matches move the players round the seats, so nothing is asked there
+    let mut asked = vec![false; if standard { seats } else { 0 }];
+    for seat in options.bots.iter().filter_map(|bot| bot.seat) {
+        if seat >= seats || std::mem::replace(&mut asked[seat], true) {
+            return Err(bad_request(format!(
+                "bots cannot share or go past the {seats} seats"
+            )));
+        }
+    }
+    if options
+        .rules
+        .target
+        .is_some_and(|target| !(target > 0.0 && target <= MAX_TARGET))
+    {
+        return Err(bad_request(format!(
+            "a target score must be above zero and at most {MAX_TARGET}"
+        )));
+    }
+    if matches!(options.kind, RoomKind::Match) && options.games == 0 {
+        return Err(bad_request(
+            "a match must have at least one game".to_string(),
+        ));
+    }
+    let mut host = Room {
+        table: Table::new(&options),
+        hints: options.hints && !options.rated,
+        asked,
+    };
+    for (i, options) in options.bots.iter().enumerate() {
+        let mut bot = Bot::new(options.difficulty, options.style);
+        if options.explain {
+            bot = bot.with_explanations();
+        }
+        // the configuration goes into the id so that it shows up in the player list
+        host.table
+            .add_agent(
+                format!("bot{} ({bot})", i + 1),
+                options.seat,
+                AgentKind::Bot(bot),
+            )
+            .map_err(|error| bad_request(error.to_string()))?;
+    }
+
     let mut hosts = state.hosts.lock().unwrap();
     loop {
         println!("attempting");
@@ -85,33 +322,65 @@ async fn new(State(state): State<AppState>) -> Json<serde_json::Value> {
         if hosts.contains_key(&id) {
             continue;
         }
-        hosts.insert(id, Host::new());
-        return Json(json!({"room": id}));
+        hosts.insert(id, host);
+        return Ok(Json(json!({"room": id})));
     }
 }
 
+fn bad_request(error: String) -> (StatusCode, Json<serde_json::Value>) {
+    (StatusCode::BAD_REQUEST, Json(json!({ "error": error })))
+}
+
 async fn join(
     State(state): State<AppState>,
     Path(room): Path<usize>,
+    Query(options): Query<JoinOptions>,
     ws: WebSocketUpgrade,
-) -> impl IntoResponse {
+) -> Result<impl IntoResponse, (StatusCode, Json<serde_json::Value>)> {
     println!("a player has attempted join");
+    if let Some(seat) = options.seat {
+        let hosts = state.hosts.lock().unwrap();
+        let host = hosts.get(&room);
+        if host.is_some_and(|host| !matches!(host.table, Table::Standard(_))) {
+            return Err(bad_request(
+                "seats can only be asked for at a standard table".to_string(),
+            ));
+        }
+        let asked = host.map(|host| &host.asked);
+        match asked {
+            Some(asked) if seat >= asked.len() => {
+                return Err(bad_request(format!(
+                    "there is no seat {seat} of the {} seats",
+                    asked.len()
+                )));
+            }
+            Some(asked) if asked[seat] => {
+                return Err(bad_request(format!("seat {seat} is taken by a bot")));
+            }
+            _ => {}
+        }
+    }
     // loop or wait if there is another player being added?
-    ws.on_upgrade(move |socket| async move {
+    Ok(ws.on_upgrade(move |socket| async move {
         let mut hosts = state.hosts.lock().unwrap();
         let Some(host) = hosts.get_mut(&room) else {
             return;
         };
-        host.add_agent("bot1".to_string(), AgentKind::Bot(Bot))
-            .unwrap();
-        host.add_agent("bot2".to_string(), AgentKind::Bot(Bot))
-            .unwrap();
-        host.add_agent("bot3".to_string(), AgentKind::Bot(Bot))
-            .unwrap();
-        let human = Human::new(Box::new(AxumTransport::new(socket)));
-        let _ = host.add_agent("ME".to_string(), AgentKind::Human(human));
-        // FIXME: ^ is an error, should handle it
-        let ready = host.is_ready();
+        let mut human = Human::new(Box::new(AxumTransport::new(socket)));
+        if host.hints {
+            human = human.with_hints();
+        }
+        // INFO: the seat was checked before the upgrade, so this is only left to another human
+        // taking the seat or the table in the meantime. the socket is closed as the human is
+        // dropped
+        if let Err(error) =
+            host.table
+                .add_agent("ME".to_string(), options.seat, AgentKind::Human(human))
+        {
+            info!(?error, room, "player could not be seated");
+            return;
+        }
+        let ready = host.table.is_ready();
         drop(hosts);
         if ready {
             let mut host = {
@@ -120,10 +389,23 @@ async fn join(
             }
             .unwrap();
             tokio::task::spawn_blocking(move || {
-                host.run();
+                let statistics = host.table.run();
+                state.statistics.lock().unwrap().insert(room, statistics);
             });
         }
-    })
+    }))
+}
+
+async fn statistics(
+    State(state): State<AppState>,
+    Path(room): Path<usize>,
+) -> Result<Json<Statistics>, StatusCode> {
+    let statistics = state.statistics.lock().unwrap();
+    statistics
+        .get(&room)
+        .cloned()
+        .map(Json)
+        .ok_or(StatusCode::NOT_FOUND)
 }
 
 #[tokio::main]
@@ -138,10 +420,12 @@ async fn main() {
         .init();
     let state = AppState {
         hosts: Arc::new(Mutex::new(HashMap::new())),
+        statistics: Arc::new(Mutex::new(HashMap::new())),
     };
     let app = Router::new()
         .route("/new", post(new))
         .route("/join/{room}", get(join))
+        .route("/statistics/{room}", get(statistics))
         .with_state(state);
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:3000").await.unwrap();
@@ -0,0 +1,74 @@
+use callbreak::Host;
+use callbreak::agent::{AgentKind, Bot, Budget, Difficulty, Ismcts, Style};
+use callbreak::env::{ACTIONS, Env, OBSERVATION};
+use criterion::{Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+
+// a whole game between bots that barely think, so that the time goes into the engine
+fn random_game(seed: u64) -> Host {
+    let mut host = Host::with_seed(seed);
+    for i in 0..4 {
+        let bot = Bot::new(Difficulty::Random, Style::Balanced).with_seed(seed + i);
+        host.add_agent(i.to_string(), AgentKind::Bot(bot)).unwrap();
+    }
+    host.run();
+    host
+}
+
+// a single seat searching every move, which plays out thousands of rounds on the engine
+fn search_game(seed: u64) -> Host {
+    let mut host = Host::with_seed(seed);
+    let search = Ismcts::new(Budget::Iterations(100)).with_seed(seed);
+    host.add_agent("search".to_string(), AgentKind::Ismcts(search))
+        .unwrap();
+    for i in 1..4 {
+        let bot = Bot::new(Difficulty::Random, Style::Balanced).with_seed(seed + i);
+        host.add_agent(i.to_string(), AgentKind::Bot(bot)).unwrap();
+    }
+    host.run();
+    host
+}
+
+// a whole game through the training environment, observing before every step
+fn env_game(env: &mut Env, seed: u64) -> f32 {
+    env.reset(seed);
+    let mut observation = [0.0; OBSERVATION];
+    while let Some(seat) = env.player() {
+        env.observe(seat, &mut observation);
+        let mask = env.legal_actions();
+        let action = (0..ACTIONS).rev().find(|a| mask[*a]).unwrap();
+        env.step(action).unwrap();
+    }
+    env.scores()[0]
+}
+
+fn engine(c: &mut Criterion) {
+    let mut seed = 0;
+    let mut env = Env::new(seed);
+    c.bench_function("env game", |b| {
+        b.iter(|| {
+            seed += 1;
+            black_box(env_game(&mut env, seed))
+        })
+    });
+
+    c.bench_function("random game", |b| {
+        b.iter(|| {
+            seed += 1;
+            black_box(random_game(seed))
+        })
+    });
+
+    let mut group = c.benchmark_group("search");
+    group.sample_size(10);
+    group.bench_function("ismcts game", |b| {
+        b.iter(|| {
+            seed += 1;
+            black_box(search_game(seed))
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, engine);
+criterion_main!(benches);
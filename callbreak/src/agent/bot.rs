@@ -1,16 +1,366 @@
+use super::calling::{
+    Standings, Weighed, as_spades, best_trump, choose_call, expected_tricks, trick_distribution,
+    trump_worth,
+};
+use super::explain::{Candidate, Choice, Explanation, Stats};
+use super::ismcts::Ismcts;
+use super::model::CallingModel;
+use super::opponent::OpponentModel;
 use super::view::Game;
-use crate::game::{Call, Card};
+use crate::game::{CLAIM_TRICKS, Call, Card, Claim, DROPPED, Rank, Suit, TrickTakingRules, beats};
+use rand::{Rng, SeedableRng, rngs::SmallRng, seq::IndexedRandom};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::sync::Arc;
+use tracing::debug;
+
+/// How well a bot plays
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Difficulty {
+    /// Any valid move will do
+    Random,
+    /// Takes a trick whenever it can
+    #[default]
+    Beginner,
+    /// Keeps track of the cards that are out and does not waste its winners
+    Intermediate,
+    /// Searches the rest of the round with ISMCTS
+    Expert,
+}
+
+/// Personality a bot plays with, on top of its difficulty
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Style {
+    #[default]
+    Balanced,
+    /// Calls more than its cards are worth and leads its strongest cards
+    Aggressive,
+    /// Calls less than its cards are worth and only wins tricks that it is sure of
+    Cautious,
+    /// Counts on its spades and never leads them while it has something else
+    SpadeHoarder,
+}
+
+impl Display for Difficulty {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Random => "random",
+            Self::Beginner => "beginner",
+            Self::Intermediate => "intermediate",
+            Self::Expert => "expert",
+        };
+        write!(f, "{s}")
+    }
+}
+
+impl Display for Style {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Balanced => "balanced",
+            Self::Aggressive => "aggressive",
+            Self::Cautious => "cautious",
+            Self::SpadeHoarder => "spade-hoarder",
+        };
+        write!(f, "{s}")
+    }
+}
+
+// a card is a master when every higher card of its suit is either played or ours
+fn is_master(card: &Card, seen: &[Card]) -> bool {
+    Rank::ALL
+        .iter()
+        .filter(|rank| **rank > card.get_rank())
+        .all(|rank| seen.contains(&Card::new(*rank, card.get_suit())))
+}
+
+/// Number of cards in the hand that are near certain to take a trick with `trump` as the trump
+/// suit
+pub(super) fn count_winners(hand: &[Card], trump: Suit) -> u8 {
+    let mut tricks = 0;
+    for suit in Suit::ALL {
+        let cards: Vec<&Card> = hand
+            .iter()
+            .filter(|card| card.get_suit() == *suit)
+            .collect();
+        for card in cards.iter() {
+            tricks += match card.get_rank() {
+                Rank::Ace => 1,
+                Rank::King if cards.len() >= 2 => 1,
+                Rank::Queen if *suit == trump && cards.len() >= 3 => 1,
+                _ => 0,
+            };
+        }
+        if *suit == trump {
+            tricks += cards.len().saturating_sub(3) as u8;
+        }
+    }
+    tricks
+}
 
 /// Agent to facilitate interaction with a bot
 #[derive(Debug)]
-pub struct Bot;
+pub struct Bot {
+    difficulty: Difficulty,
+    style: Style,
+    rng: SmallRng,
+    search: Option<Ismcts>,
+    // learned instead of hand tuned trick counts, for the bots that weigh up their calls
+    calling: Option<Arc<CallingModel>>,
+    explain: bool,
+    explanation: Option<Explanation>,
+}
+
+impl Default for Bot {
+    fn default() -> Self {
+        Self::new(Difficulty::default(), Style::default())
+    }
+}
+
+impl Display for Bot {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}, {}", self.difficulty, self.style)
+    }
+}
 
 impl Bot {
-    pub(super) fn call(&self, _view: &Game) -> Call {
-        Call::new(1).unwrap()
+    pub fn new(difficulty: Difficulty, style: Style) -> Self {
+        Self {
+            difficulty,
+            style,
+            rng: SmallRng::from_os_rng(),
+            search: (difficulty == Difficulty::Expert).then(Ismcts::default),
+            calling: None,
+            explain: false,
+            explanation: None,
+        }
+    }
+
+    /// Replace the search used by an expert bot, e.g. to change its budget
+    pub fn with_search(mut self, search: Ismcts) -> Self {
+        if self.difficulty == Difficulty::Expert {
+            self.search = Some(search);
+        }
+        self
+    }
+
+    /// Call from the trick counts of a trained model rather than the hand tuned heuristics
+    pub fn with_calling_model(mut self, model: Arc<CallingModel>) -> Self {
+        self.calling = Some(model);
+        self
+    }
+
+    /// Have the bot explain every decision it takes, see [`Bot::take_explanation`]
+    pub fn with_explanations(mut self) -> Self {
+        self.explain = true;
+        if let Some(search) = self.search.take() {
+            self.search = Some(search.with_explanations());
+        }
+        self
+    }
+
+    /// Why the bot took its last decision, if it was asked to explain itself
+    pub fn take_explanation(&mut self) -> Option<Explanation> {
+        let search = self
+            .search
+            .as_mut()
+            .and_then(|search| search.take_explanation());
+        self.explanation.take().or(search)
+    }
+
+    /// Make the bot's choices repeatable, e.g. for comparing bots over the same deals
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = SmallRng::seed_from_u64(seed);
+        if let Some(search) = self.search.take() {
+            self.search = Some(search.with_seed(seed));
+        }
+        self
+    }
+
+    pub fn difficulty(&self) -> Difficulty {
+        self.difficulty
+    }
+
+    pub fn style(&self) -> Style {
+        self.style
+    }
+
+    pub(super) fn call(&mut self, view: &Game) -> Call {
+        let round = view
+            .rounds
+            .last()
+            .expect("must call call() on a valid round");
+        // INFO: a trump yet to be chosen is taken to be the one the bot would choose. the calling
+        // model is trained with spades as trump, so it is shown the hand with its trump swapped
+        // for spades
+        let trump = round.trump.unwrap_or_else(|| best_trump(&round.hand));
+        let hand = &round.hand;
+        let spades = as_spades(hand, trump);
+        let mut weighed = vec![];
+        // chance of taking each number of tricks, for the bots that work it out
+        let mut odds = None;
+        let (tricks, reason) = match self.difficulty {
+            // keep the random calls small enough that the bot still looks like it is playing
+            Difficulty::Random => {
+                let call = Call::new(self.rng.random_range(1..=4)).unwrap();
+                self.explained(view, Choice::Call(call), "called at random", vec![]);
+                return call;
+            }
+            Difficulty::Beginner => (count_winners(hand, trump) as f32, "counted sure winners"),
+            Difficulty::Intermediate => {
+                let distribution = match &self.calling {
+                    Some(model) => model.predict(&spades),
+                    None => trick_distribution(hand, trump),
+                };
+                let call;
+                (call, weighed) = self.call_for_standings(view, &distribution);
+                odds = Some(distribution);
+                (call.value() as f32, "best chance of winning the game")
+            }
+            Difficulty::Expert => {
+                let search = self
+                    .search
+                    .as_mut()
+                    .expect("an expert bot must have a search");
+                let distribution = search
+                    .trick_distribution(view)
+                    .or_else(|| Some(self.calling.as_ref()?.predict(&spades)));
+                match distribution {
+                    Some(distribution) => {
+                        let call;
+                        (call, weighed) = self.call_for_standings(view, &distribution);
+                        odds = Some(distribution);
+                        (
+                            call.value() as f32,
+                            "best chance of winning the searched rounds",
+                        )
+                    }
+                    None => (count_winners(hand, trump) as f32, "counted sure winners"),
+                }
+            }
+        };
+        let trumps = hand.iter().filter(|card| card.get_suit() == trump).count();
+        let tricks = match self.style {
+            Style::Balanced => tricks,
+            Style::Aggressive => tricks + 1.0,
+            Style::Cautious => tricks - 1.0,
+            Style::SpadeHoarder => tricks + trumps.saturating_sub(3) as f32 * 0.5,
+        };
+        let mut call = Call::new(tricks.round().clamp(1.0, 13.0) as u8)
+            .expect("must be a valid call after clamping");
+        let mut reason = match self.style {
+            Style::Balanced => reason.to_string(),
+            style => format!("{reason}, then adjusted for playing {style}"),
+        };
+        let nil = view.ruleset.check_call(Call::nil(), &view.rules).is_ok();
+        if let (true, Some(odds)) = (nil, odds) {
+            let points = |call: Call| {
+                odds.iter()
+                    .enumerate()
+                    .map(|(tricks, p)| {
+                        p * view.ruleset.call_score(call, tricks as u8, &view.rules) as f64
+                    })
+                    .sum::<f64>()
+            };
+            let nil = points(Call::nil());
+            debug!(nil, call = points(call), "weighing up nil");
+            if nil > points(call) {
+                call = Call::nil();
+                reason = format!("nil is worth {nil:.1} points against the hand's odds");
+            }
+        }
+        if self.explain {
+            let candidates = weighed
+                .iter()
+                .map(|weighed| Candidate {
+                    choice: Choice::Call(weighed.call),
+                    score: weighed.win_chance,
+                    stats: Stats::Calling {
+                        win_chance: weighed.win_chance,
+                        expected_points: weighed.expected_points,
+                    },
+                })
+                .collect();
+            self.explained(view, Choice::Call(call), reason, candidates);
+        }
+        call
+    }
+
+    /// Trump the bot picks when it is the highest caller
+    pub(super) fn choose_trump(&mut self, view: &Game) -> Suit {
+        let round = view
+            .rounds
+            .last()
+            .expect("must call choose_trump() on a valid round");
+        if self.difficulty == Difficulty::Random {
+            let trump = *Suit::ALL
+                .choose(&mut self.rng)
+                .expect("there must be suits");
+            self.explained(view, Choice::Trump(trump), "picked at random", vec![]);
+            return trump;
+        }
+        let trump = best_trump(&round.hand);
+        if self.explain {
+            let candidates = Suit::ALL
+                .iter()
+                .map(|suit| {
+                    let expected_tricks = trump_worth(&round.hand, *suit);
+                    Candidate {
+                        choice: Choice::Trump(*suit),
+                        score: expected_tricks,
+                        stats: Stats::Trump { expected_tricks },
+                    }
+                })
+                .collect();
+            self.explained(
+                view,
+                Choice::Trump(trump),
+                "the suit the hand takes the most tricks with",
+                candidates,
+            );
+        }
+        trump
+    }
+
+    fn explained(
+        &mut self,
+        view: &Game,
+        choice: Choice,
+        reason: impl Into<String>,
+        candidates: Vec<Candidate>,
+    ) {
+        if !self.explain {
+            return;
+        }
+        let explanation = Explanation::new(view, choice, reason, candidates, &mut self.rng);
+        debug!(?explanation, "bot decision explained");
+        self.explanation = Some(explanation);
     }
 
-    pub(super) fn play(&self, view: &Game) -> Card {
+    fn call_for_standings(
+        &mut self,
+        view: &Game,
+        distribution: &[f64; 14],
+    ) -> (Call, Vec<Weighed>) {
+        let round = view.rounds.last().expect("must have a round to call on");
+        let model = OpponentModel::observe(view);
+        let standings = Standings {
+            seat: view.turn().expect("must be called on our turn"),
+            scores: view.scores(),
+            calls: round.calls,
+            made: std::array::from_fn(|seat| model.seat(seat).made_rate()),
+            rounds_left: view.rounds_left(),
+            rules: view.rules,
+            ruleset: view.ruleset,
+        };
+        let (call, weighed) = choose_call(distribution, &standings, &mut self.rng);
+        let expected = expected_tricks(distribution);
+        debug!(expected, ?standings, ?call, "calling for the standings");
+        (call, weighed)
+    }
+
+    pub(super) fn play(&mut self, view: &Game) -> Card {
         let round = view
             .rounds
             .last()
@@ -19,9 +369,471 @@ impl Bot {
             .tricks
             .last()
             .expect("must have a valid trick on a valid round");
-        *trick
-            .valid_play_from(&round.hand)
-            .first()
-            .expect("must have a valid card to play")
+        let trump = trick.trump();
+        let moves = view.valid_moves();
+        let played: Vec<Card> = trick.plays().map(|(_, card)| card).collect();
+        let seen: Vec<Card> = round
+            .tricks
+            .iter()
+            .flat_map(|trick| trick.plays().map(|(_, card)| card))
+            .chain(round.hand.iter().copied())
+            .collect();
+        let (card, reason) = match self.difficulty {
+            Difficulty::Random => (
+                *moves
+                    .choose(&mut self.rng)
+                    .expect("must have a valid card to play"),
+                "played at random",
+            ),
+            Difficulty::Expert => {
+                return self
+                    .search
+                    .as_mut()
+                    .expect("an expert bot must have a search")
+                    .play(view);
+            }
+            Difficulty::Beginner | Difficulty::Intermediate => {
+                let partner = view.turn().and_then(|seat| view.rules.partner(seat));
+                let partner_winning = trick
+                    .winner()
+                    .is_some_and(|(turn, _)| Some(usize::from(turn)) == partner);
+                self.pick(&played, &moves, &seen, partner_winning, trump, view.seats())
+            }
+        };
+        if self.explain {
+            let winning = played.iter().copied().reduce(|winning, card| {
+                if beats(card, winning, trump) {
+                    card
+                } else {
+                    winning
+                }
+            });
+            let candidates = moves
+                .iter()
+                .map(|candidate| Candidate {
+                    choice: Choice::Play(*candidate),
+                    score: (*candidate == card) as u8 as f64,
+                    stats: Stats::Heuristic {
+                        wins_trick: winning.is_none_or(|winning| beats(*candidate, winning, trump)),
+                        master: is_master(candidate, &seen),
+                    },
+                })
+                .collect();
+            self.explained(view, Choice::Play(card), reason, candidates);
+        }
+        card
+    }
+
+    /// Claim the rest of the tricks when on lead with nothing but masters and no one left to
+    /// trump them, which holds whatever the others hold
+    // INFO: the engine turns down claims it cannot prove, so bots only claim what is certain
+    pub(super) fn claim(&self, view: &Game) -> Option<Claim> {
+        if self.difficulty == Difficulty::Random {
+            return None;
+        }
+        let round = view.rounds.last()?;
+        let trick = round.tricks.last()?;
+        let trump = trick.trump();
+        // on lead, the cards in hand are the tricks left
+        if trick.plays().next().is_some() || round.hand.len() > CLAIM_TRICKS {
+            return None;
+        }
+        let seen: Vec<Card> = round
+            .tricks
+            .iter()
+            .flat_map(|trick| trick.plays().map(|(_, card)| card))
+            .chain(round.hand.iter().copied())
+            .collect();
+        // the dropped card is never dealt at a table of three
+        let out = |card: Card| !seen.contains(&card) && (view.seats() == 4 || card != DROPPED);
+        let trumps_out = Rank::ALL.iter().any(|rank| out(Card::new(*rank, trump)));
+        let only_trumps = round.hand.iter().all(|card| card.get_suit() == trump);
+        let masters = round.hand.iter().all(|card| is_master(card, &seen));
+        (masters && (only_trumps || !trumps_out)).then_some(Claim::All)
+    }
+
+    // heuristic play for the bots that do not search, along with the rule that picked it
+    fn pick(
+        &self,
+        trick: &[Card],
+        moves: &[Card],
+        seen: &[Card],
+        partner_winning: bool,
+        trump: Suit,
+        seats: usize,
+    ) -> (Card, &'static str) {
+        // the last to play knows for sure whether a card takes the trick
+        let last = trick.len() == seats - 1;
+        let cheapest = |card: &&Card| (card.get_suit() == trump, card.get_rank());
+        let is_master = |card: &Card| is_master(card, seen);
+        let hoarding = |card: &&Card| {
+            self.style != Style::SpadeHoarder
+                || card.get_suit() != trump
+                || moves.iter().all(|c| c.get_suit() == trump)
+        };
+
+        let winning = trick.iter().copied().reduce(|winning, card| {
+            if beats(card, winning, trump) {
+                card
+            } else {
+                winning
+            }
+        });
+        let choice = match (winning, self.difficulty) {
+            // a trick the partner is winning is theirs to take, rather than ours to overtrump
+            (Some(winning), _) if partner_winning => moves
+                .iter()
+                .filter(|card| !beats(**card, winning, trump))
+                .min_by_key(cheapest)
+                .or_else(|| moves.iter().min_by_key(cheapest)),
+            (None, Difficulty::Beginner) => moves
+                .iter()
+                .filter(hoarding)
+                .max_by_key(|card| card.get_rank()),
+            (None, _) => {
+                let leads = || moves.iter().filter(hoarding);
+                match self.style {
+                    Style::Aggressive => {
+                        leads().max_by_key(|card| (is_master(card), card.get_rank()))
+                    }
+                    _ => leads()
+                        .filter(|card| is_master(card) && card.get_suit() != trump)
+                        .max_by_key(|card| card.get_rank())
+                        .or_else(|| leads().min_by_key(cheapest)),
+                }
+            }
+            (Some(winning), Difficulty::Beginner) => moves
+                .iter()
+                .filter(|card| beats(**card, winning, trump))
+                .max_by_key(|card| card.get_rank())
+                .or_else(|| moves.iter().min_by_key(cheapest)),
+            (Some(winning), _) => {
+                let winners = || moves.iter().filter(|card| beats(**card, winning, trump));
+                let sure = winners()
+                    .filter(|card| last || is_master(card))
+                    .min_by_key(cheapest);
+                match self.style {
+                    Style::Cautious => sure,
+                    _ => sure.or_else(|| winners().min_by_key(cheapest)),
+                }
+                .or_else(|| moves.iter().min_by_key(cheapest))
+            }
+        };
+        let card =
+            *choice.unwrap_or_else(|| moves.first().expect("must have a valid card to play"));
+        let reason = match winning {
+            None if is_master(&card) => "led a card nobody can beat",
+            None if self.difficulty == Difficulty::Beginner => "led its highest card",
+            None => "led its cheapest card",
+            Some(winning) if partner_winning && !beats(card, winning, trump) => {
+                "left the trick to its partner"
+            }
+            Some(winning) if !beats(card, winning, trump) => "threw its cheapest card",
+            Some(_) if self.difficulty == Difficulty::Beginner => {
+                "took the trick with its highest card"
+            }
+            Some(_) if last || is_master(&card) => "took the trick with its cheapest sure winner",
+            Some(_) => "tried to take the trick as cheaply as it could",
+        };
+        (card, reason)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game as Engine;
+
+    const DIFFICULTIES: [Difficulty; 3] = [
+        Difficulty::Random,
+        Difficulty::Beginner,
+        Difficulty::Intermediate,
+    ];
+    const STYLES: [Style; 4] = [
+        Style::Balanced,
+        Style::Aggressive,
+        Style::Cautious,
+        Style::SpadeHoarder,
+    ];
+
+    #[test]
+    fn every_configuration_plays_a_round_with_valid_moves() {
+        for difficulty in DIFFICULTIES {
+            for style in STYLES {
+                let mut bot = Bot::new(difficulty, style);
+                let mut game = Engine::default();
+                for player in 0..4 {
+                    game.add_player(&player.to_string()).unwrap();
+                }
+                for _ in 0..4 {
+                    let player = game.turn().unwrap();
+                    let view = game.build_view_for(&player).unwrap();
+                    game.call(&player, bot.call(&view)).unwrap();
+                }
+                for _ in 0..52 {
+                    let player = game.turn().unwrap();
+                    let view = game.build_view_for(&player).unwrap();
+                    let card = bot.play(&view);
+                    assert!(game.get_valid_moves(&player).unwrap().contains(&card));
+                    game.play(&player, card).unwrap();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn leaves_the_trick_to_a_partner_who_is_winning_it() {
+        let bot = Bot::new(Difficulty::Intermediate, Style::Balanced);
+        let trick = [Card::new(Rank::King, Suit::Clubs)];
+        let moves = [
+            Card::new(Rank::Ace, Suit::Clubs),
+            Card::new(Rank::Three, Suit::Clubs),
+        ];
+        let seen: Vec<Card> = trick.iter().chain(moves.iter()).copied().collect();
+        let (card, _) = bot.pick(&trick, &moves, &seen, false, Suit::Spades, 4);
+        assert_eq!(card, moves[0]);
+        let (card, reason) = bot.pick(&trick, &moves, &seen, true, Suit::Spades, 4);
+        assert_eq!(card, moves[1]);
+        assert_eq!(reason, "left the trick to its partner");
+    }
+
+    #[test]
+    fn takes_the_trick_for_sure_when_last_at_a_table_of_three() {
+        let bot = Bot::new(Difficulty::Intermediate, Style::Cautious);
+        let trick = [
+            Card::new(Rank::Ten, Suit::Clubs),
+            Card::new(Rank::Two, Suit::Clubs),
+        ];
+        let moves = [
+            Card::new(Rank::Queen, Suit::Clubs),
+            Card::new(Rank::Three, Suit::Clubs),
+        ];
+        let seen: Vec<Card> = trick.iter().chain(moves.iter()).copied().collect();
+        // the queen is no master, so with a seat still to play a cautious bot throws instead
+        let (card, _) = bot.pick(&trick, &moves, &seen, false, Suit::Spades, 4);
+        assert_eq!(card, moves[1]);
+        let (card, reason) = bot.pick(&trick, &moves, &seen, false, Suit::Spades, 3);
+        assert_eq!(card, moves[0]);
+        assert_eq!(reason, "took the trick with its cheapest sure winner");
+    }
+
+    #[test]
+    fn moves_are_the_ones_the_ruleset_allows() {
+        use crate::game::{Ruleset, Spades, Trick, Turn};
+        let mut trick = Trick::for_seats(Turn::new(1), 4);
+        trick.play(Card::new(Rank::King, Suit::Clubs)).unwrap();
+        let hand = vec![
+            Card::new(Rank::Two, Suit::Spades),
+            Card::new(Rank::Ace, Suit::Hearts),
+        ];
+        let mut view = Game::of_round([Some(Call::new(1).unwrap()); 4], hand.clone(), vec![trick]);
+        // out of clubs, call break has the spade played while spades leaves the choice open
+        assert_eq!(view.valid_moves(), &hand[..1]);
+        view.ruleset = Ruleset::Spades(Spades::default());
+        assert_eq!(view.valid_moves(), hand);
+    }
+
+    #[test]
+    fn plays_a_card_once_its_claim_is_turned_down() {
+        use crate::agent::{AgentKind, Move};
+        use crate::game::{Claim, Trick, Turn};
+        let view = Game::of_round(
+            [Some(Call::new(1).unwrap()); 4],
+            vec![Card::new(Rank::Ace, Suit::Spades)],
+            vec![Trick::for_seats(Turn::new(0), 4)],
+        );
+        let mut agent = AgentKind::Bot(Bot::new(Difficulty::Intermediate, Style::Balanced));
+        assert_eq!(agent.play(&view), Move::Claim(Claim::All));
+        assert_eq!(
+            agent.play_after_refused_claim(&view),
+            Move::Play(Card::new(Rank::Ace, Suit::Spades))
+        );
+    }
+
+    #[test]
+    fn plays_a_game_whose_trump_is_chosen() {
+        use crate::game::{Rules, Trump};
+        for difficulty in [
+            Difficulty::Random,
+            Difficulty::Intermediate,
+            Difficulty::Expert,
+        ] {
+            let rules = Rules::default().with_trump(Trump::Chosen);
+            let mut game = Engine::with_seed(2).with_rules(rules);
+            for player in 0..4 {
+                game.add_player(&player.to_string()).unwrap();
+            }
+            let mut bot = Bot::new(difficulty, Style::Balanced).with_seed(2);
+            while let Ok(player) = game.turn() {
+                let view = game.build_view_for(&player).unwrap();
+                if game.is_choosing_trump() {
+                    game.choose_trump(&player, bot.choose_trump(&view)).unwrap();
+                } else if view.rounds.last().unwrap().tricks.is_empty() {
+                    game.call(&player, bot.call(&view)).unwrap();
+                } else {
+                    game.play(&player, bot.play(&view)).unwrap();
+                }
+            }
+            assert!(game.is_over());
+        }
+    }
+
+    #[test]
+    fn claims_are_always_upheld() {
+        let mut claims = 0;
+        for seed in 0..10 {
+            let mut bot = Bot::new(Difficulty::Intermediate, Style::Balanced).with_seed(seed);
+            let mut game = Engine::with_seed(seed);
+            for player in 0..4 {
+                game.add_player(&player.to_string()).unwrap();
+            }
+            while let Ok(player) = game.turn() {
+                let view = game.build_view_for(&player).unwrap();
+                if view.rounds.last().unwrap().tricks.is_empty() {
+                    game.call(&player, bot.call(&view)).unwrap();
+                } else if let Some(claim) = bot.claim(&view) {
+                    game.claim(&player, claim).unwrap();
+                    claims += 1;
+                } else {
+                    game.play(&player, bot.play(&view)).unwrap();
+                }
+            }
+        }
+        assert!(claims > 0);
+    }
+
+    #[test]
+    fn calls_nil_on_a_hopeless_hand_only_when_allowed() {
+        use crate::agent::{AgentKind, Budget};
+        use crate::game::Rules;
+        let mut hand: Vec<Card> = [Suit::Hearts, Suit::Diamonds, Suit::Clubs]
+            .iter()
+            .flat_map(|suit| Rank::ALL[..4].iter().map(|rank| Card::new(*rank, *suit)))
+            .collect();
+        hand.push(Card::new(Rank::ALL[0], Suit::Spades));
+        let mut view = Game::of_round([None; 4], hand, vec![]);
+        // both the weighed up calls of a bot and the search's own
+        let call = |view: &Game| {
+            [
+                AgentKind::Bot(Bot::new(Difficulty::Intermediate, Style::Balanced)),
+                AgentKind::Ismcts(Ismcts::new(Budget::Iterations(50)).with_seed(41)),
+            ]
+            .map(|mut agent| agent.call(view))
+        };
+        assert!(call(&view).iter().all(|call| !call.is_nil()));
+        view.rules = Rules::default().with_nil();
+        assert!(call(&view).iter().all(|call| call.is_nil()));
+    }
+
+    #[test]
+    fn styles_shift_the_call() {
+        let mut game = Engine::default();
+        for player in 0..4 {
+            game.add_player(&player.to_string()).unwrap();
+        }
+        let player = game.turn().unwrap();
+        let view = game.build_view_for(&player).unwrap();
+        let call = |style| Bot::new(Difficulty::Intermediate, style).call(&view);
+        assert!(call(Style::Aggressive) >= call(Style::Balanced));
+        assert!(call(Style::Cautious) <= call(Style::Balanced));
+    }
+
+    #[test]
+    fn calling_model_replaces_the_heuristics() {
+        let mut game = Engine::default();
+        for player in 0..4 {
+            game.add_player(&player.to_string()).unwrap();
+        }
+        let player = game.turn().unwrap();
+        let view = game.build_view_for(&player).unwrap();
+        // a model that is sure of every hand taking 9 tricks
+        let mut json: serde_json::Value =
+            serde_json::from_str(&CallingModel::default().to_json()).unwrap();
+        json["bias"][9] = 50.0.into();
+        let model = Arc::new(CallingModel::from_json(&json.to_string()).unwrap());
+        let mut bot = Bot::new(Difficulty::Intermediate, Style::Balanced).with_calling_model(model);
+        assert!(bot.call(&view).value() >= 8);
+    }
+
+    #[test]
+    fn explains_its_decisions_only_when_asked() {
+        let mut game = Engine::default();
+        for player in 0..4 {
+            game.add_player(&player.to_string()).unwrap();
+        }
+        let player = game.turn().unwrap();
+        let view = game.build_view_for(&player).unwrap();
+        let mut quiet = Bot::new(Difficulty::Intermediate, Style::Balanced);
+        quiet.call(&view);
+        assert_eq!(quiet.take_explanation(), None);
+
+        let mut bot = Bot::new(Difficulty::Intermediate, Style::Balanced).with_explanations();
+        let call = bot.call(&view);
+        let explanation = bot.take_explanation().unwrap();
+        assert_eq!(explanation.choice, Choice::Call(call));
+        assert_eq!(explanation.candidates.len(), 13);
+        assert_eq!(bot.take_explanation(), None);
+        for _ in 0..4 {
+            let player = game.turn().unwrap();
+            let view = game.build_view_for(&player).unwrap();
+            game.call(&player, bot.call(&view)).unwrap();
+        }
+
+        let player = game.turn().unwrap();
+        let view = game.build_view_for(&player).unwrap();
+        let card = bot.play(&view);
+        let explanation = bot.take_explanation().unwrap();
+        assert_eq!(explanation.choice, Choice::Play(card));
+        assert_eq!(explanation.candidates[0].choice, Choice::Play(card));
+        assert_eq!(
+            explanation.candidates.len(),
+            game.get_valid_moves(&player).unwrap().len()
+        );
+        assert!(!explanation.holdings.is_empty());
+    }
+
+    #[test]
+    fn expert_explains_its_search() {
+        let mut game = Engine::default();
+        for player in 0..4 {
+            game.add_player(&player.to_string()).unwrap();
+        }
+        for _ in 0..4 {
+            let player = game.turn().unwrap();
+            game.call(&player, Call::new(3).unwrap()).unwrap();
+        }
+        let player = game.turn().unwrap();
+        let view = game.build_view_for(&player).unwrap();
+        let mut bot = Bot::new(Difficulty::Expert, Style::Balanced)
+            .with_search(Ismcts::new(crate::agent::Budget::Iterations(100)))
+            .with_explanations();
+        let card = bot.play(&view);
+        let explanation = bot.take_explanation().unwrap();
+        assert_eq!(explanation.candidates[0].choice, Choice::Play(card));
+        assert!(matches!(
+            explanation.candidates[0].stats,
+            Stats::Search { visits, .. } if visits > 0.0
+        ));
+    }
+
+    #[test]
+    fn hands_are_weighed_with_the_trump_of_the_round() {
+        let hand: Vec<Card> = [Rank::Ace, Rank::King, Rank::Queen, Rank::Five, Rank::Four]
+            .map(|rank| Card::new(rank, Suit::Hearts))
+            .into_iter()
+            .chain([Card::new(Rank::Ace, Suit::Clubs)])
+            .collect();
+        assert_eq!(count_winners(&hand, Suit::Hearts), 6);
+        assert_eq!(count_winners(&hand, Suit::Spades), 3);
+        assert_eq!(
+            count_winners(&hand, Suit::Hearts),
+            count_winners(&as_spades(&hand, Suit::Hearts), Suit::Spades)
+        );
+    }
+
+    #[test]
+    fn bot_is_labelled_with_its_configuration() {
+        let bot = Bot::new(Difficulty::Expert, Style::SpadeHoarder);
+        assert_eq!(bot.to_string(), "expert, spade-hoarder");
     }
 }
@@ -0,0 +1,348 @@
+use crate::game::trump;
+use crate::game::{Call, Card, Rank, RoundOutcome, Rules, Ruleset, Suit, TrickTakingRules};
+use rand::Rng;
+use rand::distr::{Distribution, weighted::WeightedIndex};
+
+// INFO: the numbers below are rough guesses from playing, not measured. They are good enough
+// to rank hands and calls against each other, which is all that the bots need from them.
+
+// how often a call of 1, 2, ... is made by a player we know nothing about
+const TYPICAL_CALLS: [f64; 6] = [0.1, 0.25, 0.3, 0.2, 0.1, 0.05];
+// how often a call is met
+pub(super) const CALL_MADE: f64 = 0.7;
+// number of imagined games used to weigh up the calls
+const SAMPLES: usize = 2000;
+
+/// Chance of each card, and of each spare trump, in the hand turning into a trick with `trump`
+/// as the trump suit
+fn trick_chances(hand: &[Card], trump: Suit) -> Vec<f64> {
+    let trumps = hand.iter().filter(|c| c.get_suit() == trump).count();
+    let mut chances = vec![];
+    for suit in Suit::ALL {
+        let cards: Vec<&Card> = hand
+            .iter()
+            .filter(|card| card.get_suit() == *suit)
+            .collect();
+        let length = cards.len();
+        for card in cards.iter() {
+            chances.push(match (card.get_rank(), *suit == trump) {
+                (Rank::Ace, _) => 0.95,
+                (Rank::King, true) => 0.85,
+                (Rank::King, false) if (2..=5).contains(&length) => 0.7,
+                (Rank::King, false) => 0.3,
+                (Rank::Queen, true) if length >= 3 => 0.6,
+                (Rank::Queen, false) if (3..=4).contains(&length) => 0.35,
+                _ => 0.0,
+            });
+        }
+        if *suit == trump {
+            chances.extend((3..length).map(|_| 0.8));
+        } else if trumps > 3 {
+            // a short side suit lets the trumps beyond the first three be used to trump
+            chances.push(match length {
+                0 => 0.8,
+                1 => 0.5,
+                2 => 0.2,
+                _ => 0.0,
+            });
+        }
+    }
+    chances
+}
+
+/// Probability of the hand taking exactly `k` tricks with `trump` as the trump suit, for every
+/// `k` from 0 to 13
+pub(super) fn trick_distribution(hand: &[Card], trump: Suit) -> [f64; 14] {
+    let mut distribution = [0.0; 14];
+    distribution[0] = 1.0;
+    for chance in trick_chances(hand, trump) {
+        for k in (1..14).rev() {
+            distribution[k] = distribution[k] * (1.0 - chance) + distribution[k - 1] * chance;
+        }
+        distribution[0] *= 1.0 - chance;
+    }
+    distribution
+}
+
+/// The hand with `trump` and spades swapped, for the calling model that is trained with spades as
+/// trump
+pub(super) fn as_spades(hand: &[Card], trump: Suit) -> Vec<Card> {
+    hand.iter()
+        .map(|card| trump::as_spades(*card, trump))
+        .collect()
+}
+
+/// Tricks the hand is expected to take with `trump` as the trump suit
+pub(super) fn trump_worth(hand: &[Card], trump: Suit) -> f64 {
+    expected_tricks(&trick_distribution(hand, trump))
+}
+
+/// Trump the hand is expected to take the most tricks with, for the highest caller to pick
+pub(super) fn best_trump(hand: &[Card]) -> Suit {
+    *Suit::ALL
+        .iter()
+        .max_by(|a, b| trump_worth(hand, **a).total_cmp(&trump_worth(hand, **b)))
+        .expect("there must be suits")
+}
+
+pub(super) fn expected_tricks(distribution: &[f64; 14]) -> f64 {
+    distribution
+        .iter()
+        .enumerate()
+        .map(|(tricks, p)| tricks as f64 * p)
+        .sum()
+}
+
+/// Where the game stands as the player makes their call
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Standings {
+    pub(super) seat: usize,
+    pub(super) scores: [f32; 4],
+    pub(super) calls: [Option<Call>; 4],
+    /// Chance of each seat meeting its call
+    pub(super) made: [f64; 4],
+    /// Rounds still to be played after the current one
+    pub(super) rounds_left: usize,
+    /// The imagined rounds are scored the way the game is, so that they add up with the scores
+    pub(super) rules: Rules,
+    pub(super) ruleset: Ruleset,
+}
+
+// a call and the tricks taken on it, by a player who meets their calls `made` of the time
+fn sample_outcome<R: Rng + ?Sized>(call: Option<Call>, made: f64, rng: &mut R) -> (Call, u8) {
+    let call = match call {
+        Some(call) => call,
+        None => {
+            let calls = WeightedIndex::new(TYPICAL_CALLS).expect("must be valid weights");
+            Call::new(calls.sample(rng) as u8 + 1).expect("must be a valid call")
+        }
+    };
+    let tricks = match (rng.random_bool(made), call.is_nil()) {
+        (true, true) => 0,
+        (true, false) => call.value() + rng.random_range(0..=1),
+        (false, true) => 1,
+        (false, false) => 0,
+    };
+    (call, tricks)
+}
+
+// points each seat earns on the imagined calls and tricks of a round
+fn sampled_scores(standings: &Standings, (calls, won): &RoundOutcome) -> [f32; 4] {
+    standings
+        .ruleset
+        .round_scores(&standings.rules, calls, *won)
+        .unwrap_or_default()
+}
+
+/// How a call fared over the imagined games
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(super) struct Weighed {
+    pub(super) call: Call,
+    pub(super) win_chance: f64,
+    pub(super) expected_points: f64,
+}
+
+/// Call that gives the best chance of winning the game, which is not always the call
+/// that is worth the most points this round: a player far behind late in the game has to
+/// gamble on a higher call, while the leader can afford to play it safe. Every call is
+/// returned along with how it fared.
+pub(super) fn choose_call<R: Rng + ?Sized>(
+    distribution: &[f64; 14],
+    standings: &Standings,
+    rng: &mut R,
+) -> (Call, Vec<Weighed>) {
+    let tricks = WeightedIndex::new(distribution).expect("must be a valid distribution");
+    let calls: Vec<Call> = (1..=13).map(|v| Call::new(v).unwrap()).collect();
+    let mut wins = vec![0.0; calls.len()];
+    let mut points = vec![0.0; calls.len()];
+    let (me, seats) = (standings.seat, standings.rules.seats.count());
+    // a partner wins or loses along with the player
+    let others: Vec<usize> = (0..seats)
+        .filter(|seat| *seat != me && standings.rules.partner(me) != Some(*seat))
+        .collect();
+    let mut current: RoundOutcome = ([None; 4], [0; 4]);
+    let mut later: Vec<RoundOutcome> = vec![([None; 4], [0; 4]); standings.rounds_left];
+
+    // the same imagined games are used for every call so that only the call differs
+    for _ in 0..SAMPLES {
+        let taken = tricks.sample(rng) as u8;
+        for seat in 0..seats {
+            if seat != me {
+                let (call, won) = sample_outcome(standings.calls[seat], standings.made[seat], rng);
+                (current.0[seat], current.1[seat]) = (Some(call), won);
+            }
+            for round in later.iter_mut() {
+                let (call, won) = sample_outcome(None, standings.made[seat], rng);
+                (round.0[seat], round.1[seat]) = (Some(call), won);
+            }
+        }
+        let mut totals = standings.scores;
+        for round in later.iter() {
+            for (total, score) in totals.iter_mut().zip(sampled_scores(standings, round)) {
+                *total += score;
+            }
+        }
+        current.1[me] = taken;
+        for (i, call) in calls.iter().enumerate() {
+            current.0[me] = Some(*call);
+            let scores = sampled_scores(standings, &current);
+            let best_other = others
+                .iter()
+                .map(|seat| totals[*seat] + scores[*seat])
+                .fold(f32::MIN, f32::max);
+            let mine = totals[me] + scores[me];
+            points[i] += scores[me] as f64;
+            if mine > best_other {
+                wins[i] += 1.0;
+            } else if mine == best_other {
+                wins[i] += 0.5;
+            }
+        }
+    }
+
+    // points only break ties, e.g. when the game is already decided either way
+    let value = |i: usize| wins[i] / SAMPLES as f64 + 1e-3 * points[i] / SAMPLES as f64;
+    let best = (0..calls.len())
+        .max_by(|a, b| value(*a).total_cmp(&value(*b)))
+        .expect("must have a call to make");
+    let weighed = calls
+        .iter()
+        .enumerate()
+        .map(|(i, call)| Weighed {
+            call: *call,
+            win_chance: wins[i] / SAMPLES as f64,
+            expected_points: points[i] / SAMPLES as f64,
+        })
+        .collect();
+    (calls[best], weighed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Rank::*;
+    use crate::game::Spades as SpadesRules;
+    use crate::game::Suit::*;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    fn strong_hand() -> Vec<Card> {
+        vec![
+            Card::new(Ace, Spades),
+            Card::new(King, Spades),
+            Card::new(Queen, Spades),
+            Card::new(Nine, Spades),
+            Card::new(Five, Spades),
+            Card::new(Ace, Hearts),
+            Card::new(King, Hearts),
+            Card::new(Four, Hearts),
+            Card::new(Ace, Clubs),
+            Card::new(Three, Clubs),
+            Card::new(Ace, Diamonds),
+            Card::new(Seven, Diamonds),
+            Card::new(Two, Diamonds),
+        ]
+    }
+
+    fn weak_hand() -> Vec<Card> {
+        vec![
+            Card::new(Two, Spades),
+            Card::new(Jack, Hearts),
+            Card::new(Nine, Hearts),
+            Card::new(Four, Hearts),
+            Card::new(Three, Hearts),
+            Card::new(Ten, Clubs),
+            Card::new(Eight, Clubs),
+            Card::new(Three, Clubs),
+            Card::new(Two, Clubs),
+            Card::new(Nine, Diamonds),
+            Card::new(Seven, Diamonds),
+            Card::new(Five, Diamonds),
+            Card::new(Two, Diamonds),
+        ]
+    }
+
+    #[test]
+    fn distribution_adds_up_to_one() {
+        for hand in [strong_hand(), weak_hand()] {
+            let total: f64 = trick_distribution(&hand, Suit::Spades).iter().sum();
+            assert!((total - 1.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn strong_hand_expects_more_tricks() {
+        let strong = expected_tricks(&trick_distribution(&strong_hand(), Suit::Spades));
+        let weak = expected_tricks(&trick_distribution(&weak_hand(), Suit::Spades));
+        assert!(strong > 5.0 && weak < 1.0);
+    }
+
+    #[test]
+    fn trailing_player_calls_higher_than_leader_in_the_last_round() {
+        let distribution = trick_distribution(&strong_hand(), Suit::Spades);
+        let mut rng = StdRng::seed_from_u64(28);
+        let behind = Standings {
+            seat: 0,
+            scores: [-4.0, 12.0, 3.0, 2.0],
+            calls: [None; 4],
+            made: [CALL_MADE; 4],
+            rounds_left: 0,
+            rules: Rules::default(),
+            ruleset: Ruleset::CallBreak,
+        };
+        let ahead = Standings {
+            scores: [12.0, -4.0, 3.0, 2.0],
+            ..behind
+        };
+        let behind = choose_call(&distribution, &behind, &mut rng).0;
+        let ahead = choose_call(&distribution, &ahead, &mut rng).0;
+        assert!(behind > ahead, "behind: {behind:?} ahead: {ahead:?}");
+    }
+
+    #[test]
+    fn leader_calls_higher_against_an_opponent_who_keeps_making_calls() {
+        let distribution = trick_distribution(&strong_hand(), Suit::Spades);
+        let mut rng = StdRng::seed_from_u64(29);
+        let reliable = Standings {
+            seat: 0,
+            scores: [10.0, 8.0, 0.0, 0.0],
+            calls: [None, Some(Call::new(7).unwrap()), None, None],
+            made: [CALL_MADE, 0.95, CALL_MADE, CALL_MADE],
+            rounds_left: 0,
+            rules: Rules::default(),
+            ruleset: Ruleset::CallBreak,
+        };
+        let unreliable = Standings {
+            made: [CALL_MADE, 0.05, CALL_MADE, CALL_MADE],
+            ..reliable
+        };
+        let reliable = choose_call(&distribution, &reliable, &mut rng).0;
+        let unreliable = choose_call(&distribution, &unreliable, &mut rng).0;
+        // an opponent who will make their 7 overtakes the leader unless they call big
+        assert!(
+            reliable > unreliable,
+            "reliable: {reliable:?} unreliable: {unreliable:?}"
+        );
+    }
+
+    #[test]
+    fn imagined_rounds_are_scored_by_the_ruleset() {
+        let distribution = trick_distribution(&strong_hand(), Suit::Spades);
+        let mut rng = StdRng::seed_from_u64(28);
+        let standings = Standings {
+            seat: 0,
+            scores: [0.0, 120.0, 30.0, 20.0],
+            calls: [None; 4],
+            made: [CALL_MADE; 4],
+            rounds_left: 0,
+            rules: Rules::default(),
+            ruleset: Ruleset::Spades(SpadesRules::default()),
+        };
+        let (_, weighed) = choose_call(&distribution, &standings, &mut rng);
+        let best = weighed
+            .iter()
+            .map(|weighed| weighed.expected_points)
+            .fold(f64::MIN, f64::max);
+        // points are in tens a trick, as the scores they are added to are
+        assert!(best > 20.0, "best expected points: {best}");
+    }
+}
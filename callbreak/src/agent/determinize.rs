@@ -0,0 +1,336 @@
+use super::opponent::{EARLY, OpponentModel};
+use super::view::Game;
+use crate::game::{
+    Call, Card, DROPPED, Hand, Rank, Round, Rules, Ruleset, Suit, TrickTakingRules, Turn,
+    tricks_in_round,
+};
+use rand::{Rng, seq::IndexedRandom, seq::SliceRandom};
+
+// INFO: cards are tracked as bits in a u64 here, laid out the same way as a Hand, so that
+// what a seat could still be holding is cheap to update and to query.
+
+/// Everything a player can infer about the hidden cards from their view of a round
+#[derive(Debug, Clone)]
+pub(crate) struct Determinizer {
+    seat: usize,
+    seats: usize,
+    starter: Turn,
+    hand: Vec<Card>,
+    calls: [Option<Call>; 4],
+    plays: Vec<(Turn, Card)>,
+    played: [Vec<Card>; 4],
+    unseen: Vec<Card>,
+    // cards each seat could still be holding given how they have followed suit so far
+    possible: [u64; 4],
+    // number of unseen cards each seat is still holding
+    missing: [usize; 4],
+    model: OpponentModel,
+    rules: Rules,
+    ruleset: Ruleset,
+    trump: Suit,
+}
+
+impl Determinizer {
+    const ATTEMPTS: usize = 64;
+
+    pub(crate) fn new(view: &Game) -> Option<Self> {
+        let seats = view.seats();
+        let seat = view.turn()?;
+        let round = view.rounds.last()?;
+        // INFO: a trump that is still to be chosen leaves the searching bots to their simpler
+        // calls as well
+        let trump = round.trump?;
+        let starter = Turn::new((view.rounds.len() - 1) % seats);
+
+        let mut plays = vec![];
+        let mut played: [Vec<Card>; 4] = Default::default();
+        let mut possible = [u64::MAX; 4];
+        for trick in round.tricks.iter() {
+            let mut led: Option<Card> = None;
+            let mut winning: Option<(Turn, Card)> = None;
+            for (turn, card) in trick.plays() {
+                plays.push((turn, card));
+                played[turn].push(card);
+                let (Some(led), Some((ahead, winner))) = (led, winning) else {
+                    (led, winning) = (Some(card), Some((turn, card)));
+                    continue;
+                };
+                // the rules force a player to follow suit, then to trump and to beat the
+                // winning card if they can. not doing so tells us what they do not have.
+                // a partner who is winning need not be beaten, and under Spades no one need
+                // be, so then only the suit tells
+                let free = view.rules.partner(turn.into()) == Some(ahead.into())
+                    || view.ruleset != Ruleset::CallBreak;
+                let mask = &mut possible[turn];
+                if card.get_suit() != led.get_suit() {
+                    *mask &= !led.get_suit().mask();
+                }
+                if !free {
+                    if card.get_suit() != led.get_suit() {
+                        if winner.get_suit() != trump {
+                            if card.get_suit() != trump {
+                                *mask &= !trump.mask();
+                            }
+                        } else if !view.ruleset.beats(card, winner, trump) {
+                            *mask &= !winner.above();
+                        }
+                    } else if winner.get_suit() == led.get_suit()
+                        && !view.ruleset.beats(card, winner, trump)
+                    {
+                        *mask &= !winner.above();
+                    }
+                }
+                if view.ruleset.beats(card, winner, trump) {
+                    winning = Some((turn, card));
+                }
+            }
+        }
+
+        // a table of three plays without the dropped card, so it is as good as seen
+        let dropped = if seats == 3 { DROPPED.bit() } else { 0 };
+        let seen = round
+            .hand
+            .iter()
+            .chain(plays.iter().map(|(_, card)| card))
+            .fold(dropped, |mask, card| mask | card.bit());
+        let unseen = Suit::ALL
+            .iter()
+            .flat_map(|suit| Rank::ALL.iter().map(|rank| Card::new(*rank, *suit)))
+            .filter(|card| seen & card.bit() == 0)
+            .collect();
+        let mut missing = [0; 4];
+        for (other, missing) in missing.iter_mut().enumerate().take(seats) {
+            if other != seat {
+                *missing = tricks_in_round(seats) - played[other].len();
+            }
+        }
+
+        Some(Self {
+            seat,
+            seats,
+            starter,
+            hand: round.hand.clone(),
+            calls: round.calls,
+            plays,
+            played,
+            unseen,
+            possible,
+            missing,
+            model: OpponentModel::observe(view),
+            rules: view.rules,
+            ruleset: view.ruleset,
+            trump,
+        })
+    }
+
+    pub(crate) fn seat(&self) -> usize {
+        self.seat
+    }
+
+    pub(super) fn model(&self) -> &OpponentModel {
+        &self.model
+    }
+
+    // how likely the others are to have played the way they did had they been dealt these
+    // hands, going by how they have been playing so far
+    fn plausibility(&self, hands: &[Vec<Card>; 4]) -> f64 {
+        let mut held = hands
+            .clone()
+            .map(|hand| hand.iter().fold(0, |mask, card| mask | card.bit()));
+        let mut weight = 1.0;
+        for (i, (turn, card)) in self.plays.iter().enumerate() {
+            let seat = usize::from(*turn);
+            let profile = self.model.seat(seat);
+            // every trick before the current one is complete, so leads come every `seats` plays
+            if i % self.seats == 0 && seat != self.seat && card.get_suit() != self.trump {
+                let honours = Card::new(Rank::Ace, card.get_suit()).bit()
+                    | Card::new(Rank::King, card.get_suit()).bit();
+                if card.get_rank() < Rank::Jack && held[seat] & honours != 0 {
+                    weight *= 1.0 - profile.high_lead_rate();
+                }
+                let trumps = (held[seat] & self.trump.mask()).count_ones();
+                if i / self.seats < EARLY && trumps >= 5 {
+                    weight *= 1.0 - profile.early_spade_rate();
+                }
+            }
+            held[seat] &= !card.bit();
+        }
+        weight
+    }
+
+    /// Deal the unseen cards to the other seats in a way that agrees with everything
+    /// observed so far, and replay the round up to where the view currently is.
+    pub(crate) fn sample<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<Round<Ruleset>> {
+        let mut fallback: Option<[Vec<Card>; 4]> = None;
+        for _ in 0..Self::ATTEMPTS {
+            let Some(hands) = self.deal(rng) else {
+                continue;
+            };
+            // prefer deals that would not have been thrown in for a redeal
+            let size = tricks_in_round(self.seats);
+            if !hands
+                .iter()
+                .take(self.seats)
+                .all(|hand| Hand::dealt(hand, size, Some(self.trump)).is_ok())
+            {
+                fallback.get_or_insert(hands);
+                continue;
+            }
+            if rng.random_bool(self.plausibility(&hands)) {
+                return self.replay(hands);
+            }
+            fallback = Some(hands);
+        }
+        fallback.and_then(|hands| self.replay(hands))
+    }
+
+    fn deal<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<[Vec<Card>; 4]> {
+        let mut cards = self.unseen.clone();
+        cards.shuffle(rng);
+        // hand out the cards with the fewest possible owners first
+        cards.sort_by_key(|card| {
+            self.possible
+                .iter()
+                .zip(self.missing)
+                .filter(|(possible, missing)| *missing > 0 && *possible & card.bit() != 0)
+                .count()
+        });
+
+        let mut left = self.missing;
+        let mut hands: [Vec<Card>; 4] = self.played.clone();
+        for card in cards {
+            let seats: Vec<usize> = (0..self.seats)
+                .filter(|&seat| left[seat] > 0 && self.possible[seat] & card.bit() != 0)
+                .collect();
+            let seat = *seats.choose_weighted(rng, |&seat| left[seat]).ok()?;
+            hands[seat].push(card);
+            left[seat] -= 1;
+        }
+        hands[self.seat].extend(self.hand.iter());
+        Some(hands)
+    }
+
+    fn replay(&self, hands: [Vec<Card>; 4]) -> Option<Round<Ruleset>> {
+        let mut round = Round::from_hands(self.seats, self.starter, hands.map(Hand::new))
+            .with_ruleset(self.ruleset)
+            .with_rules(&self.rules)
+            .with_trump(self.trump);
+        // sealed calls are not all known while calling, so the known ones are made in turn
+        for turn in (0..self.seats).map(|i| self.starter.after(i, self.seats)) {
+            if let Some(call) = self.calls[turn] {
+                round.call(call, turn).ok()?;
+            }
+        }
+        for (turn, card) in self.plays.iter() {
+            round.play(*card, *turn).ok()?;
+        }
+        Some(round)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Game as Engine, Seats};
+
+    fn engine_after(plays: usize) -> (Engine, String) {
+        let mut game = Engine::default();
+        for player in 0..4 {
+            game.add_player(&player.to_string()).unwrap();
+        }
+        for _ in 0..4 {
+            let player = game.turn().unwrap();
+            game.call(&player, Call::new(2).unwrap()).unwrap();
+        }
+        for _ in 0..plays {
+            let player = game.turn().unwrap();
+            let moves = game.get_valid_moves(&player).unwrap();
+            game.play(&player, *moves.last().unwrap()).unwrap();
+        }
+        let player = game.turn().unwrap();
+        (game, player)
+    }
+
+    #[test]
+    fn sample_keeps_own_hand_and_history() {
+        let (game, player) = engine_after(22);
+        let view = game.build_view_for(&player).unwrap();
+        let determinizer = Determinizer::new(&view).unwrap();
+        let mut rng = rand::rng();
+        for _ in 0..20 {
+            let round = determinizer.sample(&mut rng).unwrap();
+            let hand: Vec<Card> = round
+                .get_hand(Turn::new(determinizer.seat()))
+                .iter()
+                .collect();
+            assert_eq!(hand, view.rounds[0].hand);
+            assert_eq!(
+                round.get_tricks().iter().flatten().count(),
+                view.rounds[0].tricks.len()
+            );
+        }
+    }
+
+    #[test]
+    fn deals_that_agree_with_the_history_are_plausible() {
+        let (game, player) = engine_after(40);
+        let view = game.build_view_for(&player).unwrap();
+        let determinizer = Determinizer::new(&view).unwrap();
+        let mut rng = rand::rng();
+        for _ in 0..20 {
+            // a deal may paint itself into a corner, which sample() retries
+            let Some(hands) = determinizer.deal(&mut rng) else {
+                continue;
+            };
+            let weight = determinizer.plausibility(&hands);
+            assert!(weight > 0.0 && weight <= 1.0);
+        }
+    }
+
+    #[test]
+    fn sample_respects_suits_a_seat_has_shown_out_of() {
+        let (game, player) = engine_after(30);
+        let view = game.build_view_for(&player).unwrap();
+        let determinizer = Determinizer::new(&view).unwrap();
+        let mut rng = rand::rng();
+        for _ in 0..20 {
+            let round = determinizer.sample(&mut rng).unwrap();
+            for seat in 0..4 {
+                for card in round.get_hand(Turn::new(seat)).iter() {
+                    assert!(
+                        seat == determinizer.seat()
+                            || determinizer.possible[seat] & card.bit() != 0
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn samples_a_table_of_three_without_the_dropped_card() {
+        let mut game = Engine::with_seed(43).with_rules(Rules::default().with_seats(Seats::Three));
+        for player in 0..3 {
+            game.add_player(&player.to_string()).unwrap();
+        }
+        for _ in 0..3 {
+            let player = game.turn().unwrap();
+            game.call(&player, Call::new(2).unwrap()).unwrap();
+        }
+        for _ in 0..20 {
+            let player = game.turn().unwrap();
+            let moves = game.get_valid_moves(&player).unwrap();
+            game.play(&player, *moves.last().unwrap()).unwrap();
+        }
+        let view = game.build_view_for(&game.turn().unwrap()).unwrap();
+        let determinizer = Determinizer::new(&view).unwrap();
+        let mut rng = rand::rng();
+        for _ in 0..20 {
+            let round = determinizer.sample(&mut rng).unwrap();
+            let dealt = round.dealt();
+            assert!(dealt[..3].iter().all(|hand| hand.len() == 17));
+            assert!(dealt[3].is_empty());
+            assert!(dealt.iter().flatten().all(|card| *card != DROPPED));
+            assert_eq!(round.get_tricks().iter().flatten().count(), 7);
+        }
+    }
+}
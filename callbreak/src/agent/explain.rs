@@ -0,0 +1,167 @@
+use super::determinize::Determinizer;
+use super::view::Game;
+use crate::game::{Call, Card, Suit, Turn};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+// number of deals sampled to guess at what the others are holding
+const SAMPLES: usize = 200;
+// chance below which a card is left out of a seat's likely holdings
+const UNLIKELY: f64 = 0.05;
+
+/// Call or play a decision was about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Choice {
+    Call(Call),
+    Play(Card),
+    Trump(Suit),
+}
+
+/// What went into scoring a candidate
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Stats {
+    /// Scored by the hand written rules of the simpler bots
+    Heuristic {
+        /// Whether the card beats everything in the trick so far
+        wins_trick: bool,
+        /// Whether every higher card of its suit is already out or in the same hand
+        master: bool,
+    },
+    /// Scored by searching the rest of the round
+    Search { visits: f64, mean_reward: f64 },
+    /// Scored by imagining how the rest of the game goes with this call
+    Calling {
+        win_chance: f64,
+        expected_points: f64,
+    },
+    /// Scored by the tricks the hand is expected to take with the suit as trump
+    Trump { expected_tricks: f64 },
+}
+
+/// A move that was considered, and how it was scored
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Candidate {
+    pub choice: Choice,
+    /// Higher is better, only comparable between candidates of the same decision
+    pub score: f64,
+    pub stats: Stats,
+}
+
+/// Chance of a seat still holding a card
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Holding {
+    pub seat: usize,
+    pub card: Card,
+    pub chance: f64,
+}
+
+/// Why a bot took a decision, for a "why did it play that?" panel
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Explanation {
+    pub player: usize,
+    pub choice: Choice,
+    /// Rule or search that picked the choice, in a few words
+    pub reason: String,
+    /// Best candidate first
+    pub candidates: Vec<Candidate>,
+    /// Cards the other seats are likely to be holding, most likely first
+    pub holdings: Vec<Holding>,
+}
+
+impl Explanation {
+    pub(super) fn new(
+        view: &Game,
+        choice: Choice,
+        reason: impl Into<String>,
+        mut candidates: Vec<Candidate>,
+        rng: &mut impl Rng,
+    ) -> Self {
+        // the choice goes first among candidates that scored the same
+        candidates.sort_by(|a, b| {
+            (b.score, b.choice == choice)
+                .partial_cmp(&(a.score, a.choice == choice))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        Self {
+            player: view.turn().unwrap_or_default(),
+            choice,
+            reason: reason.into(),
+            candidates,
+            holdings: holdings(view, rng),
+        }
+    }
+}
+
+// share of the sampled deals in which each of the others holds each card
+fn holdings(view: &Game, rng: &mut impl Rng) -> Vec<Holding> {
+    let Some(determinizer) = Determinizer::new(view) else {
+        return vec![];
+    };
+    let mut counts = [[0u32; 64]; 4];
+    let mut sampled = 0;
+    for _ in 0..SAMPLES {
+        let Some(round) = determinizer.sample(rng) else {
+            continue;
+        };
+        sampled += 1;
+        for (seat, counts) in counts.iter_mut().enumerate() {
+            if seat == determinizer.seat() {
+                continue;
+            }
+            let held = round.get_hand(Turn::new(seat)).bits();
+            for (bit, count) in counts.iter_mut().enumerate() {
+                *count += (held >> bit & 1) as u32;
+            }
+        }
+    }
+    if sampled == 0 {
+        return vec![];
+    }
+    let mut holdings: Vec<Holding> = counts
+        .iter()
+        .enumerate()
+        .flat_map(|(seat, counts)| {
+            counts
+                .iter()
+                .enumerate()
+                .filter(|(_, count)| **count > 0)
+                .map(move |(bit, count)| Holding {
+                    seat,
+                    card: Card::from_index(bit as u32),
+                    chance: *count as f64 / sampled as f64,
+                })
+        })
+        .filter(|holding| holding.chance >= UNLIKELY)
+        .collect();
+    holdings.sort_by(|a, b| b.chance.total_cmp(&a.chance));
+    holdings
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game as Engine;
+
+    #[test]
+    fn holdings_only_name_cards_the_player_cannot_see() {
+        let mut game = Engine::default();
+        for player in 0..4 {
+            game.add_player(&player.to_string()).unwrap();
+        }
+        for _ in 0..4 {
+            let player = game.turn().unwrap();
+            game.call(&player, Call::new(3).unwrap()).unwrap();
+        }
+        let player = game.turn().unwrap();
+        let view = game.build_view_for(&player).unwrap();
+        let holdings = holdings(&view, &mut rand::rng());
+        let hand = &view.rounds[0].hand;
+        assert!(!holdings.is_empty());
+        assert!(holdings.iter().all(|h| h.seat != view.turn().unwrap()));
+        assert!(holdings.iter().all(|h| !hand.contains(&h.card)));
+        // nothing has been played, so every unseen card is as likely with anyone
+        assert!(holdings.iter().all(|h| (0.15..0.55).contains(&h.chance)));
+    }
+}
@@ -0,0 +1,86 @@
+use super::bot::{Bot, Difficulty, Style};
+use super::explain::Choice;
+use super::view::Game;
+use serde::{Deserialize, Serialize};
+
+/// Call or card suggested to a player, with a short reason
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Hint {
+    pub choice: Choice,
+    pub reason: String,
+}
+
+/// Suggests what to do next by asking a bot what it would do in the player's place
+#[derive(Debug)]
+pub struct Hinter {
+    bot: Bot,
+}
+
+impl Default for Hinter {
+    fn default() -> Self {
+        Self::new(Difficulty::Intermediate)
+    }
+}
+
+impl Hinter {
+    pub fn new(difficulty: Difficulty) -> Self {
+        Self {
+            bot: Bot::new(difficulty, Style::Balanced).with_explanations(),
+        }
+    }
+
+    /// Suggestion for the player the view belongs to, which must be a view taken on their turn
+    /// as it is when they are asked to act
+    pub fn suggest(&mut self, view: &Game) -> Option<Hint> {
+        let round = view.rounds.last()?;
+        view.turn()?;
+        let called = round.calls[..view.seats()].iter().all(Option::is_some);
+        if round.tricks.is_empty() && called {
+            self.bot.choose_trump(view);
+        } else if round.tricks.is_empty() {
+            self.bot.call(view);
+        } else {
+            self.bot.play(view);
+        }
+        let explanation = self.bot.take_explanation()?;
+        Some(Hint {
+            choice: explanation.choice,
+            reason: explanation.reason,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Call, Game as Engine};
+
+    #[test]
+    fn suggests_a_call_and_then_a_valid_card() {
+        let mut game = Engine::default();
+        for player in 0..4 {
+            game.add_player(&player.to_string()).unwrap();
+        }
+        let mut hinter = Hinter::default();
+        let player = game.turn().unwrap();
+        let view = game.build_view_for(&player).unwrap();
+        let hint = hinter.suggest(&view).unwrap();
+        assert!(matches!(hint.choice, Choice::Call(_)));
+        assert!(!hint.reason.is_empty());
+
+        for _ in 0..4 {
+            let player = game.turn().unwrap();
+            game.call(&player, Call::new(2).unwrap()).unwrap();
+        }
+        let player = game.turn().unwrap();
+        let view = game.build_view_for(&player).unwrap();
+        let Some(Hint {
+            choice: Choice::Play(card),
+            ..
+        }) = hinter.suggest(&view)
+        else {
+            panic!("must suggest a card to play");
+        };
+        assert!(game.get_valid_moves(&player).unwrap().contains(&card));
+    }
+}
@@ -1,8 +1,11 @@
 use tracing::debug;
 
+use super::Move;
+use super::explain::Explanation;
+use super::hint::Hinter;
 use super::view::Game;
-use super::view::{Action, ClientMessage, ServerMessage};
-use crate::game::{Call, Card};
+use super::view::{Action, ClientMessage, Results, ServerMessage};
+use crate::game::{CLAIM_TRICKS, Call, Suit, TrickTakingRules};
 use std::fmt::Debug;
 
 /// Mechanism for communication with a human
@@ -15,58 +18,242 @@ pub trait Transport: Send + Debug {
 #[derive(Debug)]
 pub struct Human {
     transport: Box<dyn Transport>,
+    // explanations of the bots' decisions since the last message
+    explanations: Vec<Explanation>,
+    // answers requests for a hint, when hints are allowed
+    hinter: Option<Hinter>,
+    // why the last claim was turned down, until the human is told
+    rejected_claim: Option<String>,
 }
 
 impl Human {
     pub fn new(transport: Box<dyn Transport>) -> Self {
-        Self { transport }
+        Self {
+            transport,
+            explanations: vec![],
+            hinter: None,
+            rejected_claim: None,
+        }
     }
 
-    pub(super) fn call(&mut self, view: &Game) -> Call {
+    /// Answer the human's requests for a hint, which are otherwise ignored
+    pub fn with_hints(mut self) -> Self {
+        self.hinter = Some(Hinter::default());
+        self
+    }
+
+    pub(super) fn reject_claim(&mut self, reason: crate::Error) {
+        let reason = match reason {
+            crate::Error::ClaimTooEarly => {
+                format!("a claim can only be made in the last {CLAIM_TRICKS} tricks")
+            }
+            crate::Error::ClaimExceedsTricksLeft => {
+                "there are not that many tricks left to claim".to_string()
+            }
+            crate::Error::ClaimNotGuaranteed => {
+                "the other players could still take some of the claimed tricks".to_string()
+            }
+            reason => reason.to_string(),
+        };
+        self.rejected_claim = Some(reason);
+    }
+
+    pub(super) fn share_explanation(&mut self, explanation: Explanation) {
+        self.explanations.push(explanation);
+    }
+
+    // send the request for action and wait for an answer to it, handing out hints on the way.
+    // None when the human can no longer be reached.
+    fn ask(&mut self, action: Action, view: &Game) -> Option<ClientMessage> {
+        let mut message = ServerMessage {
+            action,
+            view: view.clone(),
+            explanations: std::mem::take(&mut self.explanations),
+            hint: None,
+            rejected_claim: self.rejected_claim.take(),
+            results: None,
+        };
+        loop {
+            // FIXME: because others are allowed to implement the transport
+            // may be this should have a timeout? But also only we would
+            // implement timer for our instance of this game so perhaps it is okay to implement
+            // timer in the api? might still be cool to take the timeout as a parameter for Human
+            // and use that as a timeout here though.
+            self.transport.send(message.clone())?;
+            let reply = self.transport.receive();
+            debug!(?reply);
+            if !matches!(reply, ClientMessage::Hint) {
+                return Some(reply);
+            }
+            message.explanations.clear();
+            message.rejected_claim = None;
+            message.hint = self.hinter.as_mut().and_then(|hinter| hinter.suggest(view));
+        }
+    }
+
+    // tell the human how the game went, nothing is expected back
+    pub(super) fn announce(&mut self, view: &Game, results: Results) {
         let message = ServerMessage {
-            action: Action::Call,
+            action: Action::Over,
             view: view.clone(),
+            explanations: std::mem::take(&mut self.explanations),
+            hint: None,
+            rejected_claim: None,
+            results: Some(results),
         };
-        // FIXME: because others are allowed to implement the transport
-        // may be this should have a timeout? But also only we would
-        // implement timer for our instance of this game so perhaps it is okay to implement timer
-        // in the api? might still be cool to take the timeout as a parameter for Human
-        // and use that as a timeout here though. Same goes for play()
         if self.transport.send(message).is_none() {
-            let bot = super::Bot;
-            return bot.call(view);
+            debug!("the human left before the game was over");
         }
+    }
 
-        let message = self.transport.receive();
-        debug!(?message);
-        match message {
-            ClientMessage::Call(v) => v,
+    pub(super) fn call(&mut self, view: &Game) -> Call {
+        match self.ask(Action::Call, view) {
+            Some(ClientMessage::Call(v)) if view.ruleset.check_call(v, &view.rules).is_ok() => v,
             _ => {
-                let bot = super::Bot;
+                let mut bot = super::Bot::default();
                 bot.call(view)
             }
         }
     }
 
-    pub(super) fn play(&mut self, view: &Game) -> Card {
-        let message = ServerMessage {
-            action: Action::Break,
-            view: view.clone(),
-        };
-        if self.transport.send(message).is_none() {
-            let bot = super::Bot;
-            return bot.play(view);
+    pub(super) fn choose_trump(&mut self, view: &Game) -> Suit {
+        match self.ask(Action::Trump, view) {
+            Some(ClientMessage::Trump(trump)) => trump,
+            _ => {
+                let mut bot = super::Bot::default();
+                bot.choose_trump(view)
+            }
         }
-        let message = self.transport.receive();
-        debug!(?message);
-        match message {
+    }
+
+    pub(super) fn play(&mut self, view: &Game) -> Move {
+        match self.ask(Action::Break, view) {
             // FIXME: this card should be in the list of valid moves or else we should use a bot to
             // provide a valid move
-            ClientMessage::Break(card) => card,
+            Some(ClientMessage::Break(card)) => Move::Play(card),
+            Some(ClientMessage::Claim(claim)) => Move::Claim(claim),
             _ => {
-                let bot = super::Bot;
-                bot.play(view)
+                let mut bot = super::Bot::default();
+                Move::Play(bot.play(view))
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game as Engine;
+    use std::sync::{Arc, Mutex};
+
+    // answers with a hint request first, then with whatever was suggested
+    #[derive(Debug, Default)]
+    struct Learner {
+        sent: Arc<Mutex<Vec<ServerMessage>>>,
+    }
+
+    impl Transport for Learner {
+        fn send(&mut self, message: ServerMessage) -> Option<()> {
+            self.sent.lock().unwrap().push(message);
+            Some(())
+        }
+
+        fn receive(&mut self) -> ClientMessage {
+            let sent = self.sent.lock().unwrap();
+            match sent.last().and_then(|message| message.hint.as_ref()) {
+                Some(hint) => match hint.choice {
+                    crate::agent::Choice::Call(call) => ClientMessage::Call(call),
+                    crate::agent::Choice::Play(card) => ClientMessage::Break(card),
+                    crate::agent::Choice::Trump(trump) => ClientMessage::Trump(trump),
+                },
+                None if sent.len() == 1 => ClientMessage::Hint,
+                None => ClientMessage::Call(Call::new(1).unwrap()),
+            }
+        }
+    }
+
+    // calls nil whatever the table allows
+    #[derive(Debug)]
+    struct Nil;
+
+    impl Transport for Nil {
+        fn send(&mut self, _: ServerMessage) -> Option<()> {
+            Some(())
+        }
+
+        fn receive(&mut self) -> ClientMessage {
+            ClientMessage::Call(Call::nil())
+        }
+    }
+
+    fn first_view() -> Game {
+        let mut game = Engine::default();
+        for player in 0..4 {
+            game.add_player(&player.to_string()).unwrap();
+        }
+        let player = game.turn().unwrap();
+        game.build_view_for(&player).unwrap()
+    }
+
+    #[test]
+    fn hint_is_sent_back_with_the_request() {
+        let learner = Learner::default();
+        let sent = learner.sent.clone();
+        let mut human = Human::new(Box::new(learner)).with_hints();
+        human.call(&first_view());
+        let sent = sent.lock().unwrap();
+        assert_eq!(sent.len(), 2);
+        assert!(sent[0].hint.is_none());
+        assert!(sent[1].hint.is_some());
+    }
+
+    #[test]
+    fn hints_are_not_given_unless_allowed() {
+        let learner = Learner::default();
+        let sent = learner.sent.clone();
+        let mut human = Human::new(Box::new(learner));
+        assert_eq!(human.call(&first_view()), Call::new(1).unwrap());
+        assert!(sent.lock().unwrap().iter().all(|m| m.hint.is_none()));
+    }
+
+    #[test]
+    fn nil_is_not_taken_at_a_table_without_it() {
+        let mut human = Human::new(Box::new(Nil));
+        let mut view = first_view();
+        assert!(!human.call(&view).is_nil());
+        view.rules.nil = true;
+        assert!(human.call(&view).is_nil());
+        // spades always allows nil
+        view.rules.nil = false;
+        view.ruleset = crate::game::Ruleset::Spades(Default::default());
+        assert!(human.call(&view).is_nil());
+    }
+
+    #[test]
+    fn rejected_claim_goes_out_with_the_next_request() {
+        let learner = Learner::default();
+        let sent = learner.sent.clone();
+        let mut human = Human::new(Box::new(learner));
+        human.reject_claim(crate::Error::ClaimNotGuaranteed);
+        human.call(&first_view());
+        human.call(&first_view());
+        let sent = sent.lock().unwrap();
+        assert_eq!(
+            sent[0].rejected_claim.as_deref(),
+            Some("the other players could still take some of the claimed tricks")
+        );
+        assert!(sent[1..].iter().all(|m| m.rejected_claim.is_none()));
+    }
+
+    #[test]
+    fn results_go_out_without_waiting_for_an_answer() {
+        let learner = Learner::default();
+        let sent = learner.sent.clone();
+        let mut human = Human::new(Box::new(learner));
+        human.announce(&first_view(), Results::default());
+        let sent = sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert!(matches!(sent[0].action, Action::Over));
+        assert!(sent[0].results.is_some());
+    }
+}
@@ -0,0 +1,493 @@
+use super::bot::count_winners;
+use super::calling::best_trump;
+use super::determinize::Determinizer;
+use super::explain::{Candidate, Choice, Explanation, Stats};
+use super::view::Game;
+use crate::game::{Call, Card, Hand, Round, Rules, Ruleset, Suit, TrickTakingRules, beats};
+use rand::{RngCore, SeedableRng, rngs::SmallRng, seq::IndexedRandom};
+use std::fmt::Debug;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// How much searching a single decision is allowed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Budget {
+    Iterations(usize),
+    Time(Duration),
+}
+
+/// Policy used to finish a sampled round once the search runs out of tree
+pub trait Rollout: Debug + Send {
+    /// Pick one of `moves` given the cards already in the current trick, in the order played,
+    /// and the trump of the round. The returned card must be one of `moves`.
+    fn play(&mut self, trick: &[Card], moves: &[Card], trump: Suit, rng: &mut dyn RngCore) -> Card;
+}
+
+/// Rollout that plays uniformly at random among the valid moves
+#[derive(Debug, Default, Clone, Copy)]
+pub struct RandomRollout;
+
+impl Rollout for RandomRollout {
+    fn play(
+        &mut self,
+        _trick: &[Card],
+        moves: &[Card],
+        _trump: Suit,
+        rng: &mut dyn RngCore,
+    ) -> Card {
+        *moves.choose(rng).expect("must have a valid move to play")
+    }
+}
+
+/// Rollout that wins a trick as cheaply as it can, and otherwise throws its lowest card
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GreedyRollout;
+
+impl Rollout for GreedyRollout {
+    fn play(
+        &mut self,
+        trick: &[Card],
+        moves: &[Card],
+        trump: Suit,
+        _rng: &mut dyn RngCore,
+    ) -> Card {
+        let cost = |card: &&Card| (card.get_suit() == trump, card.get_rank());
+        let winning = trick.iter().copied().reduce(|winning, card| {
+            if beats(card, winning, trump) {
+                card
+            } else {
+                winning
+            }
+        });
+        let choice = match winning {
+            // lead with the strongest card, keeping trumps back if possible
+            None => moves
+                .iter()
+                .max_by_key(|card| (card.get_suit() != trump, card.get_rank())),
+            Some(winning) => moves
+                .iter()
+                .filter(|card| beats(**card, winning, trump))
+                .min_by_key(cost)
+                .or_else(|| moves.iter().min_by_key(cost)),
+        };
+        *choice.expect("must have a valid move to play")
+    }
+}
+
+// rough guess at the call for a hand, used to fill in calls that are not yet known.
+// `bias` is how far over (or under, when negative) their calls the seat usually ends up.
+fn estimate_call(hand: &Hand, trump: Suit, bias: f64) -> Call {
+    let cards: Vec<Card> = hand.iter().collect();
+    let call = count_winners(&cards, trump) as f64 - bias.round();
+    Call::new(call.clamp(1.0, 13.0) as u8).expect("must be a valid call after clamping")
+}
+
+#[derive(Debug)]
+struct Node {
+    seat: usize,
+    card: Option<Card>,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    visits: f64,
+    available: f64,
+    reward: f64,
+}
+
+impl Node {
+    fn new(seat: usize, card: Option<Card>, parent: Option<usize>) -> Self {
+        Node {
+            seat,
+            card,
+            parent,
+            children: vec![],
+            visits: 0.0,
+            available: 0.0,
+            reward: 0.0,
+        }
+    }
+}
+
+/// Agent that runs Information Set Monte Carlo Tree Search, sampling the hidden cards
+/// anew for every iteration of the search
+#[derive(Debug)]
+pub struct Ismcts {
+    budget: Budget,
+    exploration: f64,
+    rollout: Box<dyn Rollout>,
+    rng: SmallRng,
+    explain: bool,
+    explanation: Option<Explanation>,
+}
+
+impl Default for Ismcts {
+    fn default() -> Self {
+        Self::new(Budget::Iterations(1000))
+    }
+}
+
+impl Ismcts {
+    pub fn new(budget: Budget) -> Self {
+        Self {
+            budget,
+            exploration: 0.7,
+            rollout: Box::new(GreedyRollout),
+            rng: SmallRng::from_os_rng(),
+            explain: false,
+            explanation: None,
+        }
+    }
+
+    pub fn with_rollout(mut self, rollout: impl Rollout + 'static) -> Self {
+        self.rollout = Box::new(rollout);
+        self
+    }
+
+    /// Weight of the exploration term in the UCB selection
+    pub fn with_exploration(mut self, exploration: f64) -> Self {
+        self.exploration = exploration;
+        self
+    }
+
+    pub fn budget(&self) -> Budget {
+        self.budget
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = SmallRng::seed_from_u64(seed);
+        self
+    }
+
+    /// Keep the statistics behind every decision, see [`Ismcts::take_explanation`]
+    pub fn with_explanations(mut self) -> Self {
+        self.explain = true;
+        self
+    }
+
+    /// Why the search took its last decision, if it was asked to explain itself
+    pub fn take_explanation(&mut self) -> Option<Explanation> {
+        self.explanation.take()
+    }
+
+    fn explained(&mut self, view: &Game, choice: Choice, reason: &str, candidates: Vec<Candidate>) {
+        let explanation = Explanation::new(view, choice, reason, candidates, &mut self.rng);
+        debug!(?explanation, "ismcts decision explained");
+        self.explanation = Some(explanation);
+    }
+
+    fn iterations(&self) -> impl FnMut() -> bool + use<> {
+        let budget = self.budget;
+        let start = Instant::now();
+        let mut done = 0;
+        move || {
+            done += 1;
+            match budget {
+                Budget::Iterations(n) => done <= n,
+                Budget::Time(limit) => done == 1 || start.elapsed() < limit,
+            }
+        }
+    }
+
+    // play the sampled round to the end using the rollout policy
+    fn rollout(&mut self, round: &mut Round<Ruleset>) {
+        // reused for every play so that the rollout does not allocate
+        let mut moves = Vec::with_capacity(13);
+        let mut trick = Vec::with_capacity(4);
+        let trump = round.trump().expect("a sampled round must have its trump");
+        while let Ok(turn) = round.turn() {
+            moves.clear();
+            moves.extend(
+                round
+                    .valid_moves(turn)
+                    .expect("must have valid moves on our turn")
+                    .iter(),
+            );
+            trick.clear();
+            if let Some(current) = round.current_trick() {
+                trick.extend(current.plays().map(|(_, card)| card));
+            }
+            let card = self.rollout.play(&trick, &moves, trump, &mut self.rng);
+            round
+                .play(card, turn)
+                .expect("rollout policy must return a valid move");
+        }
+    }
+
+    // reward of a finished round for each seat, scored by the ruleset of the game and brought
+    // roughly within [-1, 1] by what a trick is worth to it. partners share theirs, so the
+    // search plays for the team
+    fn rewards(round: &Round<Ruleset>, ruleset: &Ruleset, rules: &Rules) -> [f64; 4] {
+        let scale = 13.0 * ruleset.trick_value() as f64;
+        ruleset
+            .round_scores(rules, round.get_calls(), round.tricks_won())
+            .expect("a finished round must have all calls")
+            .map(|score| score as f64 / scale)
+    }
+
+    /// Share of the sampled rounds in which the player to call took each number of tricks
+    pub(super) fn trick_distribution(&mut self, view: &Game) -> Option<[f64; 14]> {
+        let determinizer = Determinizer::new(view)?;
+        let seat = determinizer.seat();
+        let mut taken = [0.0; 14];
+        let mut more = self.iterations();
+        while more() {
+            let Some(mut round) = determinizer.sample(&mut self.rng) else {
+                continue;
+            };
+            // the calls still to be made only matter for how the others score their rollout
+            while round.is_calling() {
+                let turn = round.turn().expect("must have a turn while calling");
+                let bias = determinizer.model().seat(turn.into()).call_bias();
+                let trump = round.trump().expect("a sampled round must have its trump");
+                round
+                    .call(estimate_call(round.get_hand(turn), trump, bias), turn)
+                    .expect("must accept a call on its turn");
+            }
+            self.rollout(&mut round);
+            // INFO: a table of three has more tricks than the highest call, those beyond it
+            // are counted with the last
+            taken[(round.tricks_won()[seat] as usize).min(13)] += 1.0;
+        }
+        let total: f64 = taken.iter().sum();
+        (total > 0.0).then(|| taken.map(|count| count / total))
+    }
+
+    /// Trump to pick as the highest caller. The search only starts once the trump is known,
+    /// so this goes by the hand alone
+    pub(super) fn choose_trump(&mut self, view: &Game) -> Suit {
+        let round = view
+            .rounds
+            .last()
+            .expect("must call choose_trump() on a valid round");
+        best_trump(&round.hand)
+    }
+
+    pub(super) fn call(&mut self, view: &Game) -> Call {
+        let Some(taken) = self.trick_distribution(view) else {
+            return Call::new(1).unwrap();
+        };
+        let expected = |call: &Call| -> f64 {
+            (0..=13)
+                .map(|tricks| {
+                    taken[tricks as usize]
+                        * view.ruleset.call_score(*call, tricks, &view.rules) as f64
+                })
+                .sum()
+        };
+        // nil is only weighed where the rules allow it
+        let calls: Vec<Call> = view
+            .ruleset
+            .check_call(Call::nil(), &view.rules)
+            .is_ok()
+            .then(Call::nil)
+            .into_iter()
+            .chain((1..=13).map(|value| Call::new(value).expect("must be a valid call")))
+            .collect();
+        let call = *calls
+            .iter()
+            .max_by(|a, b| expected(a).total_cmp(&expected(b)))
+            .expect("must have a call to make");
+        debug!(?taken, ?call, "ismcts call");
+        if self.explain {
+            let candidates = calls
+                .iter()
+                .map(|call| Candidate {
+                    choice: Choice::Call(*call),
+                    score: expected(call),
+                    stats: Stats::Calling {
+                        win_chance: (0..=13)
+                            .filter(|tricks| call.is_made(*tricks))
+                            .map(|tricks| taken[tricks as usize])
+                            .sum(),
+                        expected_points: expected(call),
+                    },
+                })
+                .collect();
+            self.explained(
+                view,
+                Choice::Call(call),
+                "most points over the searched rounds",
+                candidates,
+            );
+        }
+        call
+    }
+
+    pub(super) fn play(&mut self, view: &Game) -> Card {
+        let moves = view.valid_moves();
+        let fallback = *moves.first().expect("must have a valid card to play");
+        if moves.len() == 1 {
+            return fallback;
+        }
+        let Some(determinizer) = Determinizer::new(view) else {
+            return fallback;
+        };
+
+        let mut tree = vec![Node::new(determinizer.seat(), None, None)];
+        let mut more = self.iterations();
+        while more() {
+            let Some(mut round) = determinizer.sample(&mut self.rng) else {
+                continue;
+            };
+
+            // selection and expansion
+            let mut node = 0;
+            while let Ok(turn) = round.turn() {
+                let moves = round
+                    .valid_moves(turn)
+                    .expect("must have valid moves on our turn");
+                let children: Vec<usize> = tree[node]
+                    .children
+                    .iter()
+                    .copied()
+                    .filter(|&child| moves.contains(tree[child].card.unwrap()))
+                    .collect();
+                for &child in children.iter() {
+                    tree[child].available += 1.0;
+                }
+                let untried: Vec<Card> = moves
+                    .iter()
+                    .filter(|card| !children.iter().any(|&c| tree[c].card == Some(*card)))
+                    .collect();
+                if let Some(&card) = untried.choose(&mut self.rng) {
+                    let child = tree.len();
+                    tree.push(Node::new(turn.into(), Some(card), Some(node)));
+                    tree[child].available += 1.0;
+                    tree[node].children.push(child);
+                    round.play(card, turn).expect("must be a valid move");
+                    node = child;
+                    break;
+                }
+                let ucb = |child: usize| {
+                    let child = &tree[child];
+                    child.reward / child.visits
+                        + self.exploration * (child.available.ln() / child.visits).sqrt()
+                };
+                node = *children
+                    .iter()
+                    .max_by(|a, b| ucb(**a).total_cmp(&ucb(**b)))
+                    .expect("a fully expanded node must have children");
+                round
+                    .play(tree[node].card.unwrap(), turn)
+                    .expect("must be a valid move");
+            }
+
+            // simulation
+            self.rollout(&mut round);
+
+            // backpropagation
+            let rewards = Self::rewards(&round, &view.ruleset, &view.rules);
+            let mut current = Some(node);
+            while let Some(node) = current {
+                tree[node].visits += 1.0;
+                tree[node].reward += rewards[tree[node].seat];
+                current = tree[node].parent;
+            }
+        }
+
+        let best = tree[0]
+            .children
+            .iter()
+            .max_by(|a, b| tree[**a].visits.total_cmp(&tree[**b].visits))
+            .and_then(|&child| tree[child].card)
+            .unwrap_or(fallback);
+        debug!(root_visits = tree[0].visits, ?best, "ismcts play");
+        if self.explain {
+            let candidates = tree[0]
+                .children
+                .iter()
+                .filter_map(|&child| {
+                    let node = &tree[child];
+                    Some(Candidate {
+                        choice: Choice::Play(node.card?),
+                        score: node.visits,
+                        stats: Stats::Search {
+                            visits: node.visits,
+                            mean_reward: node.reward / node.visits.max(1.0),
+                        },
+                    })
+                })
+                .collect();
+            self.explained(
+                view,
+                Choice::Play(best),
+                "most visited move of the search",
+                candidates,
+            );
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Game as Engine, Seats};
+
+    fn new_game() -> Engine {
+        let mut game = Engine::default();
+        for player in 0..4 {
+            game.add_player(&player.to_string()).unwrap();
+        }
+        game
+    }
+
+    #[test]
+    fn makes_a_call_on_a_new_round() {
+        let game = new_game();
+        let player = game.turn().unwrap();
+        let view = game.build_view_for(&player).unwrap();
+        let mut agent = Ismcts::new(Budget::Iterations(50));
+        game.clone().call(&player, agent.call(&view)).unwrap();
+    }
+
+    #[test]
+    fn plays_a_round_with_valid_moves() {
+        let mut game = new_game();
+        let mut agent = Ismcts::new(Budget::Iterations(30)).with_seed(7);
+        for _ in 0..4 {
+            let player = game.turn().unwrap();
+            let view = game.build_view_for(&player).unwrap();
+            game.call(&player, agent.call(&view)).unwrap();
+        }
+        for _ in 0..52 {
+            let player = game.turn().unwrap();
+            let view = game.build_view_for(&player).unwrap();
+            let card = agent.play(&view);
+            assert!(game.get_valid_moves(&player).unwrap().contains(&card));
+            game.play(&player, card).unwrap();
+        }
+    }
+
+    #[test]
+    fn plays_a_round_at_a_table_of_three() {
+        let mut game = Engine::default().with_rules(Rules::default().with_seats(Seats::Three));
+        for player in 0..3 {
+            game.add_player(&player.to_string()).unwrap();
+        }
+        let mut agent = Ismcts::new(Budget::Iterations(20)).with_seed(43);
+        for _ in 0..3 {
+            let player = game.turn().unwrap();
+            let view = game.build_view_for(&player).unwrap();
+            game.call(&player, agent.call(&view)).unwrap();
+        }
+        for _ in 0..51 {
+            let player = game.turn().unwrap();
+            let view = game.build_view_for(&player).unwrap();
+            let card = agent.play(&view);
+            assert!(game.get_valid_moves(&player).unwrap().contains(&card));
+            game.play(&player, card).unwrap();
+        }
+    }
+
+    #[test]
+    fn respects_a_time_budget() {
+        let game = new_game();
+        let player = game.turn().unwrap();
+        let view = game.build_view_for(&player).unwrap();
+        let mut agent =
+            Ismcts::new(Budget::Time(Duration::from_millis(50))).with_rollout(RandomRollout);
+        let start = Instant::now();
+        agent.call(&view);
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+}
@@ -1,18 +1,49 @@
 mod bot;
+mod calling;
+mod determinize;
+mod explain;
+mod hint;
 mod human;
+mod ismcts;
+mod model;
+mod opponent;
 mod view;
 
-use crate::game::{Call, Card};
-pub use bot::Bot;
+use crate::game::{Call, Card, Claim, Suit};
+pub use bot::{Bot, Difficulty, Style};
+pub(crate) use determinize::Determinizer;
+pub use explain::{Candidate, Choice, Explanation, Holding, Stats};
+pub use hint::{Hint, Hinter};
 pub use human::Human;
 pub use human::Transport;
-pub use view::{Action, ClientMessage, ServerMessage};
-pub use view::{Game, Round};
+pub use ismcts::{Budget, GreedyRollout, Ismcts, RandomRollout, Rollout};
+pub use model::{Bucket, Calibration, CallingModel, CallingSample, FEATURES, features, heuristic};
+use std::fmt::Display;
+pub use view::{Action, ClientMessage, Results, ServerMessage};
+pub use view::{Game, Round, Standing};
+
+/// What an agent does when it is asked to play
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Move {
+    Play(Card),
+    Claim(Claim),
+}
 
 #[derive(Debug)]
 pub enum AgentKind {
     Bot(Bot),
     Human(Human),
+    Ismcts(Ismcts),
+}
+
+impl Display for AgentKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Bot(bot) => write!(f, "bot ({bot})"),
+            Self::Human(_) => write!(f, "human"),
+            Self::Ismcts(ismcts) => write!(f, "ismcts ({:?})", ismcts.budget()),
+        }
+    }
 }
 
 impl AgentKind {
@@ -20,13 +51,65 @@ impl AgentKind {
         match self {
             Self::Bot(bot) => bot.call(view),
             Self::Human(human) => human.call(view),
+            Self::Ismcts(ismcts) => ismcts.call(view),
         }
     }
 
-    pub(crate) fn play(&mut self, view: &Game) -> Card {
+    pub(crate) fn choose_trump(&mut self, view: &Game) -> Suit {
         match self {
-            Self::Bot(bot) => bot.play(view),
+            Self::Bot(bot) => bot.choose_trump(view),
+            Self::Human(human) => human.choose_trump(view),
+            Self::Ismcts(ismcts) => ismcts.choose_trump(view),
+        }
+    }
+
+    pub(crate) fn play(&mut self, view: &Game) -> Move {
+        match self {
+            Self::Bot(bot) => match bot.claim(view) {
+                Some(claim) => Move::Claim(claim),
+                None => Move::Play(bot.play(view)),
+            },
             Self::Human(human) => human.play(view),
+            Self::Ismcts(ismcts) => Move::Play(ismcts.play(view)),
+        }
+    }
+
+    // move of an agent whose claim was just turned down. a bot would only make the same claim
+    // again, so it plays a card instead, while a human is asked again
+    pub(crate) fn play_after_refused_claim(&mut self, view: &Game) -> Move {
+        match self {
+            Self::Bot(bot) => Move::Play(bot.play(view)),
+            agent => agent.play(view),
+        }
+    }
+
+    // let the agent know why their claim was turned down before they are asked to play again
+    pub(crate) fn reject_claim(&mut self, reason: crate::Error) {
+        if let Self::Human(human) = self {
+            human.reject_claim(reason);
+        }
+    }
+
+    /// Why the agent took its last decision, if it explains itself
+    pub fn take_explanation(&mut self) -> Option<Explanation> {
+        match self {
+            Self::Bot(bot) => bot.take_explanation(),
+            Self::Human(_) => None,
+            Self::Ismcts(ismcts) => ismcts.take_explanation(),
+        }
+    }
+
+    // let the agent know how the game went, once it is over
+    pub(crate) fn announce(&mut self, view: &Game, results: &Results) {
+        if let Self::Human(human) = self {
+            human.announce(view, results.clone());
+        }
+    }
+
+    // pass on why another player took their decision, for humans to look at
+    pub(crate) fn share_explanation(&mut self, explanation: &Explanation) {
+        if let Self::Human(human) = self {
+            human.share_explanation(explanation.clone());
         }
     }
 
@@ -0,0 +1,391 @@
+//! Learned model of how many tricks a hand takes, used by the bots to call.
+//!
+//! The model is a softmax regression over a handful of features of the hand, trained on the
+//! calls of self-play games. Its weights are kept as JSON so that they can be trained offline
+//! and loaded by the bots at runtime.
+
+use super::calling::{as_spades, expected_tricks, trick_distribution};
+use crate::dataset::GameData;
+use crate::env::{ACTIONS, Action};
+use crate::game::{Card, Rank, Suit};
+use rand::{SeedableRng, rngs::StdRng, seq::SliceRandom};
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+use std::io;
+use std::path::Path;
+
+/// Number of features a hand is described by
+pub const FEATURES: usize = 4 * 5 + 3 + 1;
+// number of tricks a hand can take, 0 to 13
+const OUTCOMES: usize = 14;
+// hands in a single step of training
+const BATCH: usize = 64;
+// keeps the weights small when the data is thin
+const L2: f32 = 1e-4;
+
+/// Features of a hand with spades as trump: for every suit its length and which honours it holds,
+/// how short the side suits are, and the tricks the hand heuristics expect it to take
+pub fn features(hand: &[Card]) -> [f32; FEATURES] {
+    let mut out = [0.0; FEATURES];
+    for (i, suit) in Suit::ALL.iter().enumerate() {
+        let cards = hand.iter().filter(|card| card.get_suit() == *suit);
+        let block = &mut out[i * 5..(i + 1) * 5];
+        for card in cards {
+            block[0] += 0.25;
+            match card.get_rank() {
+                Rank::Ace => block[1] = 1.0,
+                Rank::King => block[2] = 1.0,
+                Rank::Queen => block[3] = 1.0,
+                Rank::Jack => block[4] = 1.0,
+                _ => {}
+            }
+        }
+        if *suit != Suit::Spades {
+            // void, singleton and doubleton side suits
+            let length = (block[0] * 4.0) as usize;
+            if length < 3 {
+                out[20 + length] += 1.0;
+            }
+        }
+    }
+    out[23] = expected_tricks(&trick_distribution(hand, Suit::Spades)) as f32 / 4.0;
+    out
+}
+
+/// Trick counts of the hand tuned heuristics, to compare a model against
+pub fn heuristic(hand: &[Card]) -> [f64; OUTCOMES] {
+    trick_distribution(hand, Suit::Spades)
+}
+
+/// A hand as it was dealt, along with the tricks it went on to take
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallingSample {
+    pub hand: Vec<Card>,
+    pub tricks: u8,
+}
+
+impl CallingSample {
+    /// Every call of a recorded game whose round was played out, with the hand's trump swapped
+    /// for spades the way the bots see it
+    pub fn from_dataset(game: &GameData) -> Vec<Self> {
+        game.decisions
+            .iter()
+            .filter(|decision| (52..ACTIONS).contains(&(decision.action as usize)))
+            .filter_map(|decision| {
+                let result = decision.result?;
+                // INFO: the hand is the first 52 entries of the observation, see env::encode
+                let hand: Vec<Card> = (0..52)
+                    .filter(|index| decision.observation[*index] > 0.5)
+                    .filter_map(|index| match Action::try_from(index) {
+                        Ok(Action::Play(card)) => Some(card),
+                        _ => None,
+                    })
+                    .collect();
+                // the model is of hands dealt to four, a table of three deals more cards and
+                // can take more tricks than it has outcomes for
+                if result.tricks as usize >= OUTCOMES || hand.len() != 13 {
+                    return None;
+                }
+                Some(Self {
+                    hand: as_spades(&hand, result.trump),
+                    tricks: result.tricks,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Weights of a softmax regression from the features of a hand to the tricks it takes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CallingModel {
+    /// One row of [`FEATURES`] weights for every number of tricks from 0 to 13
+    weights: Vec<Vec<f32>>,
+    bias: Vec<f32>,
+}
+
+impl Default for CallingModel {
+    fn default() -> Self {
+        Self {
+            weights: vec![vec![0.0; FEATURES]; OUTCOMES],
+            bias: vec![0.0; OUTCOMES],
+        }
+    }
+}
+
+impl CallingModel {
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        std::fs::write(path, self.to_json())
+    }
+
+    pub fn from_json(json: &str) -> io::Result<Self> {
+        let model: Self = serde_json::from_str(json)?;
+        if model.bias.len() != OUTCOMES
+            || model.weights.len() != OUTCOMES
+            || model.weights.iter().any(|row| row.len() != FEATURES)
+        {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "weights do not match the features of a hand",
+            ));
+        }
+        Ok(model)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("must serialize without issue")
+    }
+
+    fn logits(&self, x: &[f32; FEATURES]) -> [f32; OUTCOMES] {
+        std::array::from_fn(|k| {
+            self.bias[k]
+                + self.weights[k]
+                    .iter()
+                    .zip(x)
+                    .map(|(w, x)| w * x)
+                    .sum::<f32>()
+        })
+    }
+
+    fn softmax(&self, x: &[f32; FEATURES]) -> [f32; OUTCOMES] {
+        let logits = self.logits(x);
+        let max = logits.iter().copied().fold(f32::MIN, f32::max);
+        let exp = logits.map(|logit| (logit - max).exp());
+        let total: f32 = exp.iter().sum();
+        exp.map(|e| e / total)
+    }
+
+    /// Probability of the hand taking exactly `k` tricks, for every `k` from 0 to 13
+    pub fn predict(&self, hand: &[Card]) -> [f64; OUTCOMES] {
+        self.softmax(&features(hand)).map(f64::from)
+    }
+
+    /// Fit a model to the samples by minibatch gradient descent
+    pub fn train(samples: &[CallingSample], epochs: usize, learning_rate: f32, seed: u64) -> Self {
+        let mut model = Self::default();
+        let data: Vec<([f32; FEATURES], usize)> = samples
+            .iter()
+            .map(|sample| (features(&sample.hand), sample.tricks as usize))
+            .collect();
+        let mut order: Vec<usize> = (0..data.len()).collect();
+        let mut rng = StdRng::seed_from_u64(seed);
+        for _ in 0..epochs {
+            order.shuffle(&mut rng);
+            for batch in order.chunks(BATCH) {
+                let mut weights = vec![[0.0f32; FEATURES]; OUTCOMES];
+                let mut bias = [0.0f32; OUTCOMES];
+                for (x, tricks) in batch.iter().map(|i| &data[*i]) {
+                    let p = model.softmax(x);
+                    for k in 0..OUTCOMES {
+                        // gradient of the log loss with respect to the logit
+                        let g = p[k] - (k == *tricks) as u8 as f32;
+                        bias[k] += g;
+                        for (w, x) in weights[k].iter_mut().zip(x) {
+                            *w += g * x;
+                        }
+                    }
+                }
+                let step = learning_rate / batch.len() as f32;
+                for k in 0..OUTCOMES {
+                    model.bias[k] -= step * bias[k];
+                    for (w, g) in model.weights[k].iter_mut().zip(weights[k]) {
+                        *w -= step * g + learning_rate * L2 * *w;
+                    }
+                }
+            }
+        }
+        model
+    }
+}
+
+/// Expected tricks against tricks taken, over the hands expected to take about as many
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct Bucket {
+    /// Number of expected tricks, rounded, the hands in the bucket share
+    pub tricks: u8,
+    pub hands: usize,
+    pub expected: f64,
+    pub actual: f64,
+}
+
+/// How well the predicted trick counts of a model match up with the tricks actually taken
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Calibration {
+    pub hands: usize,
+    /// Average negative log likelihood of the tricks taken, lower is better
+    pub log_loss: f64,
+    /// Average absolute difference between the expected and the actual tricks
+    pub mean_error: f64,
+    /// Predicted against actual chance of every number of tricks from 0 to 13
+    pub predicted: [f64; OUTCOMES],
+    pub actual: [f64; OUTCOMES],
+    pub buckets: Vec<Bucket>,
+}
+
+impl Calibration {
+    /// Evaluate any prediction of trick counts on the samples
+    pub fn measure(
+        samples: &[CallingSample],
+        predict: impl Fn(&[Card]) -> [f64; OUTCOMES],
+    ) -> Self {
+        let mut calibration = Self {
+            hands: samples.len(),
+            log_loss: 0.0,
+            mean_error: 0.0,
+            predicted: [0.0; OUTCOMES],
+            actual: [0.0; OUTCOMES],
+            buckets: (0..OUTCOMES as u8)
+                .map(|tricks| Bucket {
+                    tricks,
+                    ..Bucket::default()
+                })
+                .collect(),
+        };
+        if samples.is_empty() {
+            return calibration;
+        }
+        for sample in samples {
+            let distribution = predict(&sample.hand);
+            let expected = expected_tricks(&distribution);
+            // a sample made up by hand may take more tricks than there are outcomes
+            let tricks = (sample.tricks as usize).min(OUTCOMES - 1);
+            calibration.log_loss -= distribution[tricks].max(1e-9).ln();
+            calibration.mean_error += (expected - tricks as f64).abs();
+            for (total, p) in calibration.predicted.iter_mut().zip(distribution) {
+                *total += p;
+            }
+            calibration.actual[tricks] += 1.0;
+            let bucket = &mut calibration.buckets[(expected.round() as usize).min(OUTCOMES - 1)];
+            bucket.hands += 1;
+            bucket.expected += expected;
+            bucket.actual += tricks as f64;
+        }
+        let n = samples.len() as f64;
+        calibration.log_loss /= n;
+        calibration.mean_error /= n;
+        calibration.predicted.iter_mut().for_each(|p| *p /= n);
+        calibration.actual.iter_mut().for_each(|p| *p /= n);
+        calibration.buckets.retain(|bucket| bucket.hands > 0);
+        for bucket in calibration.buckets.iter_mut() {
+            bucket.expected /= bucket.hands as f64;
+            bucket.actual /= bucket.hands as f64;
+        }
+        calibration
+    }
+}
+
+impl Display for Calibration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "hands: {}  log loss: {:.3}  mean error: {:.3}",
+            self.hands, self.log_loss, self.mean_error
+        )?;
+        writeln!(f, "{:>8}{:>12}{:>12}", "tricks", "predicted", "actual")?;
+        for k in 0..OUTCOMES {
+            writeln!(
+                f,
+                "{:>8}{:>11.1}%{:>11.1}%",
+                k,
+                self.predicted[k] * 100.0,
+                self.actual[k] * 100.0
+            )?;
+        }
+        writeln!(
+            f,
+            "{:>8}{:>8}{:>12}{:>12}",
+            "bucket", "hands", "expected", "actual"
+        )?;
+        for bucket in self.buckets.iter() {
+            writeln!(
+                f,
+                "{:>8}{:>8}{:>12.2}{:>12.2}",
+                bucket.tricks, bucket.hands, bucket.expected, bucket.actual
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Host;
+    use crate::agent::Difficulty;
+
+    fn self_play(games: u64) -> Vec<CallingSample> {
+        (0..games)
+            .flat_map(|seed| {
+                let mut host = Host::with_seed(seed)
+                    .recording()
+                    .with_bots(Difficulty::Beginner);
+                host.run();
+                CallingSample::from_dataset(&host.dataset().unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn every_call_of_the_game_is_a_sample() {
+        let samples = self_play(1);
+        assert_eq!(samples.len(), 5 * 4);
+        assert!(samples.iter().all(|sample| sample.hand.len() == 13));
+        let tricks: u32 = samples.iter().map(|sample| sample.tricks as u32).sum();
+        assert_eq!(tricks, 5 * 13);
+    }
+
+    #[test]
+    fn samples_see_their_trump_as_spades() {
+        let mut host = Host::with_seed(3)
+            .recording()
+            .with_bots(Difficulty::Beginner);
+        host.run();
+        let mut game = host.dataset().unwrap();
+        let samples = CallingSample::from_dataset(&game);
+        for decision in game.decisions.iter_mut() {
+            if let Some(result) = decision.result.as_mut() {
+                result.trump = Suit::Hearts;
+            }
+        }
+        for (swapped, sample) in CallingSample::from_dataset(&game).iter().zip(samples) {
+            assert_eq!(swapped.hand, as_spades(&sample.hand, Suit::Hearts));
+        }
+    }
+
+    #[test]
+    fn hands_dealt_to_three_are_left_out() {
+        use crate::game::{Rules, Seats};
+        let mut host = Host::with_seed(4)
+            .with_rules(Rules::default().with_seats(Seats::Three))
+            .recording()
+            .with_bots(Difficulty::Beginner);
+        host.run();
+        assert!(CallingSample::from_dataset(&host.dataset().unwrap()).is_empty());
+        let sample = CallingSample {
+            hand: vec![],
+            tricks: 17,
+        };
+        assert_eq!(Calibration::measure(&[sample], heuristic).hands, 1);
+    }
+
+    #[test]
+    fn training_beats_guessing_evenly() {
+        let samples = self_play(20);
+        let model = CallingModel::train(&samples, 50, 0.5, 7);
+        let trained = Calibration::measure(&samples, |hand| model.predict(hand));
+        let untrained =
+            Calibration::measure(&samples, |hand| CallingModel::default().predict(hand));
+        assert!(trained.log_loss < untrained.log_loss);
+        assert!(trained.mean_error < 1.5, "{trained}");
+    }
+
+    #[test]
+    fn weights_round_trip_through_json() {
+        let model = CallingModel::train(&self_play(2), 2, 0.5, 1);
+        assert_eq!(CallingModel::from_json(&model.to_json()).unwrap(), model);
+        assert!(CallingModel::from_json(r#"{"weights": [], "bias": []}"#).is_err());
+    }
+}
@@ -0,0 +1,150 @@
+use super::calling::CALL_MADE;
+use super::view::Game;
+use crate::game::Rank;
+
+// how many observations the typical values below are worth, so that a couple of rounds do not
+// swing the estimates too far
+const PRIOR_WEIGHT: f64 = 4.0;
+const TYPICAL_HIGH_LEAD: f64 = 0.5;
+const TYPICAL_EARLY_SPADES: f64 = 0.2;
+// tricks that count as early in a round
+pub(super) const EARLY: usize = 6;
+
+fn smoothed(count: u32, total: u32, typical: f64) -> f64 {
+    (count as f64 + typical * PRIOR_WEIGHT) / (total as f64 + PRIOR_WEIGHT)
+}
+
+/// How a single seat has been calling and playing so far in the game
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(super) struct Profile {
+    rounds: u32,
+    made: u32,
+    // sum of tricks taken minus the call, over the rounds that are over
+    surplus: i32,
+    leads: u32,
+    high_leads: u32,
+    early_plays: u32,
+    early_spades: u32,
+}
+
+impl Profile {
+    /// Chance that this seat meets its call
+    pub(super) fn made_rate(&self) -> f64 {
+        smoothed(self.made, self.rounds, CALL_MADE)
+    }
+
+    /// Tricks taken over the call on average, negative for someone who over-calls
+    pub(super) fn call_bias(&self) -> f64 {
+        if self.rounds == 0 {
+            0.0
+        } else {
+            self.surplus as f64 / self.rounds as f64
+        }
+    }
+
+    /// Chance that this seat leads with a jack or better
+    pub(super) fn high_lead_rate(&self) -> f64 {
+        smoothed(self.high_leads, self.leads, TYPICAL_HIGH_LEAD)
+    }
+
+    /// Share of the cards played early in a round that were spades
+    pub(super) fn early_spade_rate(&self) -> f64 {
+        smoothed(self.early_spades, self.early_plays, TYPICAL_EARLY_SPADES)
+    }
+}
+
+/// Statistics on every seat collected from the rounds in a player's view of the game
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(super) struct OpponentModel {
+    seats: [Profile; 4],
+}
+
+impl OpponentModel {
+    pub(super) fn observe(view: &Game) -> Self {
+        let mut seats = [Profile::default(); 4];
+        for round in view.rounds.iter() {
+            for (i, trick) in round.tricks.iter().enumerate() {
+                for (k, (turn, card)) in trick.plays().enumerate() {
+                    let profile = &mut seats[usize::from(turn)];
+                    if k == 0 {
+                        profile.leads += 1;
+                        profile.high_leads += (card.get_rank() >= Rank::Jack) as u32;
+                    }
+                    if i < EARLY {
+                        profile.early_plays += 1;
+                        profile.early_spades += (card.get_suit() == trick.trump()) as u32;
+                    }
+                }
+            }
+            if !round.is_over() {
+                continue;
+            }
+            let won = round.tricks_won();
+            for (seat, profile) in seats.iter_mut().enumerate().take(view.seats()) {
+                let call = round.calls[seat].expect("a round that is over must have all calls");
+                profile.rounds += 1;
+                profile.made += call.is_made(won[seat]) as u32;
+                profile.surplus += won[seat] as i32 - call.value() as i32;
+            }
+        }
+        Self { seats }
+    }
+
+    pub(super) fn seat(&self, seat: usize) -> &Profile {
+        &self.seats[seat]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Call, Game as Engine};
+
+    // play two rounds where the first seat always calls 13 and plays its first valid card
+    fn played_view() -> (Game, usize) {
+        let mut game = Engine::default();
+        for player in 0..4 {
+            game.add_player(&player.to_string()).unwrap();
+        }
+        let first = game.turn().unwrap();
+        for _ in 0..2 {
+            for _ in 0..4 {
+                let player = game.turn().unwrap();
+                let call = if player == first { 13 } else { 1 };
+                game.call(&player, Call::new(call).unwrap()).unwrap();
+            }
+            for _ in 0..52 {
+                let player = game.turn().unwrap();
+                let moves = game.get_valid_moves(&player).unwrap();
+                game.play(&player, moves[0]).unwrap();
+            }
+        }
+        let player = game.turn().unwrap();
+        let view = game.build_view_for(&player).unwrap();
+        let seat = view.players.iter().position(|p| *p == first).unwrap();
+        (view, seat)
+    }
+
+    #[test]
+    fn over_caller_is_expected_to_miss() {
+        let (view, seat) = played_view();
+        let model = OpponentModel::observe(&view);
+        assert_eq!(model.seat(seat).rounds, 2);
+        assert!(model.seat(seat).made_rate() < CALL_MADE);
+        assert!(model.seat(seat).call_bias() < -5.0);
+    }
+
+    #[test]
+    fn every_trick_has_one_lead() {
+        let (view, _) = played_view();
+        let model = OpponentModel::observe(&view);
+        let leads: u32 = (0..4).map(|seat| model.seat(seat).leads).sum();
+        let early: u32 = (0..4).map(|seat| model.seat(seat).early_plays).sum();
+        assert_eq!(leads, 26);
+        assert_eq!(early, 2 * 4 * EARLY as u32);
+        for seat in 0..4 {
+            let rate = model.seat(seat).high_lead_rate();
+            assert!((0.0..=1.0).contains(&rate));
+        }
+    }
+}
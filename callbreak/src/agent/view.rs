@@ -1,19 +1,186 @@
-use crate::game::{Call, Card, Trick};
+use super::explain::Explanation;
+use super::hint::Hint;
+use crate::duplicate;
+use crate::game::{
+    Call, Card, Claim, Hand, Rules, Ruleset, Suit, Trick, TrickTakingRules, Turn, score, trump,
+};
+use crate::stats::Summary;
 use serde::{Deserialize, Serialize};
 
 /// View of the game sent to each player
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Game {
     pub players: Vec<String>,
+    /// House rules the table plays by
+    #[serde(default)]
+    pub rules: Rules,
+    /// Ruleset the game is played and scored by
+    #[serde(default)]
+    pub ruleset: Ruleset,
+    /// Who sits in each seat, a seat being empty while the table fills up
+    #[serde(default)]
+    pub seats: Vec<Option<String>>,
+    /// Player sitting across the table, when playing in partnerships
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub partner: Option<String>,
+    /// Seat of the player the view is for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub seat: Option<usize>,
+    /// Totals of the match the game is part of, over the games played before this one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub standings: Vec<Standing>,
     pub rounds: Vec<Round>,
 }
 
+/// How a player stands in a match, see [`Match`](crate::matches::Match)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Standing {
+    pub player: String,
+    /// Points collected over the games of the match
+    pub total: f32,
+    /// Games the player finished with the most points
+    pub wins: usize,
+}
+
 /// View of a round sent to each player
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Round {
     pub calls: [Option<Call>; 4],
     pub hand: Vec<Card>,
+    /// Trump of the round, none while the highest caller is still to choose it
+    #[serde(default = "spades")]
+    pub trump: Option<Suit>,
     pub tricks: Vec<Trick>,
+    /// Tricks each seat was given by a claim that settled the rest of the round
+    #[serde(default, skip_serializing_if = "score::is_unclaimed")]
+    pub claimed: [u8; 4],
+}
+
+fn spades() -> Option<Suit> {
+    Some(Suit::Spades)
+}
+
+impl Game {
+    /// Seat whose action is awaited in the latest round, if any
+    pub fn turn(&self) -> Option<usize> {
+        let seats = self.seats();
+        let starter = self.rounds.len().checked_sub(1)? % seats;
+        let round = self.rounds.last()?;
+        match round.tricks.last() {
+            // a claim may settle the round in the middle of a trick
+            Some(_) if round.is_over() => None,
+            Some(trick) => trick.turn().ok().map(usize::from),
+            None => {
+                let pending = |seat: usize| round.calls[seat].is_none();
+                if (0..seats).all(|seat| !pending(seat)) {
+                    // every call is in, so it is on the highest caller to pick the trump
+                    let chooser = trump::chooser(&round.calls, Turn::new(starter), seats);
+                    return round.trump.is_none().then_some(usize::from(chooser));
+                }
+                match self.seat {
+                    // the others' calls are hidden, so only our own is known to be missing
+                    Some(seat) if self.rules.sealed => pending(seat).then_some(seat),
+                    _ => (0..seats)
+                        .map(|i| (starter + i) % seats)
+                        .find(|&s| pending(s)),
+                }
+            }
+        }
+    }
+
+    /// Number of players at the table
+    pub fn seats(&self) -> usize {
+        self.rules.seats.count()
+    }
+
+    /// Cards of the hand that may be played to the current trick, by the ruleset of the game
+    pub(crate) fn valid_moves(&self) -> Vec<Card> {
+        let Some((round, trick)) = self
+            .rounds
+            .last()
+            .and_then(|round| Some((round, round.tricks.last()?)))
+        else {
+            return vec![];
+        };
+        let moves = self
+            .ruleset
+            .valid_moves(trick, &Hand::new(round.hand.iter().copied()));
+        round
+            .hand
+            .iter()
+            .copied()
+            .filter(|card| moves.contains(*card))
+            .collect()
+    }
+
+    /// Points each seat has collected over the rounds that are over
+    pub fn scores(&self) -> [f32; 4] {
+        let rounds = self.rounds.iter();
+        score::total(
+            &self.ruleset,
+            &self.rules,
+            rounds.map(|round| (&round.calls, round.tricks.as_slice(), &round.claimed)),
+        )
+    }
+
+    /// Rounds still to be played after the latest one
+    pub fn rounds_left(&self) -> usize {
+        let played = self.rounds.len().max(1);
+        let left = self.rules.rounds().saturating_sub(played);
+        match self.rules.target {
+            None => left,
+            // INFO: a game to a target score has no last round, so it is taken to last as long
+            // as it would take the leader to get there calling three a round, but never past the
+            // last round the game is played to
+            Some(target) => {
+                let leader = self.scores().into_iter().fold(f32::MIN, f32::max);
+                let round = 3.0 * self.ruleset.trick_value();
+                let needed = ((target - leader) / round).ceil().max(1.0) as usize;
+                (needed - 1).min(left)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+impl Game {
+    // view of the first round at a table of four by the standard rules with spades as trump, for
+    // the tests that need a particular hand or trick
+    pub(crate) fn of_round(calls: [Option<Call>; 4], hand: Vec<Card>, tricks: Vec<Trick>) -> Self {
+        Self {
+            players: (0..4).map(|player| player.to_string()).collect(),
+            rules: Rules::default(),
+            ruleset: Ruleset::default(),
+            seats: vec![],
+            partner: None,
+            seat: None,
+            standings: vec![],
+            rounds: vec![Round {
+                calls,
+                hand,
+                trump: Some(Suit::Spades),
+                tricks,
+                claimed: [0; 4],
+            }],
+        }
+    }
+}
+
+impl Round {
+    /// Number of tricks won by each seat, played or claimed
+    pub fn tricks_won(&self) -> [u8; 4] {
+        score::tricks_won(&self.tricks, &self.claimed)
+    }
+
+    pub fn is_over(&self) -> bool {
+        score::is_round_over(&self.tricks, &self.claimed)
+    }
+
+    /// Points each seat earned in the round of a game played by `ruleset` and `rules`, once it
+    /// is over
+    pub fn scores(&self, ruleset: &Ruleset, rules: &Rules) -> Option<[f32; 4]> {
+        score::round_scores(ruleset, rules, &self.calls, &self.tricks, &self.claimed)
+    }
 }
 
 /// Action that is requested of a player
@@ -22,6 +189,10 @@ pub struct Round {
 pub enum Action {
     Call,
     Break,
+    /// Pick the trump of the round, as its highest caller
+    Trump,
+    /// Nothing is asked, the game is over and the message says how it went
+    Over,
     // TODO: might eventually have to include update on the action
 }
 
@@ -31,6 +202,32 @@ pub enum Action {
 pub struct ServerMessage {
     pub action: Action,
     pub view: Game,
+    /// Why the bots took their decisions since the last message, for those that explain them
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub explanations: Vec<Explanation>,
+    /// Suggestion the player asked for with [`ClientMessage::Hint`], if hints are allowed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hint: Option<Hint>,
+    /// Why the last [`ClientMessage::Claim`] was turned down, the player is to play instead
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rejected_claim: Option<String>,
+    /// How the game went, sent with [`Action::Over`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub results: Option<Results>,
+}
+
+/// How a game went, for the players to look at once it is over
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct Results {
+    /// How lucky each player was with their cards, see [`Meter`](crate::stats::Meter)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub luck: Vec<Summary>,
+    /// Standings of the duplicate the game is a table of, over the tables played so far
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub duplicate: Vec<duplicate::Standing>,
+    /// Totals of the match the game is part of, this game included
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub standings: Vec<Standing>,
 }
 
 /// Message expected from a human agent
@@ -39,5 +236,30 @@ pub struct ServerMessage {
 pub enum ClientMessage {
     Call(Call),
     Break(Card),
+    /// Ask for a suggested call or card before answering, the request is sent again with it
+    Hint,
+    /// Settle the rest of the round instead of playing a card, checked against every hand
+    Claim(Claim),
+    /// Trump picked for the round, when asked with [`Action::Trump`]
+    Trump(Suit),
     //TODO: also allow clients to request for update?
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Spades;
+
+    #[test]
+    fn rounds_left_to_a_target_stop_at_the_last_round() {
+        let mut view = Game::of_round([None; 4], vec![], vec![]);
+        view.rules = Rules::default().with_target(1e30);
+        assert_eq!(view.rounds_left(), view.rules.rounds() - 1);
+        // a round of spades is worth ten times the points of one of call break
+        view.rules = Rules::default().with_target(500.0);
+        view.ruleset = Ruleset::Spades(Spades::default());
+        assert_eq!(view.rounds_left(), 16);
+        view.ruleset = Ruleset::CallBreak;
+        assert_eq!(view.rounds_left(), view.rules.rounds() - 1);
+    }
+}
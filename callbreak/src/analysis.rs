@@ -0,0 +1,654 @@
+//! Review of a finished game, looking for the calls and cards that cost a player.
+//!
+//! Every card a reviewed player played is compared with the best card they had. Near the end of
+//! a round, when few enough cards are left, the comparison is double dummy: every hand is known
+//! and the others play to keep the player's tricks down. Before that, the hidden cards are
+//! sampled from what the player could see at the time and the rest of the round is played out.
+//! Every call is compared with the tricks the hand could be expected to take.
+
+use crate::agent::{Determinizer, Game as GameView, GreedyRollout, Rollout, Round as RoundView};
+use crate::game::claim::{solve, taken};
+use crate::game::{
+    Call, Card, Hand, Record, Round, RoundRecord, Suit, TrickTakingRules, Turn, tricks_in_round,
+};
+use rand::{SeedableRng, rngs::StdRng};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+// tricks left in the round from which plays are solved double dummy
+const DOUBLE_DUMMY_TRICKS: usize = 4;
+// tricks a card has to give away before it counts as a mistake
+const MISTAKE: f64 = 0.5;
+// distance between the call and the expected tricks that is still a fair call
+const FAIR_CALL: f64 = 1.0;
+
+/// How the value of a card was worked out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Method {
+    DoubleDummy,
+    Sampled,
+}
+
+/// How a call compares with what the hand could be expected to take
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Verdict {
+    Fair,
+    Overcalled,
+    Undercalled,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CallReview {
+    pub player: String,
+    pub call: u8,
+    /// Tricks the hand was expected to take, going by what the player could see
+    pub potential: f64,
+    pub taken: u8,
+    pub verdict: Verdict,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayReview {
+    pub player: String,
+    /// Trick of the round the card was played to, from 1
+    pub trick: usize,
+    pub card: Card,
+    pub best: Card,
+    /// Tricks the player would go on to take from this trick on, with the card played
+    pub expected: f64,
+    /// and with the best card instead
+    pub best_expected: f64,
+    pub method: Method,
+    pub mistake: bool,
+}
+
+impl PlayReview {
+    /// Tricks given away by not playing the best card
+    pub fn loss(&self) -> f64 {
+        (self.best_expected - self.expected).max(0.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoundReview {
+    pub calls: Vec<CallReview>,
+    /// Every play of the reviewed players, in the order they were played
+    pub plays: Vec<PlayReview>,
+}
+
+/// Totals of a single player over the whole game
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    pub player: String,
+    pub plays: usize,
+    pub mistakes: usize,
+    pub tricks_lost: f64,
+    pub fair_calls: usize,
+    pub calls: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Report {
+    pub seed: Option<u64>,
+    pub players: Vec<String>,
+    pub summaries: Vec<Summary>,
+    pub rounds: Vec<RoundReview>,
+}
+
+/// Reviews finished games
+#[derive(Debug, Clone)]
+pub struct Analyzer {
+    samples: usize,
+    seed: u64,
+    players: Option<Vec<String>>,
+}
+
+impl Default for Analyzer {
+    fn default() -> Self {
+        Self {
+            samples: 100,
+            seed: 0,
+            players: None,
+        }
+    }
+}
+
+impl Analyzer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of deals sampled for every play that is not solved double dummy
+    pub fn with_samples(mut self, samples: usize) -> Self {
+        self.samples = samples.max(1);
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Review only these players, e.g. the humans, rather than everyone
+    pub fn only(mut self, players: impl IntoIterator<Item = String>) -> Self {
+        self.players = Some(players.into_iter().collect());
+        self
+    }
+
+    fn reviews(&self, player: &str) -> bool {
+        self.players
+            .as_ref()
+            .is_none_or(|players| players.iter().any(|p| p == player))
+    }
+
+    pub fn analyze(&self, record: &Record) -> Report {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let mut rounds = vec![];
+        for (index, played) in record.rounds.iter().enumerate() {
+            rounds.push(self.round(record, index, played, &mut rng));
+        }
+        let summaries = record
+            .players
+            .iter()
+            .filter(|player| self.reviews(player))
+            .map(|player| {
+                let plays = rounds
+                    .iter()
+                    .flat_map(|round: &RoundReview| round.plays.iter())
+                    .filter(|play| &play.player == player);
+                let calls = rounds
+                    .iter()
+                    .flat_map(|round| round.calls.iter())
+                    .filter(|call| &call.player == player);
+                Summary {
+                    player: player.clone(),
+                    plays: plays.clone().count(),
+                    mistakes: plays.clone().filter(|play| play.mistake).count(),
+                    tricks_lost: plays.map(|play| play.loss()).sum(),
+                    fair_calls: calls
+                        .clone()
+                        .filter(|call| call.verdict == Verdict::Fair)
+                        .count(),
+                    calls: calls.count(),
+                }
+            })
+            .collect();
+        Report {
+            seed: record.seed,
+            players: record.players.clone(),
+            summaries,
+            rounds,
+        }
+    }
+
+    fn round(
+        &self,
+        record: &Record,
+        index: usize,
+        played: &RoundRecord,
+        rng: &mut StdRng,
+    ) -> RoundReview {
+        let seats = record.rules.seats.count();
+        let starter = Turn::new(index % seats);
+        // INFO: a round whose trump was never chosen was never played either
+        let mut round = Round::from_hands(seats, starter, played.hands.clone().map(Hand::new))
+            .with_ruleset(record.ruleset)
+            .with_rules(&record.rules)
+            .with_trump(played.trump.unwrap_or(Suit::Spades));
+        let mut review = RoundReview {
+            calls: vec![],
+            plays: vec![],
+        };
+        let taken = played.tricks_won();
+        for i in 0..seats {
+            let turn = starter.after(i, seats);
+            let Some(call) = played.calls[turn] else {
+                return review;
+            };
+            let player = &record.players[usize::from(turn)];
+            if self.reviews(player) {
+                let view = view_of(record, index, &round, turn);
+                let potential = self.potential(&view, played, rng);
+                let verdict = match call.value() as f64 - potential {
+                    d if d > FAIR_CALL => Verdict::Overcalled,
+                    d if d < -FAIR_CALL => Verdict::Undercalled,
+                    _ => Verdict::Fair,
+                };
+                review.calls.push(CallReview {
+                    player: player.clone(),
+                    call: call.value(),
+                    potential,
+                    taken: taken[turn],
+                    verdict,
+                });
+            }
+            round
+                .call(call, turn)
+                .expect("a recorded call must be valid");
+        }
+
+        for (t, trick) in played.tricks.iter().enumerate() {
+            for (turn, card) in trick.plays() {
+                let player = &record.players[usize::from(turn)];
+                if self.reviews(player) {
+                    let moves = round
+                        .valid_moves(turn)
+                        .expect("a recorded play must be on its turn");
+                    if moves.len() > 1 {
+                        let (method, values) = if tricks_in_round(seats) - t <= DOUBLE_DUMMY_TRICKS
+                        {
+                            (
+                                Method::DoubleDummy,
+                                double_dummy_values(&round, turn, &moves),
+                            )
+                        } else {
+                            let view = view_of(record, index, &round, turn);
+                            (Method::Sampled, self.sampled_values(&view, &moves, rng))
+                        };
+                        let value = |card: Card| values.get(&card).copied().unwrap_or(0.0);
+                        let best = moves
+                            .iter()
+                            .max_by(|a, b| value(*a).total_cmp(&value(*b)))
+                            .expect("must have a move");
+                        // the card played wins ties, so only a strictly better card is a mistake
+                        let best = if value(card) >= value(best) {
+                            card
+                        } else {
+                            best
+                        };
+                        let (expected, best_expected) = (value(card), value(best));
+                        review.plays.push(PlayReview {
+                            player: player.clone(),
+                            trick: t + 1,
+                            card,
+                            best,
+                            expected,
+                            best_expected,
+                            method,
+                            mistake: best_expected - expected >= MISTAKE,
+                        });
+                    }
+                }
+                round
+                    .play(card, turn)
+                    .expect("a recorded play must be valid");
+            }
+        }
+        review
+    }
+
+    // tricks the hand is expected to take, over deals that agree with the player's view
+    fn potential(&self, view: &GameView, played: &RoundRecord, rng: &mut StdRng) -> f64 {
+        let Some(determinizer) = Determinizer::new(view) else {
+            return 0.0;
+        };
+        let seat = Turn::new(determinizer.seat());
+        let (mut total, mut sampled) = (0.0, 0);
+        for _ in 0..self.samples {
+            let Some(mut round) = determinizer.sample(rng) else {
+                continue;
+            };
+            // the calls do not change how the round is played out, only that they are made
+            while let Ok(turn) = round.turn() {
+                if !round.is_calling() {
+                    break;
+                }
+                let call = played.calls[turn].unwrap_or(Call::new(1).unwrap());
+                round
+                    .call(call, turn)
+                    .expect("must accept a call on its turn");
+            }
+            play_out(&mut round, rng);
+            total += round.tricks_won()[seat] as f64;
+            sampled += 1;
+        }
+        if sampled == 0 {
+            0.0
+        } else {
+            total / sampled as f64
+        }
+    }
+
+    // tricks the player goes on to take after each of the moves, over the same sampled deals
+    fn sampled_values(
+        &self,
+        view: &GameView,
+        moves: &Hand,
+        rng: &mut StdRng,
+    ) -> HashMap<Card, f64> {
+        let mut values: HashMap<Card, f64> = moves.iter().map(|card| (card, 0.0)).collect();
+        let Some(determinizer) = Determinizer::new(view) else {
+            return values;
+        };
+        let seat = Turn::new(determinizer.seat());
+        let mut sampled = 0;
+        for _ in 0..self.samples {
+            let Some(round) = determinizer.sample(rng) else {
+                continue;
+            };
+            let before = round.tricks_won()[seat];
+            for card in moves.iter() {
+                let mut round = round.clone();
+                round.play(card, seat).expect("must be a valid move");
+                play_out(&mut round, rng);
+                *values.get_mut(&card).expect("must be a move") +=
+                    (round.tricks_won()[seat] - before) as f64;
+            }
+            sampled += 1;
+        }
+        if sampled > 0 {
+            values.values_mut().for_each(|v| *v /= sampled as f64);
+        }
+        values
+    }
+}
+
+// what the player at `turn` could see of the game with the round at this point
+fn view_of<R: TrickTakingRules>(
+    record: &Record,
+    index: usize,
+    round: &Round<R>,
+    turn: Turn,
+) -> GameView {
+    // INFO: the round is replayed up to the player's turn, so the calls in it are only the ones
+    // made before theirs
+    let mut rounds: Vec<RoundView> = record.rounds[..index]
+        .iter()
+        .map(|played| RoundView {
+            calls: played.calls,
+            hand: vec![],
+            trump: played.trump,
+            tricks: played.tricks.clone(),
+            claimed: played.claimed,
+        })
+        .collect();
+    rounds.push(RoundView {
+        calls: round.visible_calls(turn),
+        hand: round.get_hand(turn).iter().collect(),
+        trump: round.trump(),
+        tricks: round.get_tricks().iter().flatten().cloned().collect(),
+        claimed: *round.claimed(),
+    });
+    GameView {
+        players: record.players.clone(),
+        rules: record.rules,
+        ruleset: record.ruleset,
+        seats: record.players.iter().cloned().map(Some).collect(),
+        partner: record
+            .rules
+            .partner(usize::from(turn))
+            .map(|partner| record.players[partner].clone()),
+        seat: Some(usize::from(turn)),
+        standings: vec![],
+        rounds,
+    }
+}
+
+pub(crate) fn play_out<R: TrickTakingRules>(round: &mut Round<R>, rng: &mut StdRng) {
+    let mut rollout = GreedyRollout;
+    while let Ok(turn) = round.turn() {
+        let moves: Vec<Card> = round
+            .valid_moves(turn)
+            .expect("must have valid moves on its turn")
+            .iter()
+            .collect();
+        let trick: Vec<Card> = round
+            .current_trick()
+            .map(|trick| trick.plays().map(|(_, card)| card).collect())
+            .unwrap_or_default();
+        let trump = round
+            .trump()
+            .expect("a round being played must have its trump");
+        let card = rollout.play(&trick, &moves, trump, rng);
+        round
+            .play(card, turn)
+            .expect("rollout must play a valid move");
+    }
+}
+
+// tricks the player at `seat` is sure to go on to take after each of the moves, along with their
+// partner in partnerships, when every card is known and the others play to keep them from taking
+// any
+fn double_dummy_values<R: TrickTakingRules>(
+    round: &Round<R>,
+    seat: Turn,
+    moves: &Hand,
+) -> HashMap<Card, f64> {
+    let mut memo = HashMap::new();
+    let before = taken(round, seat);
+    moves
+        .iter()
+        .map(|card| {
+            let mut round = round.clone();
+            round.play(card, seat).expect("must be a valid move");
+            let tricks = solve(&round, seat, &mut memo);
+            (card, (taken(&round, seat) + tricks - before) as f64)
+        })
+        .collect()
+}
+
+fn card_name(card: Card) -> String {
+    format!("{} of {}", card.get_rank(), card.get_suit())
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+impl Report {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("must serialize without issue")
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# Game review\n");
+        if let Some(seed) = self.seed {
+            let _ = writeln!(out, "Seed: {seed}\n");
+        }
+        let _ = writeln!(
+            out,
+            "| player | mistakes | tricks lost | fair calls |\n|---|---|---|---|"
+        );
+        for s in self.summaries.iter() {
+            let _ = writeln!(
+                out,
+                "| {} | {} of {} plays | {:.1} | {} of {} |",
+                s.player, s.mistakes, s.plays, s.tricks_lost, s.fair_calls, s.calls
+            );
+        }
+        for (i, round) in self.rounds.iter().enumerate() {
+            let _ = writeln!(out, "\n## Round {}\n", i + 1);
+            let _ = writeln!(
+                out,
+                "| player | call | expected | taken | verdict |\n|---|---|---|---|---|"
+            );
+            for call in round.calls.iter() {
+                let _ = writeln!(
+                    out,
+                    "| {} | {} | {:.1} | {} | {:?} |",
+                    call.player, call.call, call.potential, call.taken, call.verdict
+                );
+            }
+            let mistakes: Vec<&PlayReview> = round.plays.iter().filter(|p| p.mistake).collect();
+            if mistakes.is_empty() {
+                let _ = writeln!(out, "\nNo mistakes in the play.");
+                continue;
+            }
+            let _ = writeln!(out);
+            for play in mistakes {
+                let _ = writeln!(
+                    out,
+                    "- Trick {}: {} played the {} for {:.1} tricks, the {} would have taken {:.1} ({})",
+                    play.trick,
+                    play.player,
+                    card_name(play.card),
+                    play.expected,
+                    card_name(play.best),
+                    play.best_expected,
+                    method_name(play.method),
+                );
+            }
+        }
+        out
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut out = String::from(
+            "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Game review</title></head>\n<body>\n<h1>Game review</h1>\n",
+        );
+        if let Some(seed) = self.seed {
+            let _ = writeln!(out, "<p>Seed: {seed}</p>");
+        }
+        let _ = writeln!(
+            out,
+            "<table>\n<tr><th>player</th><th>mistakes</th><th>tricks lost</th><th>fair calls</th></tr>"
+        );
+        for s in self.summaries.iter() {
+            let _ = writeln!(
+                out,
+                "<tr><td>{}</td><td>{} of {} plays</td><td>{:.1}</td><td>{} of {}</td></tr>",
+                escape(&s.player),
+                s.mistakes,
+                s.plays,
+                s.tricks_lost,
+                s.fair_calls,
+                s.calls
+            );
+        }
+        let _ = writeln!(out, "</table>");
+        for (i, round) in self.rounds.iter().enumerate() {
+            let _ = writeln!(out, "<h2>Round {}</h2>", i + 1);
+            let _ = writeln!(
+                out,
+                "<table>\n<tr><th>player</th><th>call</th><th>expected</th><th>taken</th><th>verdict</th></tr>"
+            );
+            for call in round.calls.iter() {
+                let _ = writeln!(
+                    out,
+                    "<tr><td>{}</td><td>{}</td><td>{:.1}</td><td>{}</td><td>{:?}</td></tr>",
+                    escape(&call.player),
+                    call.call,
+                    call.potential,
+                    call.taken,
+                    call.verdict
+                );
+            }
+            let _ = writeln!(out, "</table>\n<ul>");
+            for play in round.plays.iter().filter(|p| p.mistake) {
+                let _ = writeln!(
+                    out,
+                    "<li>Trick {}: {} played the {} for {:.1} tricks, the {} would have taken {:.1} ({})</li>",
+                    play.trick,
+                    escape(&play.player),
+                    card_name(play.card),
+                    play.expected,
+                    card_name(play.best),
+                    play.best_expected,
+                    method_name(play.method),
+                );
+            }
+            let _ = writeln!(out, "</ul>");
+        }
+        out.push_str("</body>\n</html>\n");
+        out
+    }
+}
+
+fn method_name(method: Method) -> &'static str {
+    match method {
+        Method::DoubleDummy => "double dummy",
+        Method::Sampled => "sampled",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::Difficulty;
+    use crate::game::CallBreak;
+    use crate::host::bot_game;
+
+    fn record(seed: u64) -> Record {
+        bot_game(seed, Difficulty::Random, CallBreak).record()
+    }
+
+    #[test]
+    fn reviews_only_the_players_asked_for() {
+        let record = record(3);
+        let report = Analyzer::new()
+            .with_samples(10)
+            .only(["bot0".to_string()])
+            .analyze(&record);
+        assert_eq!(report.summaries.len(), 1);
+        assert_eq!(report.rounds.len(), 5);
+        for round in report.rounds.iter() {
+            assert_eq!(round.calls.len(), 1);
+            assert!(round.plays.iter().all(|play| play.player == "bot0"));
+            assert!(round.plays.len() <= 13);
+        }
+        let summary = &report.summaries[0];
+        assert_eq!(summary.calls, 5);
+        assert!(summary.tricks_lost >= 0.0);
+    }
+
+    #[test]
+    fn replays_a_game_of_spades_by_its_ruleset() {
+        let host = bot_game(0, Difficulty::Random, crate::game::Spades::default());
+        let report = Analyzer::new().with_samples(5).analyze(&host.record());
+        assert_eq!(report.summaries.len(), 4);
+        assert!(!report.rounds.is_empty());
+    }
+
+    #[test]
+    fn best_card_is_never_worse_than_the_card_played() {
+        let report = Analyzer::new().with_samples(10).analyze(&record(5));
+        let plays = report.rounds.iter().flat_map(|round| round.plays.iter());
+        for play in plays {
+            assert!(play.best_expected >= play.expected);
+            assert_eq!(play.mistake, play.loss() >= MISTAKE);
+            if play.card == play.best {
+                assert!(!play.mistake);
+            }
+        }
+    }
+
+    #[test]
+    fn double_dummy_finds_the_card_that_takes_more() {
+        use crate::game::Rank::*;
+        use crate::game::Suit::*;
+        // leading the ace of spades draws out the king, leaving the two to win the last trick.
+        // leading the two lets the king win, and only the ace is left to trump back.
+        let hands = [
+            vec![Card::new(Ace, Spades), Card::new(Two, Spades)],
+            vec![Card::new(King, Spades), Card::new(Three, Hearts)],
+            vec![Card::new(Four, Hearts), Card::new(Five, Hearts)],
+            vec![Card::new(Six, Hearts), Card::new(Seven, Hearts)],
+        ];
+        let mut round = Round::from_hands(4, Turn::new(0), hands.map(Hand::new));
+        for seat in 0..4 {
+            round.call(Call::new(1).unwrap(), Turn::new(seat)).unwrap();
+        }
+        let moves = round.valid_moves(Turn::new(0)).unwrap();
+        let values = double_dummy_values(&round, Turn::new(0), &moves);
+        assert_eq!(values[&Card::new(Ace, Spades)], 2.0);
+        assert_eq!(values[&Card::new(Two, Spades)], 1.0);
+    }
+
+    #[test]
+    fn renders_every_round() {
+        let report = Analyzer::new().with_samples(5).analyze(&record(1));
+        let markdown = report.to_markdown();
+        let html = report.to_html();
+        assert_eq!(markdown.matches("## Round").count(), 5);
+        assert_eq!(html.matches("<h2>").count(), 5);
+        let json: Report = serde_json::from_str(&report.to_json()).unwrap();
+        assert_eq!(json.rounds.len(), 5);
+    }
+}
@@ -39,7 +39,7 @@ fn main() {
         .init();
     let mut host = Host::new();
     for id in 0..3 {
-        let agent = callbreak::agent::AgentKind::Bot(Bot);
+        let agent = callbreak::agent::AgentKind::Bot(Bot::default());
         host.add_agent(id.to_string(), agent)
             .expect("must be able to add 4 players");
     }
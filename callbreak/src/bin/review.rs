@@ -0,0 +1,109 @@
+use callbreak::analysis::Analyzer;
+use callbreak::game::Record;
+use std::io::Read;
+
+const USAGE: &str = "usage: review [--player NAME]... [--samples N] [--seed S] \
+[--format json|markdown|html] [RECORD]
+
+reviews a finished game from its record, as written by `Host::record()`, read from RECORD or
+from stdin. every player is reviewed unless some are picked with --player.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Json,
+    Markdown,
+    Html,
+}
+
+#[derive(Debug)]
+struct Options {
+    players: Vec<String>,
+    samples: Option<usize>,
+    seed: u64,
+    format: Format,
+    record: Option<String>,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        players: vec![],
+        samples: None,
+        seed: 0,
+        format: Format::Markdown,
+        record: None,
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{name} requires a value"));
+        match arg.as_str() {
+            "--player" => options.players.push(value("--player")?),
+            "--samples" => {
+                options.samples = Some(
+                    value("--samples")?
+                        .parse()
+                        .map_err(|_| "--samples requires a number")?,
+                )
+            }
+            "--seed" => {
+                options.seed = value("--seed")?
+                    .parse()
+                    .map_err(|_| "--seed requires a number")?
+            }
+            "--format" => {
+                options.format = match value("--format")?.as_str() {
+                    "json" => Format::Json,
+                    "markdown" | "md" => Format::Markdown,
+                    "html" => Format::Html,
+                    format => return Err(format!("unknown format: {format}")),
+                }
+            }
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            record => options.record = Some(record.to_string()),
+        }
+    }
+    Ok(options)
+}
+
+fn read_record(path: Option<&str>) -> Result<Record, String> {
+    let json = match path {
+        Some(path) => std::fs::read_to_string(path).map_err(|e| format!("{path}: {e}"))?,
+        None => {
+            let mut json = String::new();
+            std::io::stdin()
+                .read_to_string(&mut json)
+                .map_err(|e| format!("stdin: {e}"))?;
+            json
+        }
+    };
+    serde_json::from_str(&json).map_err(|e| format!("not a game record: {e}"))
+}
+
+fn main() {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{message}");
+            std::process::exit(2);
+        }
+    };
+    let record = match read_record(options.record.as_deref()) {
+        Ok(record) => record,
+        Err(message) => {
+            eprintln!("{message}");
+            std::process::exit(1);
+        }
+    };
+    let mut analyzer = Analyzer::new().with_seed(options.seed);
+    if let Some(samples) = options.samples {
+        analyzer = analyzer.with_samples(samples);
+    }
+    if !options.players.is_empty() {
+        analyzer = analyzer.only(options.players);
+    }
+    let report = analyzer.analyze(&record);
+    match options.format {
+        Format::Json => println!("{}", report.to_json()),
+        Format::Markdown => print!("{}", report.to_markdown()),
+        Format::Html => print!("{}", report.to_html()),
+    }
+}
@@ -0,0 +1,485 @@
+use callbreak::Host;
+use callbreak::agent::{AgentKind, Bot, Budget, CallingModel, Difficulty, Ismcts, Style};
+use callbreak::dataset::{Format, GameData, Writer};
+use callbreak::duplicate::{self, TABLES};
+use callbreak::game::Record;
+use callbreak::stats::Meter;
+use serde::Serialize;
+use std::fs::File;
+use std::io::BufWriter;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+const USAGE: &str =
+    "usage: tournament [--games N] [--seed S] [--threads T] [--iterations I] [--json] \
+[--duplicate] [--export PATH] [--calling-model PATH] ENTRANT ENTRANT [ENTRANT...]
+
+an entrant is a bot configuration written as difficulty[:style], e.g. expert:spade-hoarder
+difficulties: random, beginner, intermediate, expert
+styles: balanced, aggressive, cautious, spade-hoarder
+--duplicate also scores every entrant against the others that held the same cards
+--export writes every decision taken to PATH, as binary if it ends in .bin and JSON lines otherwise
+--calling-model has the intermediate and expert bots call from a model trained by `train`
+luck is the tricks an entrant's hands were worth beyond the others' at the table, per game, and the
+adjusted score is the average score with that luck taken off";
+
+// rating every entrant starts from, and how far a single game can move it
+const ELO_START: f64 = 1500.0;
+const ELO_K: f64 = 16.0;
+// z value for a 95% confidence interval
+const Z: f64 = 1.96;
+
+#[derive(Debug, Clone, Copy)]
+struct Entrant {
+    difficulty: Difficulty,
+    style: Style,
+}
+
+impl Entrant {
+    fn parse(s: &str) -> Result<Self, String> {
+        let parse = |value: &str| serde_json::Value::String(value.to_string());
+        let (difficulty, style) = s.split_once(':').unwrap_or((s, "balanced"));
+        Ok(Self {
+            difficulty: serde_json::from_value(parse(difficulty))
+                .map_err(|_| format!("unknown difficulty: {difficulty}"))?,
+            style: serde_json::from_value(parse(style))
+                .map_err(|_| format!("unknown style: {style}"))?,
+        })
+    }
+
+    fn label(&self) -> String {
+        format!("{}:{}", self.difficulty, self.style)
+    }
+}
+
+#[derive(Debug)]
+struct Options {
+    games: usize,
+    seed: u64,
+    threads: usize,
+    iterations: Option<usize>,
+    json: bool,
+    duplicate: bool,
+    export: Option<String>,
+    calling: Option<Arc<CallingModel>>,
+    entrants: Vec<Entrant>,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        games: 100,
+        seed: 0,
+        threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        iterations: None,
+        json: false,
+        duplicate: false,
+        export: None,
+        calling: None,
+        entrants: vec![],
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .and_then(|v| v.parse::<u64>().ok())
+                .ok_or(format!("{name} requires a number"))
+        };
+        match arg.as_str() {
+            "--games" => options.games = value("--games")? as usize,
+            "--seed" => options.seed = value("--seed")?,
+            "--threads" => options.threads = (value("--threads")? as usize).max(1),
+            "--iterations" => options.iterations = Some(value("--iterations")? as usize),
+            "--json" => options.json = true,
+            "--duplicate" => options.duplicate = true,
+            "--calling-model" => {
+                let path = args.next().ok_or("--calling-model requires a path")?;
+                let model =
+                    CallingModel::load(&path).map_err(|e| format!("could not load {path}: {e}"))?;
+                options.calling = Some(Arc::new(model));
+            }
+            "--export" => options.export = Some(args.next().ok_or("--export requires a path")?),
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            entrant => options.entrants.push(Entrant::parse(entrant)?),
+        }
+    }
+    if options.entrants.len() < 2 {
+        return Err(format!("need at least two entrants\n\n{USAGE}"));
+    }
+    if options.duplicate {
+        // only whole sets of tables can be compared against each other
+        options.games = options.games.div_ceil(TABLES) * TABLES;
+    }
+    Ok(options)
+}
+
+// spread a seed over several independent streams
+fn mix(seed: u64, stream: u64) -> u64 {
+    let mut z = seed ^ stream.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+/// What happened to each seat of a single game
+#[derive(Debug)]
+struct Outcome {
+    // entrant sitting at each seat
+    entrants: [usize; 4],
+    scores: [f32; 4],
+    // filled in once every table on the same deals is over
+    versus_par: [f32; 4],
+    // tricks the hands were worth beyond the others' at the table, and the scores with that taken off
+    luck: [f64; 4],
+    adjusted: [f64; 4],
+    calls: [u32; 4],
+    made: [u32; 4],
+    exact: [u32; 4],
+}
+
+// INFO: games are played in blocks of four on the same deals, with the lineup moving one seat
+// along every game, so that no entrant is favoured by the cards it happened to be dealt. this
+// is exactly how a duplicate match is played, so the blocks double as duplicate matches.
+// returns the place in the block's lineup of the agent joining in each position
+fn lineup(game: usize) -> [usize; 4] {
+    let rotation = game % TABLES;
+    std::array::from_fn(|i| (i + rotation) % 4)
+}
+
+fn play(options: &Options, game: usize) -> (Outcome, Record, Option<GameData>) {
+    let block = game / TABLES;
+    let deals = mix(options.seed, block as u64);
+    let mut host = Host::with_seed(deals);
+    if options.export.is_some() {
+        host = host.recording();
+    }
+    let entrant = |place: usize| (block + place) % options.entrants.len();
+    // the id stays the same over the block so that the tables can be compared
+    let ids: [String; 4] =
+        lineup(game).map(|place| format!("{place}:{}", options.entrants[entrant(place)].label()));
+    for (i, id) in ids.iter().enumerate() {
+        let entrant = options.entrants[entrant(lineup(game)[i])];
+        let seed = mix(options.seed, (game * 4 + i) as u64 + 1);
+        let mut bot = Bot::new(entrant.difficulty, entrant.style).with_seed(seed);
+        if let Some(iterations) = options.iterations {
+            bot = bot.with_search(Ismcts::new(Budget::Iterations(iterations)).with_seed(seed));
+        }
+        if let Some(model) = &options.calling {
+            bot = bot.with_calling_model(model.clone());
+        }
+        host.add_agent(id.clone(), AgentKind::Bot(bot))
+            .expect("must be able to add 4 bots");
+    }
+    host.run();
+    let record = host.record();
+    let outcome = summarize(&record, |player| {
+        entrant(
+            lineup(game)[ids
+                .iter()
+                .position(|id| id == player)
+                .expect("player must be one of the bots")],
+        )
+    });
+    (outcome, record, host.dataset())
+}
+
+fn summarize(record: &Record, entrant: impl Fn(&str) -> usize) -> Outcome {
+    let luck = Meter::default().measure(record);
+    let mut outcome = Outcome {
+        entrants: std::array::from_fn(|seat| entrant(&record.players[seat])),
+        scores: record.scores(),
+        versus_par: [0.0; 4],
+        luck: std::array::from_fn(|seat| luck.summaries[seat].luck),
+        adjusted: std::array::from_fn(|seat| luck.summaries[seat].adjusted),
+        calls: [0; 4],
+        made: [0; 4],
+        exact: [0; 4],
+    };
+    for round in record.rounds.iter().filter(|round| round.is_over()) {
+        let won = round.tricks_won();
+        for (seat, call) in round.calls.iter().enumerate() {
+            let call = call.expect("a round that is over must have all calls");
+            outcome.calls[seat] += 1;
+            outcome.made[seat] += call.is_made(won[seat]) as u32;
+            outcome.exact[seat] += (won[seat] == call.value()) as u32;
+        }
+    }
+    outcome
+}
+
+type Export = Mutex<Writer<BufWriter<File>>>;
+
+fn open_export(path: &str) -> std::io::Result<Export> {
+    let format = if path.ends_with(".bin") {
+        Format::Binary
+    } else {
+        Format::Jsonl
+    };
+    let writer = Writer::new(BufWriter::new(File::create(path)?), format)?;
+    Ok(Mutex::new(writer))
+}
+
+fn run(options: &Options, export: Option<&Export>) -> std::io::Result<Vec<Outcome>> {
+    let next = AtomicUsize::new(0);
+    let outcomes = Mutex::new(vec![]);
+    let failed = Mutex::new(None);
+    thread::scope(|scope| {
+        for _ in 0..options.threads.min(options.games) {
+            scope.spawn(|| {
+                loop {
+                    let game = next.fetch_add(1, Ordering::Relaxed);
+                    if game >= options.games {
+                        break;
+                    }
+                    let (outcome, record, data) = play(options, game);
+                    // INFO: games are written as they finish to keep memory flat over long
+                    // runs, every game carries its seed so the order does not matter
+                    if let (Some(export), Some(data)) = (export, data)
+                        && let Err(e) = export.lock().unwrap().write(&data)
+                    {
+                        failed.lock().unwrap().get_or_insert(e);
+                    }
+                    outcomes.lock().unwrap().push((game, (outcome, record)));
+                }
+            });
+        }
+    });
+    if let Some(e) = failed.into_inner().unwrap() {
+        return Err(e);
+    }
+    let mut outcomes = outcomes.into_inner().unwrap();
+    // keep the report independent of how the games were spread over the threads
+    outcomes.sort_by_key(|(game, _)| *game);
+    let (mut outcomes, records): (Vec<Outcome>, Vec<Record>) =
+        outcomes.into_iter().map(|(_, played)| played).unzip();
+    if options.duplicate {
+        for (outcomes, records) in outcomes.chunks_mut(TABLES).zip(records.chunks(TABLES)) {
+            let standings = duplicate::score(records).expect("a block must share its deals");
+            for (outcome, record) in outcomes.iter_mut().zip(records) {
+                for (seat, player) in record.players.iter().enumerate() {
+                    let standing = standings
+                        .iter()
+                        .find(|s| &s.player == player)
+                        .expect("every player must have a standing");
+                    // spread over the tables so that it reads as per game like the scores
+                    outcome.versus_par[seat] = standing.versus_par / TABLES as f32;
+                }
+            }
+        }
+    }
+    Ok(outcomes)
+}
+
+/// Value along with the half width of its 95% confidence interval
+#[derive(Debug, Clone, Copy, Serialize)]
+struct Estimate {
+    value: f64,
+    margin: f64,
+}
+
+impl Estimate {
+    fn mean(samples: &[f64]) -> Self {
+        let n = samples.len() as f64;
+        if n == 0.0 {
+            return Self {
+                value: 0.0,
+                margin: 0.0,
+            };
+        }
+        let mean = samples.iter().sum::<f64>() / n;
+        let variance = if n > 1.0 {
+            samples.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0)
+        } else {
+            0.0
+        };
+        Self {
+            value: mean,
+            margin: Z * (variance / n).sqrt(),
+        }
+    }
+
+    fn rate(hits: u32, total: u32) -> Self {
+        if total == 0 {
+            return Self {
+                value: 0.0,
+                margin: 0.0,
+            };
+        }
+        let p = hits as f64 / total as f64;
+        Self {
+            value: p,
+            margin: Z * (p * (1.0 - p) / total as f64).sqrt(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Standing {
+    entrant: String,
+    games: usize,
+    win_rate: Estimate,
+    average_score: Estimate,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    versus_par: Option<Estimate>,
+    luck: Estimate,
+    adjusted_score: Estimate,
+    calls_made: Estimate,
+    calls_exact: Estimate,
+    elo: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    games: usize,
+    seed: u64,
+    duplicate: bool,
+    standings: Vec<Standing>,
+}
+
+fn report(options: &Options, outcomes: &[Outcome]) -> Report {
+    let n = options.entrants.len();
+    let mut wins = vec![vec![]; n];
+    let mut scores = vec![vec![]; n];
+    let mut versus_par = vec![vec![]; n];
+    let mut luck = vec![vec![]; n];
+    let mut adjusted = vec![vec![]; n];
+    let mut calls = vec![(0, 0, 0); n];
+    let mut elo = vec![ELO_START; n];
+
+    for outcome in outcomes {
+        let best = outcome.scores.iter().copied().fold(f32::MIN, f32::max);
+        let winners = outcome.scores.iter().filter(|s| **s == best).count() as f64;
+        for seat in 0..4 {
+            let entrant = outcome.entrants[seat];
+            let won = if outcome.scores[seat] == best {
+                1.0 / winners
+            } else {
+                0.0
+            };
+            wins[entrant].push(won);
+            scores[entrant].push(outcome.scores[seat] as f64);
+            versus_par[entrant].push(outcome.versus_par[seat] as f64);
+            luck[entrant].push(outcome.luck[seat]);
+            adjusted[entrant].push(outcome.adjusted[seat]);
+            calls[entrant].0 += outcome.calls[seat];
+            calls[entrant].1 += outcome.made[seat];
+            calls[entrant].2 += outcome.exact[seat];
+        }
+
+        // every pair of seats is a head to head match, the k factor is shared among the
+        // three opponents so that a game is worth the same whatever the lineup
+        let before = elo.clone();
+        for a in 0..4 {
+            for b in 0..4 {
+                let (ea, eb) = (outcome.entrants[a], outcome.entrants[b]);
+                if ea == eb {
+                    continue;
+                }
+                let expected = 1.0 / (1.0 + 10f64.powf((before[eb] - before[ea]) / 400.0));
+                let actual = match outcome.scores[a].total_cmp(&outcome.scores[b]) {
+                    std::cmp::Ordering::Greater => 1.0,
+                    std::cmp::Ordering::Equal => 0.5,
+                    std::cmp::Ordering::Less => 0.0,
+                };
+                elo[ea] += ELO_K / 3.0 * (actual - expected);
+            }
+        }
+    }
+
+    let mut standings: Vec<Standing> = (0..n)
+        .map(|i| Standing {
+            entrant: options.entrants[i].label(),
+            games: wins[i].len(),
+            win_rate: Estimate::mean(&wins[i]),
+            average_score: Estimate::mean(&scores[i]),
+            versus_par: options.duplicate.then(|| Estimate::mean(&versus_par[i])),
+            luck: Estimate::mean(&luck[i]),
+            adjusted_score: Estimate::mean(&adjusted[i]),
+            calls_made: Estimate::rate(calls[i].1, calls[i].0),
+            calls_exact: Estimate::rate(calls[i].2, calls[i].0),
+            elo: elo[i],
+        })
+        .collect();
+    standings.sort_by(|a, b| b.elo.total_cmp(&a.elo));
+    Report {
+        games: outcomes.len(),
+        seed: options.seed,
+        duplicate: options.duplicate,
+        standings,
+    }
+}
+
+fn print_table(report: &Report) {
+    let kind = if report.duplicate { " duplicate" } else { "" };
+    println!("{}{kind} games, seed {}", report.games, report.seed);
+    println!(
+        "{:<28} {:>6} {:>15} {:>15} {:>15} {:>15} {:>15} {:>15} {:>15} {:>7}",
+        "entrant",
+        "games",
+        "win rate",
+        "avg score",
+        "vs par",
+        "luck",
+        "adj score",
+        "calls made",
+        "calls exact",
+        "elo"
+    );
+    let percent = |e: Estimate| format!("{:.1}% ±{:.1}", e.value * 100.0, e.margin * 100.0);
+    let points = |e: Estimate| format!("{:.2} ±{:.2}", e.value, e.margin);
+    for s in report.standings.iter() {
+        println!(
+            "{:<28} {:>6} {:>15} {:>15} {:>15} {:>15} {:>15} {:>15} {:>15} {:>7.0}",
+            s.entrant,
+            s.games,
+            percent(s.win_rate),
+            points(s.average_score),
+            s.versus_par.map_or("-".to_string(), points),
+            points(s.luck),
+            points(s.adjusted_score),
+            percent(s.calls_made),
+            percent(s.calls_exact),
+            s.elo
+        );
+    }
+}
+
+fn main() {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{message}");
+            std::process::exit(2);
+        }
+    };
+    let export = match options.export.as_deref().map(open_export).transpose() {
+        Ok(export) => export,
+        Err(e) => {
+            eprintln!("could not create the export: {e}");
+            std::process::exit(1);
+        }
+    };
+    let outcomes = run(&options, export.as_ref()).and_then(|outcomes| {
+        if let Some(export) = export {
+            use std::io::Write;
+            export.into_inner().unwrap().into_inner().flush()?;
+        }
+        Ok(outcomes)
+    });
+    let outcomes = match outcomes {
+        Ok(outcomes) => outcomes,
+        Err(e) => {
+            eprintln!("could not write the export: {e}");
+            std::process::exit(1);
+        }
+    };
+    let report = report(&options, &outcomes);
+    if options.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).expect("must serialize without issue")
+        );
+    } else {
+        print_table(&report);
+    }
+}
@@ -0,0 +1,173 @@
+use callbreak::Host;
+use callbreak::agent::{
+    AgentKind, Bot, Calibration, CallingModel, CallingSample, Difficulty, Style, heuristic,
+};
+use callbreak::dataset;
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+
+const USAGE: &str = "usage: train [--games N] [--seed S] [--threads T] [--epochs E] [--rate R] \
+[--out PATH] [--json] [DATASET...]
+
+trains the calling model on the calls of binary datasets written by `tournament --export`, or
+on N games of self-play between intermediate bots when no dataset is given. one hand in five
+is held back to report how well the model is calibrated against the hand tuned heuristics.";
+
+// every this many hands one is held back from training
+const HOLDOUT: usize = 5;
+
+#[derive(Debug)]
+struct Options {
+    games: u64,
+    seed: u64,
+    threads: usize,
+    epochs: usize,
+    rate: f32,
+    out: String,
+    json: bool,
+    datasets: Vec<String>,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut options = Options {
+        games: 2000,
+        seed: 0,
+        threads: thread::available_parallelism().map_or(1, |n| n.get()),
+        epochs: 100,
+        rate: 0.5,
+        out: "calling.json".to_string(),
+        json: false,
+        datasets: vec![],
+    };
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or(format!("{name} requires a value"));
+        match arg.as_str() {
+            "--games" => options.games = number("--games", value("--games")?)?,
+            "--seed" => options.seed = number("--seed", value("--seed")?)?,
+            "--threads" => {
+                options.threads = number::<usize>("--threads", value("--threads")?)?.max(1)
+            }
+            "--epochs" => options.epochs = number("--epochs", value("--epochs")?)?,
+            "--rate" => options.rate = number("--rate", value("--rate")?)?,
+            "--out" => options.out = value("--out")?,
+            "--json" => options.json = true,
+            "-h" | "--help" => return Err(USAGE.to_string()),
+            dataset => options.datasets.push(dataset.to_string()),
+        }
+    }
+    Ok(options)
+}
+
+fn number<T: std::str::FromStr>(name: &str, value: String) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{name} requires a number"))
+}
+
+fn self_play(options: &Options) -> Vec<CallingSample> {
+    let next = AtomicU64::new(0);
+    let samples = Mutex::new(vec![]);
+    thread::scope(|scope| {
+        for _ in 0..options.threads {
+            scope.spawn(|| {
+                loop {
+                    let game = next.fetch_add(1, Ordering::Relaxed);
+                    if game >= options.games {
+                        break;
+                    }
+                    let seed = options.seed.wrapping_add(game);
+                    let mut host = Host::with_seed(seed).recording();
+                    for i in 0..4 {
+                        let bot = Bot::new(Difficulty::Intermediate, Style::Balanced)
+                            .with_seed(seed.wrapping_mul(4).wrapping_add(i));
+                        host.add_agent(i.to_string(), AgentKind::Bot(bot))
+                            .expect("must be able to add 4 bots");
+                    }
+                    host.run();
+                    let data = host.dataset().expect("host must be recording");
+                    samples
+                        .lock()
+                        .unwrap()
+                        .push((game, CallingSample::from_dataset(&data)));
+                }
+            });
+        }
+    });
+    let mut samples = samples.into_inner().unwrap();
+    // keep the model independent of how the games were spread over the threads
+    samples.sort_by_key(|(game, _)| *game);
+    samples
+        .into_iter()
+        .flat_map(|(_, samples)| samples)
+        .collect()
+}
+
+fn load(paths: &[String]) -> std::io::Result<Vec<CallingSample>> {
+    let mut samples = vec![];
+    for path in paths {
+        for game in dataset::read_binary(BufReader::new(File::open(path)?))? {
+            samples.extend(CallingSample::from_dataset(&game));
+        }
+    }
+    Ok(samples)
+}
+
+fn main() {
+    let options = match parse_args() {
+        Ok(options) => options,
+        Err(message) => {
+            eprintln!("{message}");
+            std::process::exit(2);
+        }
+    };
+    let samples = if options.datasets.is_empty() {
+        self_play(&options)
+    } else {
+        match load(&options.datasets) {
+            Ok(samples) => samples,
+            Err(e) => {
+                eprintln!("could not read the datasets: {e}");
+                std::process::exit(1);
+            }
+        }
+    };
+    let (mut train, mut test) = (vec![], vec![]);
+    for (i, sample) in samples.into_iter().enumerate() {
+        if i % HOLDOUT == 0 {
+            test.push(sample);
+        } else {
+            train.push(sample);
+        }
+    }
+
+    let model = CallingModel::train(&train, options.epochs, options.rate, options.seed);
+    if let Err(e) = model.save(&options.out) {
+        eprintln!("could not save the model: {e}");
+        std::process::exit(1);
+    }
+    let trained = Calibration::measure(&test, |hand| model.predict(hand));
+    let heuristic = Calibration::measure(&test, heuristic);
+    if options.json {
+        let report = serde_json::json!({
+            "trained_on": train.len(),
+            "model": trained,
+            "heuristic": heuristic,
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).expect("must serialize without issue")
+        );
+    } else {
+        println!(
+            "trained on {} hands, saved to {}\n",
+            train.len(),
+            options.out
+        );
+        println!("model\n{trained}");
+        println!("heuristic\n{heuristic}");
+    }
+}
@@ -0,0 +1,392 @@
+//! Decisions taken over whole games, written out for offline learning.
+//!
+//! A [`Host`](crate::Host) that records its decisions hands back a [`GameData`] once the game is
+//! over, which a [`Writer`] turns into either JSON lines, one decision to a line, or a compact
+//! binary format that [`read_binary`] reads back.
+
+use crate::env::{ACTIONS, OBSERVATION};
+use crate::game::Suit;
+use serde::{Deserialize, Serialize};
+use std::io::{self, Read, Write};
+
+/// Version of the crate that played the games, recorded with every game
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+const MAGIC: &[u8; 4] = b"CBDS";
+const FORMAT_VERSION: u32 = 3;
+const GAME: u8 = 1;
+const DECISION: u8 = 2;
+
+/// What came of the round a decision was taken in, for the seat that took it
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RoundResult {
+    pub call: u8,
+    pub tricks: u8,
+    pub score: f32,
+    /// Trump the round was played with
+    #[serde(default = "spades")]
+    pub trump: Suit,
+}
+
+fn spades() -> Suit {
+    Suit::Spades
+}
+
+/// A single call or play, with what the player could see when making it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Decision {
+    pub seat: u8,
+    pub round: u8,
+    /// Encoding of the player's view, see [`crate::env::encode`]
+    pub observation: Vec<f32>,
+    /// Actions the player could have taken, in increasing order, see [`crate::env::Action`]
+    pub legal: Vec<u8>,
+    pub action: u8,
+    /// Left out when the game stopped before the round was over
+    pub result: Option<RoundResult>,
+}
+
+/// Who sat at a seat and what was making their decisions
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerData {
+    pub id: String,
+    /// Kind and configuration of the agent, e.g. `bot (expert, balanced)`
+    pub agent: String,
+}
+
+/// Every decision taken over a game
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameData {
+    pub seed: Option<u64>,
+    pub version: String,
+    /// Players in the order of their seats
+    pub players: Vec<PlayerData>,
+    pub decisions: Vec<Decision>,
+}
+
+/// Format a [`Writer`] writes in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Jsonl,
+    Binary,
+}
+
+// a line of the JSON lines output, which stands on its own
+#[derive(Serialize)]
+struct Row<'a> {
+    game: u64,
+    seed: Option<u64>,
+    version: &'a str,
+    player: &'a str,
+    agent: &'a str,
+    #[serde(flatten)]
+    decision: &'a Decision,
+}
+
+/// Writes games out one after the other
+#[derive(Debug)]
+pub struct Writer<W: Write> {
+    inner: W,
+    format: Format,
+    games: u64,
+}
+
+impl<W: Write> Writer<W> {
+    pub fn new(mut inner: W, format: Format) -> io::Result<Self> {
+        if format == Format::Binary {
+            inner.write_all(MAGIC)?;
+            inner.write_all(&FORMAT_VERSION.to_le_bytes())?;
+            inner.write_all(&(OBSERVATION as u32).to_le_bytes())?;
+        }
+        Ok(Self {
+            inner,
+            format,
+            games: 0,
+        })
+    }
+
+    pub fn write(&mut self, game: &GameData) -> io::Result<()> {
+        match self.format {
+            Format::Jsonl => self.write_jsonl(game)?,
+            Format::Binary => self.write_binary(game)?,
+        }
+        self.games += 1;
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    fn write_jsonl(&mut self, game: &GameData) -> io::Result<()> {
+        for decision in game.decisions.iter() {
+            let player = &game.players[decision.seat as usize];
+            let row = Row {
+                game: self.games,
+                seed: game.seed,
+                version: &game.version,
+                player: &player.id,
+                agent: &player.agent,
+                decision,
+            };
+            serde_json::to_writer(&mut self.inner, &row)?;
+            self.inner.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+
+    // INFO: a game is a header block followed by one block per decision. everything is little
+    // endian, strings are a u16 length followed by utf-8, and the legal actions are a bitmask
+    // over the 66 actions in 9 bytes. the round result follows a byte telling whether there is
+    // one, since a nil call is a zero, and the trump goes by its place in the suits.
+    fn write_binary(&mut self, game: &GameData) -> io::Result<()> {
+        let w = &mut self.inner;
+        w.write_all(&[GAME, game.seed.is_some() as u8])?;
+        w.write_all(&game.seed.unwrap_or(0).to_le_bytes())?;
+        write_str(w, &game.version)?;
+        w.write_all(&[game.players.len() as u8])?;
+        for player in game.players.iter() {
+            write_str(w, &player.id)?;
+            write_str(w, &player.agent)?;
+        }
+        w.write_all(&(game.decisions.len() as u32).to_le_bytes())?;
+        for decision in game.decisions.iter() {
+            let mut legal = [0u8; 9];
+            for action in decision.legal.iter() {
+                legal[*action as usize / 8] |= 1 << (action % 8);
+            }
+            let result = decision.result.unwrap_or(RoundResult {
+                call: 0,
+                tricks: 0,
+                score: 0.0,
+                trump: Suit::Spades,
+            });
+            w.write_all(&[DECISION, decision.seat, decision.round, decision.action])?;
+            w.write_all(&legal)?;
+            w.write_all(&[
+                decision.result.is_some() as u8,
+                result.call,
+                result.tricks,
+                result.trump as u8,
+            ])?;
+            w.write_all(&result.score.to_le_bytes())?;
+            for value in decision.observation.iter() {
+                w.write_all(&value.to_le_bytes())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_str(w: &mut impl Write, s: &str) -> io::Result<()> {
+    let len = u16::try_from(s.len()).map_err(|_| io::Error::other("string is too long"))?;
+    w.write_all(&len.to_le_bytes())?;
+    w.write_all(s.as_bytes())
+}
+
+fn read_array<const N: usize>(r: &mut impl Read) -> io::Result<[u8; N]> {
+    let mut buf = [0; N];
+    r.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn read_str(r: &mut impl Read) -> io::Result<String> {
+    let len = u16::from_le_bytes(read_array(r)?) as usize;
+    let mut buf = vec![0; len];
+    r.read_exact(&mut buf)?;
+    String::from_utf8(buf).map_err(io::Error::other)
+}
+
+/// Read back every game of a file written in the binary format
+pub fn read_binary(mut r: impl Read) -> io::Result<Vec<GameData>> {
+    let invalid = |message: &str| io::Error::new(io::ErrorKind::InvalidData, message.to_string());
+    if &read_array::<4>(&mut r)? != MAGIC {
+        return Err(invalid("not a dataset file"));
+    }
+    if u32::from_le_bytes(read_array(&mut r)?) != FORMAT_VERSION {
+        return Err(invalid("unsupported dataset version"));
+    }
+    let observation = u32::from_le_bytes(read_array(&mut r)?) as usize;
+
+    let mut games = vec![];
+    loop {
+        let mut tag = [0u8; 1];
+        if r.read(&mut tag)? == 0 {
+            return Ok(games);
+        }
+        if tag[0] != GAME {
+            return Err(invalid("expected a game"));
+        }
+        let [has_seed] = read_array(&mut r)?;
+        let seed = u64::from_le_bytes(read_array(&mut r)?);
+        let version = read_str(&mut r)?;
+        let [players] = read_array(&mut r)?;
+        let players = (0..players)
+            .map(|_| {
+                Ok(PlayerData {
+                    id: read_str(&mut r)?,
+                    agent: read_str(&mut r)?,
+                })
+            })
+            .collect::<io::Result<Vec<PlayerData>>>()?;
+        let decisions = u32::from_le_bytes(read_array(&mut r)?);
+        let decisions = (0..decisions)
+            .map(|_| {
+                let [tag, seat, round, action] = read_array(&mut r)?;
+                if tag != DECISION {
+                    return Err(invalid("expected a decision"));
+                }
+                let legal: [u8; 9] = read_array(&mut r)?;
+                let [has_result, call, tricks, trump] = read_array(&mut r)?;
+                let trump = *Suit::ALL
+                    .get(trump as usize)
+                    .ok_or_else(|| invalid("expected a suit"))?;
+                let score = f32::from_le_bytes(read_array(&mut r)?);
+                let observation = (0..observation)
+                    .map(|_| Ok(f32::from_le_bytes(read_array(&mut r)?)))
+                    .collect::<io::Result<Vec<f32>>>()?;
+                Ok(Decision {
+                    seat,
+                    round,
+                    observation,
+                    legal: (0..ACTIONS as u8)
+                        .filter(|a| legal[*a as usize / 8] & 1 << (a % 8) != 0)
+                        .collect(),
+                    action,
+                    result: (has_result != 0).then_some(RoundResult {
+                        call,
+                        tricks,
+                        score,
+                        trump,
+                    }),
+                })
+            })
+            .collect::<io::Result<Vec<Decision>>>()?;
+        games.push(GameData {
+            seed: (has_seed != 0).then_some(seed),
+            version,
+            players,
+            decisions,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Host;
+    use crate::agent::Difficulty;
+    use crate::env::CALLS;
+    use crate::game::{CLAIM_TRICKS, Rules};
+
+    fn recorded_game(seed: u64) -> GameData {
+        recorded_game_with(seed, Rules::default())
+    }
+
+    fn recorded_game_with(seed: u64, rules: Rules) -> GameData {
+        let mut host = Host::with_seed(seed)
+            .with_rules(rules)
+            .recording()
+            .with_bots(Difficulty::Beginner);
+        host.run();
+        host.dataset().unwrap()
+    }
+
+    #[test]
+    fn sealed_calls_are_each_taken_without_seeing_the_others() {
+        let game = recorded_game_with(5, Rules::default().with_sealed_calls());
+        let calls: Vec<&Decision> = game.decisions.iter().filter(|d| d.action >= 52).collect();
+        assert_eq!(calls.len(), 20);
+        // no caller saw any call, however late they were asked
+        for decision in calls {
+            assert!((0..4).all(|other| decision.observation[CALLS + other * 15] == 1.0));
+        }
+        assert!(game.decisions.iter().all(|d| d.result.is_some()));
+    }
+
+    #[test]
+    fn every_decision_of_the_game_is_recorded() {
+        let game = recorded_game(4);
+        assert_eq!(game.seed, Some(4));
+        for round in 0..5 {
+            let decisions = game.decisions.iter().filter(|d| d.round == round);
+            let calls = decisions.clone().filter(|d| d.action >= 52).count();
+            // the tricks after a claim are played out by the engine rather than decided
+            let plays = decisions.count() - calls;
+            assert_eq!(calls, 4);
+            assert!((52 - 4 * CLAIM_TRICKS..=52).contains(&plays));
+        }
+        for decision in game.decisions.iter() {
+            assert!(decision.legal.contains(&decision.action));
+            assert_eq!(decision.observation.len(), OBSERVATION);
+            assert!(decision.result.is_some());
+        }
+        assert!(
+            game.players
+                .iter()
+                .all(|p| p.agent == "bot (beginner, balanced)")
+        );
+    }
+
+    #[test]
+    fn nil_calls_of_spades_are_legal() {
+        let mut host = Host::with_seed(3)
+            .with_ruleset(crate::game::Spades::default())
+            .recording()
+            .with_bots(Difficulty::Intermediate);
+        host.run();
+        let game = host.dataset().unwrap();
+        let nil = (ACTIONS - 1) as u8;
+        assert!(game.decisions.iter().any(|d| d.action == nil));
+        for decision in game.decisions.iter() {
+            assert!(decision.legal.contains(&decision.action));
+        }
+    }
+
+    #[test]
+    fn binary_format_reads_back_the_same_games() {
+        let games = [recorded_game(1), recorded_game(2)];
+        let mut writer = Writer::new(vec![], Format::Binary).unwrap();
+        for game in games.iter() {
+            writer.write(game).unwrap();
+        }
+        let bytes = writer.into_inner();
+        assert_eq!(read_binary(bytes.as_slice()).unwrap(), games);
+    }
+
+    #[test]
+    fn binary_format_reads_back_nil_calls() {
+        let game = recorded_game_with(6, Rules::default().with_nil());
+        let mut nil = game.clone();
+        // a bot need not call nil, so one is put in by hand
+        let decision = nil.decisions.iter_mut().find(|d| d.action >= 52).unwrap();
+        assert_eq!(decision.legal.last(), Some(&((ACTIONS - 1) as u8)));
+        decision.action = (ACTIONS - 1) as u8;
+        decision.result = Some(RoundResult {
+            call: 0,
+            tricks: 0,
+            score: 10.0,
+            trump: Suit::Hearts,
+        });
+        let games = [game, nil];
+        let mut writer = Writer::new(vec![], Format::Binary).unwrap();
+        for game in games.iter() {
+            writer.write(game).unwrap();
+        }
+        let bytes = writer.into_inner();
+        assert_eq!(read_binary(bytes.as_slice()).unwrap(), games);
+    }
+
+    #[test]
+    fn jsonl_has_a_line_per_decision() {
+        let game = recorded_game(3);
+        let mut writer = Writer::new(vec![], Format::Jsonl).unwrap();
+        writer.write(&game).unwrap();
+        let text = String::from_utf8(writer.into_inner()).unwrap();
+        assert_eq!(text.lines().count(), game.decisions.len());
+        let row: serde_json::Value = serde_json::from_str(text.lines().next().unwrap()).unwrap();
+        assert_eq!(row["seed"], 3);
+        assert_eq!(row["version"], VERSION);
+        assert!(row["observation"].is_array());
+    }
+}
@@ -0,0 +1,278 @@
+//! Duplicate Call Break: the same deals are played at several tables with the players moved
+//! one seat along at every table, and each player is scored against what the others made of
+//! the very same cards.
+
+use crate::agent::{AgentKind, Results};
+use crate::game::{Record, Rules};
+use crate::stats::Meter;
+use crate::{Error, Host, Result};
+use serde::{Deserialize, Serialize};
+use tracing::debug;
+
+/// Number of tables at four players, one for every seat a player has to sit in
+pub const TABLES: usize = 4;
+
+/// How a player did over all the tables
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Standing {
+    pub player: String,
+    /// Points collected over all the tables
+    pub total: f32,
+    /// Points over the average of everyone who held the same cards, zero being par
+    pub versus_par: f32,
+}
+
+/// Score games that were played on the same deals against each other
+///
+/// Every hand is compared with the average score made with it over all the records, so the
+/// strength of the cards cancels out and only what was done with them counts.
+pub fn score(records: &[Record]) -> Result<Vec<Standing>> {
+    let Some(first) = records.first() else {
+        return Ok(vec![]);
+    };
+    for record in records.iter() {
+        let same = record
+            .rounds
+            .iter()
+            .zip(first.rounds.iter())
+            .all(|(a, b)| a.hands == b.hands);
+        if !same {
+            return Err(Error::DealsDoNotMatch);
+        }
+    }
+
+    let rounds = records.iter().map(|r| r.rounds.len()).max().unwrap_or(0);
+    // average score made with each dealt hand, over the tables that finished the round
+    let par: Vec<[f32; 4]> = (0..rounds)
+        .map(|round| {
+            let scores: Vec<[f32; 4]> = records
+                .iter()
+                .filter_map(|record| {
+                    record
+                        .rounds
+                        .get(round)?
+                        .scores(&record.ruleset, &record.rules)
+                })
+                .collect();
+            std::array::from_fn(|seat| {
+                let total: f32 = scores.iter().map(|s| s[seat]).sum();
+                total / scores.len().max(1) as f32
+            })
+        })
+        .collect();
+
+    let mut standings: Vec<Standing> = vec![];
+    for record in records.iter() {
+        for (seat, player) in record.players.iter().enumerate() {
+            let index = match standings.iter().position(|s| &s.player == player) {
+                Some(index) => index,
+                None => {
+                    standings.push(Standing {
+                        player: player.clone(),
+                        total: 0.0,
+                        versus_par: 0.0,
+                    });
+                    standings.len() - 1
+                }
+            };
+            for (round, scores) in record.rounds.iter().enumerate() {
+                if let Some(scores) = scores.scores(&record.ruleset, &record.rules) {
+                    standings[index].total += scores[seat];
+                    standings[index].versus_par += scores[seat] - par[round][seat];
+                }
+            }
+        }
+    }
+    standings.sort_by(|a, b| b.versus_par.total_cmp(&a.versus_par));
+    Ok(standings)
+}
+
+/// Host for a duplicate match: the same seeded deals are played at every table, with the
+/// players rotated through every seat
+#[derive(Debug)]
+pub struct Duplicate {
+    seed: u64,
+    rules: Rules,
+    agents: Vec<(String, AgentKind)>,
+    records: Vec<Record>,
+}
+
+impl Duplicate {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rules: Rules::default(),
+            agents: vec![],
+            records: vec![],
+        }
+    }
+
+    /// Play every table by these house rules
+    pub fn with_rules(mut self, rules: Rules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    pub fn add_agent(&mut self, id: String, agent: AgentKind) -> Result<()> {
+        if self.is_ready() {
+            return Err(Error::NotAcceptingNewPlayers);
+        }
+        if self.agents.iter().any(|(other, _)| *other == id) {
+            return Err(Error::PlayerAlreadyInGame);
+        }
+        self.agents.push((id, agent));
+        Ok(())
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.agents.len() == self.rules.seats.count()
+    }
+
+    /// Every seat is sat in once, so there are as many tables as players
+    pub fn is_over(&self) -> bool {
+        self.records.len() == self.rules.seats.count()
+    }
+
+    /// Play every table, one after the other
+    pub fn run(&mut self) {
+        // FIXME: should return an error if the table is not full
+        while !self.is_over() {
+            debug!(table = self.records.len(), "starting duplicate table");
+            // INFO: the seating is shuffled the same way at every table since it follows from
+            // the seed, so moving everyone one place along the joining order moves them one
+            // seat along as well
+            let mut host = Host::with_seed(self.seed).with_rules(self.rules);
+            for (id, agent) in self.agents.drain(..) {
+                host.add_agent(id, agent)
+                    .expect("must be able to add the same agents at every table");
+            }
+            host.run();
+            let record = host.record();
+            let luck = Meter::default().measure(&record).summaries;
+            self.records.push(record);
+            host.announce(Results {
+                luck,
+                duplicate: self
+                    .standings()
+                    .expect("every table must be dealt the same from the seed"),
+                ..Results::default()
+            });
+            self.agents = host.into_agents();
+            self.agents.rotate_left(1);
+        }
+    }
+
+    /// Records of the tables that have been played so far
+    pub fn records(&self) -> &[Record] {
+        &self.records
+    }
+
+    pub fn standings(&self) -> Result<Vec<Standing>> {
+        score(&self.records)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::{Bot, Difficulty, Style};
+    use crate::game::Seats;
+
+    fn played(seed: u64) -> Duplicate {
+        let mut duplicate = Duplicate::new(seed);
+        for (i, difficulty) in [
+            Difficulty::Random,
+            Difficulty::Beginner,
+            Difficulty::Intermediate,
+            Difficulty::Beginner,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let bot = Bot::new(difficulty, Style::Balanced).with_seed(i as u64);
+            duplicate
+                .add_agent(i.to_string(), AgentKind::Bot(bot))
+                .unwrap();
+        }
+        duplicate.run();
+        duplicate
+    }
+
+    #[test]
+    fn every_player_holds_every_hand_once() {
+        let duplicate = played(11);
+        let records = duplicate.records();
+        assert_eq!(records.len(), TABLES);
+        for player in 0..4 {
+            let mut seats: Vec<usize> = records
+                .iter()
+                .map(|r| {
+                    r.players
+                        .iter()
+                        .position(|p| *p == player.to_string())
+                        .unwrap()
+                })
+                .collect();
+            seats.sort();
+            assert_eq!(seats, vec![0, 1, 2, 3]);
+        }
+        for record in records.iter() {
+            assert_eq!(record.rounds[0].hands, records[0].rounds[0].hands);
+        }
+    }
+
+    #[test]
+    fn scores_against_par_add_up_to_zero() {
+        let standings = played(5).standings().unwrap();
+        assert_eq!(standings.len(), 4);
+        let total: f32 = standings.iter().map(|s| s.versus_par).sum();
+        assert!(total.abs() < 1e-3);
+    }
+
+    #[test]
+    fn cannot_score_different_deals_together() {
+        let mut records = played(1).records().to_vec();
+        records.extend_from_slice(played(2).records());
+        assert_eq!(score(&records), Err(Error::DealsDoNotMatch));
+    }
+
+    #[test]
+    fn three_players_sit_at_three_tables() {
+        let mut duplicate = Duplicate::new(4).with_rules(Rules::default().with_seats(Seats::Three));
+        for (i, difficulty) in [
+            Difficulty::Random,
+            Difficulty::Intermediate,
+            Difficulty::Expert,
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let bot = Bot::new(difficulty, Style::Balanced).with_seed(i as u64);
+            duplicate
+                .add_agent(i.to_string(), AgentKind::Bot(bot))
+                .unwrap();
+        }
+        assert!(duplicate.is_ready());
+        duplicate.run();
+        assert_eq!(duplicate.records().len(), 3);
+        assert!(
+            duplicate
+                .records()
+                .iter()
+                .all(|record| record.rounds.iter().all(|round| round.is_over()))
+        );
+        assert_eq!(duplicate.standings().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn cannot_add_fifth_agent() {
+        let mut duplicate = Duplicate::new(0);
+        for i in 0..4 {
+            duplicate
+                .add_agent(i.to_string(), AgentKind::Bot(Bot::default()))
+                .unwrap();
+        }
+        let result = duplicate.add_agent("4".to_string(), AgentKind::Bot(Bot::default()));
+        assert_eq!(result, Err(Error::NotAcceptingNewPlayers));
+    }
+}
@@ -0,0 +1,364 @@
+//! Gym style environment for training agents by reinforcement learning.
+//!
+//! A single environment plays whole games, [`ROUNDS`] rounds each, with every seat driven by
+//! the caller through [`Env::step`]. Nothing is allocated on a step, and observations are
+//! written into a buffer owned by the caller.
+
+use crate::agent::Game as GameView;
+use crate::game::{Call, Card, Hand, ROUNDS, Rank, Round, Suit, Trick, Turn};
+use crate::{Error, Result};
+use rand::{SeedableRng, rngs::StdRng};
+
+/// Number of actions: one for every card, then one for every call from 1 to 13, then nil
+pub const ACTIONS: usize = 52 + 13 + 1;
+
+// index of the nil call, which the environment itself never allows
+const NIL: usize = ACTIONS - 1;
+
+// INFO: layout of an observation, always from the point of view of the seat it is made for so
+// that index 0 of every per seat block is that seat, 1 the seat after it and so on
+const HAND: usize = 0;
+const PLAYED: usize = HAND + 52;
+pub(crate) const CALLS: usize = PLAYED + 4 * 52;
+const WON: usize = CALLS + 4 * 15;
+const TRICK: usize = WON + 4;
+const ROUND: usize = TRICK + 4 * 52;
+const SCORES: usize = ROUND + ROUNDS;
+
+/// Length of an observation:
+///
+/// | offset | length | contents                                                        |
+/// |--------|--------|-----------------------------------------------------------------|
+/// | 0      | 52     | own hand                                                        |
+/// | 52     | 4 x 52 | cards played by each seat so far this round                     |
+/// | 260    | 4 x 15 | call of each seat, one hot with 0 for no call yet and 14 for nil |
+/// | 320    | 4      | tricks won by each seat this round, divided by 13               |
+/// | 324    | 4 x 52 | card played by each seat to the current trick                   |
+/// | 532    | 5      | round being played, one hot                                     |
+/// | 537    | 4      | score of each seat over the rounds that are over, divided by 13 |
+pub const OBSERVATION: usize = SCORES + 4;
+
+/// Something a seat can do, convertible to and from an action index below [`ACTIONS`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Call(Call),
+    Play(Card),
+}
+
+fn card_index(card: Card) -> usize {
+    card.get_suit() as usize * 13 + card.get_rank() as usize
+}
+
+impl From<Action> for usize {
+    fn from(action: Action) -> Self {
+        match action {
+            Action::Play(card) => card_index(card),
+            Action::Call(call) if call.is_nil() => NIL,
+            Action::Call(call) => 52 + call.value() as usize - 1,
+        }
+    }
+}
+
+impl TryFrom<usize> for Action {
+    type Error = Error;
+    fn try_from(index: usize) -> Result<Self> {
+        match index {
+            0..52 => Ok(Action::Play(Card::new(
+                Rank::ALL[index % 13],
+                Suit::ALL[index / 13],
+            ))),
+            NIL => Ok(Action::Call(Call::nil())),
+            52..NIL => Ok(Action::Call(Call::new((index - 51) as u8)?)),
+            _ => Err(Error::InvalidPlay),
+        }
+    }
+}
+
+/// Outcome of a single step
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Step {
+    /// Points each seat earned, only ever non zero on the step that finishes a round
+    pub rewards: [f32; 4],
+    /// Whether the game is over and the environment has to be reset
+    pub done: bool,
+}
+
+/// Environment playing one game at a time
+#[derive(Debug, Clone)]
+pub struct Env {
+    rng: StdRng,
+    round: Round,
+    // index of the round being played
+    index: usize,
+    scores: [f32; 4],
+    done: bool,
+}
+
+impl Env {
+    pub fn new(seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let round = Round::deal(Turn::new(0), &mut rng);
+        Self {
+            rng,
+            round,
+            index: 0,
+            scores: [0.0; 4],
+            done: false,
+        }
+    }
+
+    /// Start a new game whose deals all follow from `seed`
+    pub fn reset(&mut self, seed: u64) {
+        *self = Self::new(seed);
+    }
+
+    /// Seat whose action is awaited, if the game is not over
+    pub fn player(&self) -> Option<usize> {
+        self.round.turn().ok().map(usize::from)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Points each seat has collected over the rounds that are over
+    pub fn scores(&self) -> [f32; 4] {
+        self.scores
+    }
+
+    /// Which of the actions the current player may take
+    pub fn legal_actions(&self) -> [bool; ACTIONS] {
+        let mut mask = [false; ACTIONS];
+        let Ok(turn) = self.round.turn() else {
+            return mask;
+        };
+        if self.round.is_calling() {
+            mask[52..NIL].fill(true);
+        } else if let Ok(moves) = self.round.valid_moves(turn) {
+            for card in moves.iter() {
+                mask[card_index(card)] = true;
+            }
+        }
+        mask
+    }
+
+    /// Take an action for the current player
+    pub fn step(&mut self, action: usize) -> Result<Step> {
+        let turn = self.round.turn()?;
+        match Action::try_from(action)? {
+            Action::Call(call) if call.is_nil() => return Err(Error::NilNotAllowed),
+            Action::Call(call) => self.round.call(call, turn)?,
+            Action::Play(card) => self.round.play(card, turn)?,
+        }
+        let mut rewards = [0.0; 4];
+        if self.round.is_over() {
+            let won = self.round.tricks_won();
+            let calls = self.round.get_calls();
+            for (seat, reward) in rewards.iter_mut().enumerate() {
+                let call = calls[seat].expect("a round that is over must have all calls");
+                *reward = call.score(won[seat]);
+                self.scores[seat] += *reward;
+            }
+            self.index += 1;
+            if self.index == ROUNDS {
+                self.done = true;
+            } else {
+                self.round = Round::deal(Turn::new(self.index % 4), &mut self.rng);
+            }
+        }
+        Ok(Step {
+            rewards,
+            done: self.done,
+        })
+    }
+
+    /// Write what `seat` can see of the game into `out`
+    pub fn observe(&self, seat: usize, out: &mut [f32; OBSERVATION]) {
+        let tricks = self.round.get_tricks().iter().flatten();
+        let hand = self.round.get_hand(Turn::new(seat));
+        let round = self.index.min(ROUNDS - 1);
+        encode_parts(
+            seat,
+            hand,
+            self.round.get_calls(),
+            tricks,
+            round,
+            self.scores,
+            out,
+        );
+    }
+}
+
+/// Write a player's view of the game into `out`, from the point of view of `seat`
+pub fn encode(view: &GameView, seat: usize, out: &mut [f32; OBSERVATION]) {
+    let Some(round) = view.rounds.last() else {
+        out.fill(0.0);
+        return;
+    };
+    let hand = Hand::new(round.hand.iter().copied());
+    encode_parts(
+        seat,
+        &hand,
+        &round.calls,
+        round.tricks.iter(),
+        (view.rounds.len() - 1).min(ROUNDS - 1),
+        view.scores(),
+        out,
+    );
+}
+
+fn encode_parts<'a>(
+    seat: usize,
+    hand: &Hand,
+    calls: &[Option<Call>; 4],
+    tricks: impl Iterator<Item = &'a Trick>,
+    round: usize,
+    scores: [f32; 4],
+    out: &mut [f32; OBSERVATION],
+) {
+    out.fill(0.0);
+    let relative = |other: usize| (other + 4 - seat) % 4;
+    for card in hand.iter() {
+        out[HAND + card_index(card)] = 1.0;
+    }
+    let mut won = [0u8; 4];
+    for trick in tricks {
+        let current = !trick.is_over();
+        for (turn, card) in trick.plays() {
+            let other = relative(turn.into());
+            out[PLAYED + other * 52 + card_index(card)] = 1.0;
+            if current {
+                out[TRICK + other * 52 + card_index(card)] = 1.0;
+            }
+        }
+        if let (false, Some((winner, _))) = (current, trick.winner()) {
+            won[relative(winner.into())] += 1;
+        }
+    }
+    for other in 0..4 {
+        let call = match calls[(other + seat) % 4] {
+            None => 0,
+            Some(call) if call.is_nil() => 14,
+            Some(call) => call.value() as usize,
+        };
+        out[CALLS + other * 15 + call] = 1.0;
+        out[WON + other] = won[other] as f32 / 13.0;
+        out[SCORES + other] = scores[(other + seat) % 4] / 13.0;
+    }
+    out[ROUND + round] = 1.0;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::seq::IteratorRandom;
+
+    // play a whole game picking legal actions at random
+    fn play_out(env: &mut Env, rng: &mut StdRng) -> [f32; 4] {
+        let mut total = [0.0; 4];
+        let mut observation = [0.0; OBSERVATION];
+        while let Some(seat) = env.player() {
+            env.observe(seat, &mut observation);
+            let mask = env.legal_actions();
+            let action = (0..ACTIONS).filter(|a| mask[*a]).choose(rng).unwrap();
+            let step = env.step(action).unwrap();
+            for (total, reward) in total.iter_mut().zip(step.rewards) {
+                *total += reward;
+            }
+            if step.done {
+                break;
+            }
+        }
+        total
+    }
+
+    #[test]
+    fn actions_round_trip_through_their_index() {
+        for index in 0..ACTIONS {
+            let action = Action::try_from(index).unwrap();
+            assert_eq!(usize::from(action), index);
+        }
+        assert!(Action::try_from(ACTIONS).is_err());
+    }
+
+    #[test]
+    fn rewards_add_up_to_the_scores() {
+        let mut env = Env::new(3);
+        let total = play_out(&mut env, &mut StdRng::seed_from_u64(3));
+        assert!(env.is_done());
+        assert_eq!(env.player(), None);
+        assert_eq!(total, env.scores());
+    }
+
+    #[test]
+    fn same_seed_replays_the_same_game() {
+        let mut a = Env::new(9);
+        let mut b = Env::new(0);
+        b.reset(9);
+        let first = play_out(&mut a, &mut StdRng::seed_from_u64(1));
+        let second = play_out(&mut b, &mut StdRng::seed_from_u64(1));
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn illegal_actions_are_refused() {
+        let mut env = Env::new(5);
+        let mask = env.legal_actions();
+        assert!(mask[..52].iter().all(|legal| !legal));
+        assert!(!mask[NIL]);
+        assert!(env.step(0).is_err());
+        assert_eq!(env.step(NIL), Err(Error::NilNotAllowed));
+    }
+
+    #[test]
+    fn observation_matches_the_encoded_view() {
+        use crate::game::Game as Engine;
+        let mut game = Engine::default();
+        for player in 0..4 {
+            game.add_player(&player.to_string()).unwrap();
+        }
+        for _ in 0..4 {
+            let player = game.turn().unwrap();
+            game.call(&player, Call::new(2).unwrap()).unwrap();
+        }
+        for _ in 0..6 {
+            let player = game.turn().unwrap();
+            let moves = game.get_valid_moves(&player).unwrap();
+            game.play(&player, moves[0]).unwrap();
+        }
+        let player = game.turn().unwrap();
+        let view = game.build_view_for(&player).unwrap();
+        let seat = view.turn().unwrap();
+        let mut observation = [0.0; OBSERVATION];
+        encode(&view, seat, &mut observation);
+
+        let hand: f32 = observation[HAND..PLAYED].iter().sum();
+        let played: f32 = observation[PLAYED..CALLS].iter().sum();
+        let trick: f32 = observation[TRICK..ROUND].iter().sum();
+        assert_eq!((hand, played, trick), (12.0, 6.0, 2.0));
+        for other in 0..4 {
+            assert_eq!(observation[CALLS + other * 15 + 2], 1.0);
+        }
+        assert_eq!(observation[ROUND], 1.0);
+    }
+
+    #[test]
+    fn nil_call_is_told_apart_from_no_call() {
+        use crate::game::{Game as Engine, Rules};
+        let mut game = Engine::default().with_rules(Rules::default().with_nil());
+        for player in 0..4 {
+            game.add_player(&player.to_string()).unwrap();
+        }
+        let player = game.turn().unwrap();
+        game.call(&player, Call::nil()).unwrap();
+        let player = game.turn().unwrap();
+        let view = game.build_view_for(&player).unwrap();
+        let seat = view.turn().unwrap();
+        let mut observation = [0.0; OBSERVATION];
+        encode(&view, seat, &mut observation);
+        // the nil caller sits right before the one to call next
+        assert_eq!(observation[CALLS + 3 * 15 + 14], 1.0);
+        assert_eq!(observation[CALLS + 3 * 15], 0.0);
+        assert_eq!(observation[CALLS], 1.0);
+    }
+}
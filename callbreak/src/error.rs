@@ -5,16 +5,23 @@ pub enum Error {
     // Call Errors
     CallValueTooLarge,
     CallValueTooSmall,
+    NilNotAllowed,
+    AlreadyCalled,
     // Hand Errors
     HandIsFull,
     HandHasCardAlready,
     HandDoesNotHaveThisCard,
     RequiresFaceCard,
     RequiresSpades,
+    RequiresTrump,
     Not13Cards,
+    WrongNumberOfCards,
     HasDuplicateCards,
     // Round Errors
     RoundIsOver,
+    ClaimTooEarly,
+    ClaimExceedsTricksLeft,
+    ClaimNotGuaranteed,
     // Trick Errors
     InvalidPlay,
     // Turn Errors
@@ -23,10 +30,16 @@ pub enum Error {
     NotAcceptingNewPlayers,
     NotAcceptingCalls,
     NotAcceptingPlay,
+    NotAcceptingTrump,
     PlayerAlreadyInGame,
     PlayerNotInGame,
+    NoSuchSeat,
+    SeatTaken,
     RoundIsNotOver,
     RoundNotInProgress,
+    UnsupportedSeats,
+    // Duplicate Errors
+    DealsDoNotMatch,
     // Agent Error
     AgentSend,
     AgentRecv,
@@ -1,6 +1,10 @@
 use crate::{Error, Result};
 
 use serde::{Deserialize, Serialize};
+use std::fmt::Display;
+
+/// Points won for taking no tricks on a nil call, and lost for taking any
+pub const NIL_BONUS: f32 = 5.0;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
 pub struct Call(u8);
@@ -13,6 +17,55 @@ impl Call {
             _ => Ok(Call(call)),
         }
     }
+
+    /// Promise to take no tricks at all, only allowed at tables playing with
+    /// [`Rules::nil`](super::Rules::nil)
+    pub fn nil() -> Self {
+        Call(0)
+    }
+
+    pub fn is_nil(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Tricks called, zero for nil
+    pub fn value(&self) -> u8 {
+        self.0
+    }
+
+    /// Whether taking `tricks` meets the call
+    pub fn is_made(&self, tricks: u8) -> bool {
+        if self.is_nil() {
+            tricks == 0
+        } else {
+            tricks >= self.0
+        }
+    }
+
+    /// Points earned for taking `tricks` on this call:
+    /// the call plus a tenth for every extra trick, or minus the call if it is not met.
+    /// A nil call wins or loses [`NIL_BONUS`] instead.
+    pub fn score(&self, tricks: u8) -> f32 {
+        if self.is_nil() {
+            if self.is_made(tricks) {
+                NIL_BONUS
+            } else {
+                -NIL_BONUS
+            }
+        } else {
+            contract_score(self.0, tricks)
+        }
+    }
+}
+
+/// Points for taking `tricks` on a `contract` of one or more calls added together, scored the
+/// same way as a single call
+pub(crate) fn contract_score(contract: u8, tricks: u8) -> f32 {
+    if tricks >= contract {
+        contract as f32 + (tricks - contract) as f32 / 10.0
+    } else {
+        -(contract as f32)
+    }
 }
 
 impl<'de> Deserialize<'de> for Call {
@@ -20,10 +73,43 @@ impl<'de> Deserialize<'de> for Call {
     where
         D: serde::Deserializer<'de>,
     {
+        // INFO: zero is read as nil, whether the table allows it is up to the game
         let call = u8::deserialize(deserializer)?;
-        match Call::new(call) {
-            Ok(_) => Ok(Call(call)),
-            Err(e) => Err(serde::de::Error::custom(e.to_string())),
+        match call {
+            0 => Ok(Call::nil()),
+            call => Call::new(call).map_err(|e| serde::de::Error::custom(e.to_string())),
+        }
+    }
+}
+
+impl Display for Call {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_nil() {
+            write!(f, "nil")
+        } else {
+            write!(f, "{}", self.0)
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nil_is_only_made_by_taking_nothing() {
+        assert_eq!(Call::new(0), Err(Error::CallValueTooSmall));
+        assert_eq!(Call::nil().score(0), NIL_BONUS);
+        assert_eq!(Call::nil().score(1), -NIL_BONUS);
+        assert!(!Call::nil().is_made(1));
+        assert_eq!(Call::nil().to_string(), "nil");
+        assert_eq!(Call::new(4).unwrap().to_string(), "4");
+    }
+
+    #[test]
+    fn deserializes_zero_as_nil_and_rejects_too_large() {
+        assert_eq!(serde_json::from_str::<Call>("0").unwrap(), Call::nil());
+        assert_eq!(serde_json::from_str::<Call>("7").unwrap().value(), 7);
+        assert!(serde_json::from_str::<Call>("14").is_err());
+    }
+}
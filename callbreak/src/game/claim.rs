@@ -0,0 +1,64 @@
+use super::{Round, TrickTakingRules, Turn};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Most tricks that may be left in a round for a claim to be checked
+pub const CLAIM_TRICKS: usize = 5;
+
+/// Tricks a player says they are sure to take of the ones left in the round, counting the one
+/// being played
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Claim {
+    All,
+    Tricks(u8),
+}
+
+/// Tricks already worked out, for the positions at the start of a trick
+pub(crate) type Memo = HashMap<([u64; 4], Turn), u8>;
+
+/// Tricks taken so far by `seat` along with its partner, when playing in partnerships
+pub(crate) fn taken<R: TrickTakingRules>(round: &Round<R>, seat: Turn) -> u8 {
+    let won = round.tricks_won();
+    won[seat] + round.partner(seat).map_or(0, |partner| won[partner])
+}
+
+/// Tricks `seat` and its partner take from here on under best play for them against the others,
+/// with every hand known
+pub(crate) fn solve<R: TrickTakingRules>(round: &Round<R>, seat: Turn, memo: &mut Memo) -> u8 {
+    let Ok(turn) = round.turn() else {
+        return 0;
+    };
+    let fresh = round
+        .current_trick()
+        .is_some_and(|trick| trick.plays().next().is_none());
+    let key = fresh.then(|| {
+        (
+            std::array::from_fn(|i| round.get_hand(Turn::new(i)).bits()),
+            turn,
+        )
+    });
+    if let Some(tricks) = key.and_then(|key| memo.get(&key)) {
+        return *tricks;
+    }
+    let won = taken(round, seat);
+    let outcomes = round
+        .valid_moves(turn)
+        .expect("must have valid moves on its turn")
+        .iter()
+        .map(|card| {
+            let mut next = round.clone();
+            next.play(card, turn).expect("must be a valid move");
+            taken(&next, seat) - won + solve(&next, seat, memo)
+        });
+    let tricks = if turn == seat || round.partner(seat) == Some(turn) {
+        outcomes.max()
+    } else {
+        outcomes.min()
+    }
+    .unwrap_or(0);
+    if let Some(key) = key {
+        memo.insert(key, tricks);
+    }
+    tricks
+}
@@ -1,38 +1,85 @@
 use std::cmp::Ordering;
-use std::fmt::Display;
+use std::fmt::{Debug, Display};
 
 use super::{Rank, Suit};
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
-pub struct Card {
+// INFO: a card is packed into a single byte, the suit in the high nibble and the rank in the low
+// one. Its index doubles as its bit in the u64 bitsets used for hands, 16 bits to a suit.
+#[derive(Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Hash)]
+#[serde(from = "CardData", into = "CardData")]
+pub struct Card(u8);
+
+// what a card looks like on the wire
+#[derive(Serialize, Deserialize)]
+struct CardData {
     rank: Rank,
     suit: Suit,
 }
 
+impl From<CardData> for Card {
+    fn from(data: CardData) -> Self {
+        Card::new(data.rank, data.suit)
+    }
+}
+
+impl From<Card> for CardData {
+    fn from(card: Card) -> Self {
+        CardData {
+            rank: card.get_rank(),
+            suit: card.get_suit(),
+        }
+    }
+}
+
 impl Card {
-    pub fn new(rank: Rank, suit: Suit) -> Self {
+    pub const fn new(rank: Rank, suit: Suit) -> Self {
         // TODO: figure out a way to take both reference and owned values here
         // INFO: This could be rewritten to take both Rank and  &Rank but knowing
         // both Rank and Suit and very light enums, it is easier to just implement
         // Copy on them and pass them as value
-        Card { rank, suit }
+        Card((suit as u8) << 4 | rank as u8)
     }
 
     pub fn get_suit(&self) -> Suit {
-        self.suit
+        Suit::ALL[(self.0 >> 4) as usize]
     }
 
     pub fn get_rank(&self) -> Rank {
-        self.rank
+        Rank::ALL[(self.0 & 0xF) as usize]
+    }
+
+    /// Card whose bit is at `index` in a card bitset
+    pub(crate) fn from_index(index: u32) -> Self {
+        debug_assert!(index & 0xF < 13 && index < 64);
+        Card(index as u8)
+    }
+
+    pub(crate) fn bit(self) -> u64 {
+        1 << self.0
+    }
+
+    /// Bits of the cards of the same suit that outrank this one
+    pub(crate) fn above(self) -> u64 {
+        self.get_suit().mask() & !((self.bit() << 1) - 1)
+    }
+}
+
+impl Debug for Card {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Card")
+            .field("rank", &self.get_rank())
+            .field("suit", &self.get_suit())
+            .finish()
     }
 }
 
 impl Ord for Card {
     fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        match self.get_suit().cmp(&other.get_suit()) {
-            Ordering::Equal => other.get_rank().cmp(&self.get_rank()),
+        // by suit, then from the highest rank down
+        match (self.0 >> 4).cmp(&(other.0 >> 4)) {
+            Ordering::Equal => other.0.cmp(&self.0),
             ord => ord,
         }
     }
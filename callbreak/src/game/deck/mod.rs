@@ -6,40 +6,71 @@ pub use card::Card;
 pub use rank::Rank;
 pub use suit::Suit;
 
-use rand::{rng, seq::SliceRandom};
-use serde::{Deserialize, Serialize};
+use rand::{Rng, rng, seq::SliceRandom};
+use std::array;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Card left out of the deck for three players, so that it deals out evenly
+pub const DROPPED: Card = Card::new(Rank::Two, Suit::Clubs);
+
+#[derive(Debug, Clone)]
 pub(crate) struct Deck {
     idx: usize,
-    cards: Vec<Card>,
+    cards: [Card; 52],
+    // cards in play, from the start of the array
+    len: usize,
 }
 
 impl Deck {
+    #[cfg(test)]
     pub fn new() -> Self {
         Self::default()
     }
 
+    /// Deck shuffled with the given source of randomness, e.g. a seeded one
+    pub fn with_rng<R: Rng + ?Sized>(rng: &mut R) -> Self {
+        let mut deck = Self::ordered();
+        deck.cards.shuffle(rng);
+        deck
+    }
+
+    /// Shuffled deck for a table of `seats`, without [`DROPPED`] for three
+    pub fn for_seats<R: Rng + ?Sized>(seats: usize, rng: &mut R) -> Self {
+        let mut deck = Self::with_rng(rng);
+        if seats == 3 {
+            let dropped = deck
+                .cards
+                .iter()
+                .position(|card| *card == DROPPED)
+                .expect("a full deck must have every card");
+            deck.cards[dropped..].rotate_left(1);
+            deck.len = 51;
+        }
+        deck
+    }
+
     pub fn shuffle(&mut self) {
         let mut rng = rng();
         self.cards.shuffle(&mut rng);
     }
+
+    pub(crate) fn cards(&self) -> &[Card] {
+        &self.cards[..self.len]
+    }
+
+    fn ordered() -> Self {
+        Deck {
+            cards: array::from_fn(|i| Card::new(Rank::ALL[i / 4], Suit::ALL[i % 4])),
+            idx: 0,
+            len: 52,
+        }
+    }
 }
 
 impl Default for Deck {
     fn default() -> Self {
-        let mut deck = Deck {
-            cards: vec![],
-            idx: 0,
-        };
-        for rank in Rank::ALL {
-            for suit in Suit::ALL {
-                deck.cards.push(Card::new(*rank, *suit));
-            }
-        }
-        // INFO: it looks like here a stack variable is being returned
-        // however, we are returning a pointer to a value allocated in the heap.
-        // the ownership transfers to the function that calls this function
+        let mut deck = Self::ordered();
+        // INFO: the cards live in the deck itself rather than on the heap so that dealing does
+        // not allocate, which matters when millions of rounds are dealt for training
         deck.shuffle();
         deck
     }
@@ -48,9 +79,9 @@ impl Default for Deck {
 impl Iterator for Deck {
     type Item = Card;
     fn next(&mut self) -> Option<Self::Item> {
-        let next = self.cards.get(self.idx);
+        let next = self.cards().get(self.idx).copied();
         self.idx += 1;
-        next.cloned()
+        next
     }
 }
 
@@ -70,4 +101,21 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn same_seed_shuffles_the_same_way() {
+        use rand::{SeedableRng, rngs::StdRng};
+        let a: Vec<Card> = Deck::with_rng(&mut StdRng::seed_from_u64(7)).collect();
+        let b: Vec<Card> = Deck::with_rng(&mut StdRng::seed_from_u64(7)).collect();
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn deck_for_three_leaves_out_the_dropped_card() {
+        let deck = Deck::for_seats(3, &mut rand::rng());
+        assert_eq!(deck.cards().len(), 51);
+        assert!(!deck.cards().contains(&DROPPED));
+        assert_eq!(deck.count(), 51);
+        assert_eq!(Deck::for_seats(4, &mut rand::rng()).cards().len(), 52);
+    }
 }
@@ -13,6 +13,11 @@ pub enum Suit {
 
 impl Suit {
     pub(crate) const ALL: &[Self] = &[Self::Clubs, Self::Diamonds, Self::Hearts, Self::Spades];
+
+    /// Bits of every card of the suit in a card bitset
+    pub(crate) fn mask(self) -> u64 {
+        0x1FFF << (self as u64 * 16)
+    }
 }
 
 impl Display for Suit {
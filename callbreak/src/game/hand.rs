@@ -1,24 +1,45 @@
 use super::{Card, Rank, Suit};
 use crate::{Error, Result};
 use serde::{Deserialize, Serialize};
-use std::{collections::HashSet, fmt::Display};
+use std::fmt::{Debug, Display};
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-#[serde(transparent)]
-pub struct Hand(Vec<Card>);
+// INFO: a hand is a bitset of cards, see Card for the layout. It goes over the wire as a list of
+// cards sorted by suit and then from the highest rank down, same as it always has.
+#[derive(Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
+#[serde(from = "Vec<Card>", into = "Vec<Card>")]
+pub struct Hand(u64);
 
 impl Hand {
-    pub(crate) fn filter<P>(&self, mut predicate: P) -> impl Iterator<Item = &Card>
-    where
-        P: FnMut(&Card) -> bool,
-    {
-        self.0.iter().filter(move |card| predicate(card))
+    // INFO: unlike try_from() this does not check the dealing rules. It is meant for hands that are
+    // already part way through a round, e.g. when a bot guesses what the others are holding.
+    pub(crate) fn new(cards: impl IntoIterator<Item = Card>) -> Self {
+        Self(cards.into_iter().fold(0, |bits, card| bits | card.bit()))
+    }
+
+    pub(crate) fn from_bits(bits: u64) -> Self {
+        Self(bits)
+    }
+
+    pub(crate) fn bits(&self) -> u64 {
+        self.0
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub(crate) fn contains(&self, card: Card) -> bool {
+        self.0 & card.bit() != 0
+    }
+
+    /// Cards in the hand, sorted by suit and then from the highest rank down
+    pub(crate) fn iter(&self) -> Cards {
+        Cards(self.0)
     }
 
     pub(crate) fn play(&mut self, card: Card) -> Result<()> {
-        let idx = self.0.iter().position(|v| *v == card);
-        if let Some(idx) = idx {
-            self.0.remove(idx);
+        if self.contains(card) {
+            self.0 &= !card.bit();
             Ok(())
         } else {
             Err(Error::HandDoesNotHaveThisCard)
@@ -26,36 +47,157 @@ impl Hand {
     }
 }
 
-impl TryFrom<&[Card]> for Hand {
-    type Error = Error;
-    fn try_from(cards: &[Card]) -> std::result::Result<Self, Self::Error> {
-        let mut has_spades = false;
-        let mut has_face = false;
-        for card in cards.iter() {
-            has_face |= card.get_rank() >= Rank::Jack;
-            has_spades |= card.get_suit() == Suit::Spades;
+/// Iterator over the cards of a [`Hand`]
+#[derive(Debug, Clone)]
+pub(crate) struct Cards(u64);
+
+impl Iterator for Cards {
+    type Item = Card;
+
+    fn next(&mut self) -> Option<Card> {
+        if self.0 == 0 {
+            return None;
         }
-        let mut cards = cards.to_vec();
-        cards.sort();
-        if !has_face {
+        // highest card of the lowest suit left
+        let suit = self.0.trailing_zeros() / 16;
+        let bits = self.0 & (0xFFFF << (suit * 16));
+        let index = 63 - bits.leading_zeros();
+        self.0 &= !(1 << index);
+        Some(Card::from_index(index))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for Cards {}
+
+impl From<Vec<Card>> for Hand {
+    fn from(cards: Vec<Card>) -> Self {
+        Self::new(cards)
+    }
+}
+
+impl From<Hand> for Vec<Card> {
+    fn from(hand: Hand) -> Self {
+        hand.iter().collect()
+    }
+}
+
+impl Hand {
+    /// Hand of `size` cards as it was dealt, which has to hold a trump and a face card or else
+    /// the round is dealt again. A trump that is yet to be chosen is not checked for
+    pub(crate) fn dealt(cards: &[Card], size: usize, trump: Option<Suit>) -> Result<Self> {
+        let hand = Self::new(cards.iter().copied());
+        let faces = Suit::ALL
+            .iter()
+            .fold(0, |mask, suit| mask | Card::new(Rank::Ten, *suit).above());
+        if hand.0 & faces == 0 {
             Err(Error::RequiresFaceCard)
-        } else if !has_spades {
-            Err(Error::RequiresSpades)
-        } else if cards.len() != 13 {
-            Err(Error::Not13Cards)
-        } else if HashSet::<&Card>::from_iter(cards.iter()).len() != 13 {
+        } else if trump.is_some_and(|trump| hand.0 & trump.mask() == 0) {
+            Err(match trump {
+                Some(Suit::Spades) => Error::RequiresSpades,
+                _ => Error::RequiresTrump,
+            })
+        } else if cards.len() != size {
+            Err(Error::WrongNumberOfCards)
+        } else if hand.len() != size {
             Err(Error::HasDuplicateCards)
         } else {
-            Ok(Self(cards)) // TODO: sort the vector here by suit then rank?
+            Ok(hand)
+        }
+    }
+}
+
+impl TryFrom<&[Card]> for Hand {
+    type Error = Error;
+    fn try_from(cards: &[Card]) -> std::result::Result<Self, Self::Error> {
+        match Self::dealt(cards, 13, Some(Suit::Spades)) {
+            Err(Error::WrongNumberOfCards) => Err(Error::Not13Cards),
+            dealt => dealt,
         }
     }
 }
 
+impl Debug for Hand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_tuple("Hand")
+            .field(&self.iter().collect::<Vec<Card>>())
+            .finish()
+    }
+}
+
 impl Display for Hand {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for card in self.0.iter() {
+        for card in self.iter() {
             write!(f, "{} of {}", card.get_rank(), card.get_suit())?
         }
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Rank::*;
+    use crate::game::Suit::*;
+
+    #[test]
+    fn iterates_in_the_same_order_as_sorting() {
+        let mut cards = vec![
+            Card::new(Two, Spades),
+            Card::new(Ace, Clubs),
+            Card::new(Ten, Hearts),
+            Card::new(King, Spades),
+            Card::new(Two, Clubs),
+            Card::new(Jack, Diamonds),
+        ];
+        let hand = Hand::new(cards.clone());
+        cards.sort();
+        assert_eq!(hand.iter().collect::<Vec<Card>>(), cards);
+        assert_eq!(hand.iter().len(), 6);
+    }
+
+    #[test]
+    fn dealt_hand_must_hold_a_trump_once_it_is_known() {
+        let cards: Vec<Card> = Rank::ALL
+            .iter()
+            .map(|rank| Card::new(*rank, Hearts))
+            .collect();
+        assert_eq!(
+            Hand::dealt(&cards, 13, Some(Spades)),
+            Err(Error::RequiresSpades)
+        );
+        assert_eq!(
+            Hand::dealt(&cards, 13, Some(Clubs)),
+            Err(Error::RequiresTrump)
+        );
+        assert!(Hand::dealt(&cards, 13, Some(Hearts)).is_ok());
+        assert!(Hand::dealt(&cards, 13, None).is_ok());
+    }
+
+    #[test]
+    fn serializes_as_a_sorted_list_of_cards() {
+        let hand = Hand::new([Card::new(Queen, Hearts), Card::new(Ace, Clubs)]);
+        let json = serde_json::to_string(&hand).unwrap();
+        assert_eq!(
+            json,
+            r#"[{"rank":"ace","suit":"clubs"},{"rank":"queen","suit":"hearts"}]"#
+        );
+        assert_eq!(serde_json::from_str::<Hand>(&json).unwrap(), hand);
+    }
+
+    #[test]
+    fn playing_a_card_removes_only_that_card() {
+        let mut hand = Hand::new([Card::new(Queen, Hearts), Card::new(Ace, Clubs)]);
+        hand.play(Card::new(Queen, Hearts)).unwrap();
+        assert!(!hand.contains(Card::new(Queen, Hearts)));
+        assert!(hand.contains(Card::new(Ace, Clubs)));
+        assert_eq!(
+            hand.play(Card::new(Queen, Hearts)),
+            Err(Error::HandDoesNotHaveThisCard)
+        );
+    }
+}
@@ -1,87 +1,241 @@
 mod call;
+pub(crate) mod claim;
 mod deck;
 mod hand;
+pub mod phase;
+mod record;
 mod round;
+mod rules;
+mod ruleset;
+pub(crate) mod score;
 mod trick;
+pub(crate) mod trump;
 mod turn;
 
-pub use call::Call;
+pub use call::{Call, NIL_BONUS};
+pub use claim::{CLAIM_TRICKS, Claim};
 use deck::Deck;
-pub use deck::{Card, Rank, Suit};
+pub use deck::{Card, DROPPED, Rank, Suit};
 pub use hand::Hand;
+pub use record::{Record, RoundRecord};
+pub use rules::{EndReason, Reward, Rules, Seating, Seats, SpecialCall};
+pub use ruleset::{CallBreak, RoundOutcome, Ruleset, Spades, TrickTakingRules};
 pub use trick::Trick;
+pub(crate) use trick::beats;
+pub use trump::Trump;
 
-use crate::agent::{Game as GameView, Round as RoundView};
+use crate::agent::{Game as GameView, Round as RoundView, Standing};
 use crate::{Error, Result};
-use rand::{rng, seq::SliceRandom};
-use round::Round;
+use rand::{SeedableRng, rng, rngs::StdRng, seq::SliceRandom};
+pub(crate) use round::Round;
 use serde::Serialize;
 use tracing::debug;
-use turn::Turn;
+pub(crate) use turn::Turn;
 
 type Player = String;
 
-#[derive(Debug, Default, Clone, Serialize)]
-pub(crate) struct Game {
+/// Number of rounds in a game
+pub(crate) const ROUNDS: usize = 5;
+
+/// Most rounds a game played to a target score lasts, in case no one ever gets there
+pub(crate) const MAX_ROUNDS: usize = 50;
+
+/// Tricks in a round at a table of `seats`, every card in the deck being dealt
+pub(crate) const fn tricks_in_round(seats: usize) -> usize {
+    52 / seats
+}
+
+/// Most tricks a round can have, at a table of three
+pub(crate) const MAX_TRICKS: usize = tricks_in_round(3);
+
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct Game<R = CallBreak> {
     players: [Option<Player>; 4],
-    rounds: [Option<Round>; 5],
+    rounds: Vec<Round<R>>,
+    seed: Option<u64>,
+    rules: Rules,
+    end: Option<EndReason>,
+    // what may be called and played, and how it is scored
+    #[serde(skip)]
+    ruleset: R,
+    // totals of the match the game is part of, to show the players
+    #[serde(skip)]
+    standings: Vec<Standing>,
+    // seats the players asked for, which are kept out of the shuffle
+    #[serde(skip)]
+    chosen: [bool; 4],
+    // INFO: kept up to date as players join and rounds finish rather than worked out each time
+    #[serde(skip)]
+    state: State,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 enum State {
+    #[default]
     Lobby,
     RoundInProgress,
     Over,
 }
 
+// INFO: only a Call Break game has a default, so that `Game::default()` needs no annotation
+impl Default for Game {
+    fn default() -> Self {
+        Self {
+            players: Default::default(),
+            rounds: vec![],
+            seed: None,
+            rules: Rules::default(),
+            end: None,
+            ruleset: CallBreak,
+            standings: vec![],
+            chosen: [false; 4],
+            state: State::default(),
+        }
+    }
+}
+
 impl Game {
-    fn state(&self) -> State {
-        if self.players.iter().any(|v| v.as_ref().is_none()) {
-            State::Lobby
-        } else if self
-            .rounds
-            .iter()
-            .all(|round| round.as_ref().is_some_and(|v| v.is_over()))
-        {
-            State::Over
-        } else {
-            State::RoundInProgress
+    /// Game whose seating and deals all follow from `seed`
+    pub(crate) fn with_seed(seed: u64) -> Self {
+        Self {
+            seed: Some(seed),
+            ..Self::default()
         }
     }
 
+    /// Game played by another `ruleset` than Call Break, e.g. [`Spades`]
+    pub(crate) fn with_ruleset<R: TrickTakingRules>(self, ruleset: R) -> Game<R> {
+        Game {
+            players: self.players,
+            rounds: self
+                .rounds
+                .into_iter()
+                .map(|round| round.with_ruleset(ruleset.clone()))
+                .collect(),
+            seed: self.seed,
+            rules: self.rules,
+            end: self.end,
+            ruleset,
+            standings: self.standings,
+            chosen: self.chosen,
+            state: self.state,
+        }
+    }
+}
+
+impl<R: TrickTakingRules> Game<R> {
+    // source of randomness for seating (stream 0) and for dealing each round (stream i + 1)
+    fn rng_for(&self, stream: u64) -> StdRng {
+        match self.seed {
+            Some(seed) => StdRng::seed_from_u64(seed ^ stream.wrapping_mul(0x9E37_79B9_7F4A_7C15)),
+            None => StdRng::from_rng(&mut rng()),
+        }
+    }
+
+    fn seats(&self) -> usize {
+        self.rules.seats.count()
+    }
+
+    // the round's starter goes round the table
+    fn deal(&self, round: usize) -> Round<R> {
+        let seats = self.seats();
+        Round::deal_for(
+            seats,
+            Turn::new(round % seats),
+            self.rules.trump.for_round(round),
+            &mut self.rng_for(round as u64 + 1),
+        )
+        .with_rules(&self.rules)
+        .with_ruleset(self.ruleset.clone())
+    }
+
+    fn state(&self) -> State {
+        self.state
+    }
+
+    fn round(&self) -> &Round<R> {
+        self.rounds
+            .last()
+            .expect("must have an active round in this state")
+    }
+
+    fn round_mut(&mut self) -> &mut Round<R> {
+        self.rounds
+            .last_mut()
+            .expect("must have an active round in this state")
+    }
+
+    #[cfg(test)]
     pub(crate) fn add_player(&mut self, id: &str) -> Result<()> {
-        match self.state() {
-            State::Lobby => {
-                if self.players.iter().flatten().any(|player| player == id) {
-                    Err(Error::PlayerAlreadyInGame)
-                } else {
-                    self.players
-                        .iter_mut()
-                        .find(|slot| slot.is_none())
+        self.add_player_at(id, None)
+    }
+
+    /// Seat the player in `seat` if they asked for one, or in any free seat otherwise
+    pub(crate) fn add_player_at(&mut self, id: &str, seat: Option<usize>) -> Result<()> {
+        if self.state() != State::Lobby {
+            return Err(Error::NotAcceptingNewPlayers);
+        }
+        let seats = self.seats();
+        if self.players.iter().flatten().any(|player| player == id) {
+            return Err(Error::PlayerAlreadyInGame);
+        }
+        match seat {
+            Some(seat) if seat >= seats => return Err(Error::NoSuchSeat),
+            Some(seat) if self.chosen[seat] => return Err(Error::SeatTaken),
+            Some(seat) => {
+                // INFO: a player who did not ask for the seat makes room, there being at least
+                // one other free seat since the table is not full yet
+                if let Some(other) = self.players[seat].replace(id.to_string()) {
+                    self.free_seat()
                         .expect("an empty slot is expected in the Lobby")
-                        .replace(id.to_string());
-                    if self.state() == State::RoundInProgress {
-                        let mut rng = rng();
-                        self.players.shuffle(&mut rng);
-                        self.rounds[0] = Some(Round::new(Turn::new(0)));
-                        let players: Vec<String> = self
-                            .players
-                            .iter()
-                            .flatten()
-                            .map(|p| p.to_string())
-                            .collect();
-                        debug!(?players, "players order after shuffle for the game");
-                    }
-                    Ok(())
+                        .replace(other);
                 }
+                self.chosen[seat] = true;
             }
-            _ => Err(Error::NotAcceptingNewPlayers),
+            None => {
+                self.free_seat()
+                    .expect("an empty slot is expected in the Lobby")
+                    .replace(id.to_string());
+            }
+        }
+        if self.players[..seats].iter().all(|player| player.is_some()) {
+            self.state = State::RoundInProgress;
+            if self.rules.seating == Seating::Shuffled {
+                self.shuffle_seats();
+            }
+            self.rounds = vec![self.deal(0)];
+            debug!(seats = ?self.seat_map(), "seat map for the game");
         }
+        Ok(())
+    }
+
+    fn free_seat(&mut self) -> Option<&mut Option<Player>> {
+        let seats = self.seats();
+        self.players[..seats].iter_mut().find(|slot| slot.is_none())
+    }
+
+    // shuffle the players who did not ask for a seat among the seats left to them
+    fn shuffle_seats(&mut self) {
+        let free: Vec<usize> = (0..self.seats())
+            .filter(|seat| !self.chosen[*seat])
+            .collect();
+        let mut players: Vec<Option<Player>> =
+            free.iter().map(|seat| self.players[*seat].take()).collect();
+        players.shuffle(&mut self.rng_for(0));
+        for (seat, player) in free.into_iter().zip(players) {
+            self.players[seat] = player;
+        }
+    }
+
+    /// Who sits in each seat, empty while the table fills up
+    pub(crate) fn seat_map(&self) -> Vec<Option<Player>> {
+        self.players[..self.seats()].to_vec()
     }
 
     fn player_id_to_turn(&self, player_id: &str) -> Result<Turn> {
         match self.state() {
-            State::RoundInProgress => {
+            // the players keep their seats once the game is over, for a last look at it
+            State::RoundInProgress | State::Over => {
                 if let Some(turn) = self
                     .players
                     .iter()
@@ -97,16 +251,26 @@ impl Game {
         }
     }
 
+    pub(crate) fn with_rules(mut self, rules: Rules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    pub(crate) fn rules(&self) -> Rules {
+        self.rules
+    }
+
+    /// Ruleset the game is played and scored by
+    pub(crate) fn ruleset(&self) -> &R {
+        &self.ruleset
+    }
+
     pub(crate) fn call(&mut self, player_id: &str, call: Call) -> Result<()> {
         match self.state() {
             State::RoundInProgress => {
+                self.ruleset.check_call(call, &self.rules)?;
                 let turn = self.player_id_to_turn(player_id)?;
-                self.rounds
-                    .iter_mut()
-                    .rev()
-                    .find_map(|v| v.as_mut())
-                    .expect("must have an active round in this state")
-                    .call(call, turn)
+                self.round_mut().call(call, turn)
             }
             _ => Err(Error::NotAcceptingCalls),
         }
@@ -116,34 +280,94 @@ impl Game {
         match self.state() {
             State::RoundInProgress => {
                 let turn = self.player_id_to_turn(player_id)?;
-                let slot = self
-                    .rounds
-                    .iter()
-                    .position(|r| r.as_ref().is_some_and(|r| !r.is_over()))
-                    .expect("must have an active round in this state");
-                self.rounds[slot]
-                    .as_mut()
-                    .expect("must have an active round in this state")
-                    .play(card, turn)?;
-                if slot != 4 && self.rounds[slot].as_ref().unwrap().is_over() {
-                    self.rounds[slot + 1] = Some(Round::new(Turn::new(slot + 1)));
-                }
+                self.round_mut().play(card, turn)?;
+                self.advance();
                 Ok(())
             }
             _ => Err(Error::NotAcceptingPlay),
         }
     }
 
+    /// Pick the trump of the round, for the highest caller at tables playing [`Trump::Chosen`]
+    pub(crate) fn choose_trump(&mut self, player_id: &str, trump: Suit) -> Result<()> {
+        match self.state() {
+            State::RoundInProgress => {
+                let turn = self.player_id_to_turn(player_id)?;
+                self.round_mut().choose_trump(trump, turn)
+            }
+            _ => Err(Error::NotAcceptingTrump),
+        }
+    }
+
+    /// Whether the round is waiting on calls
+    pub(crate) fn is_calling(&self) -> bool {
+        self.state() == State::RoundInProgress && self.round().is_calling()
+    }
+
+    /// Whether the round is waiting on the highest caller to pick the trump
+    pub(crate) fn is_choosing_trump(&self) -> bool {
+        self.state() == State::RoundInProgress && self.round().is_choosing_trump()
+    }
+
+    /// Settle the rest of the round with a claim, see [`Round::claim`]
+    pub(crate) fn claim(&mut self, player_id: &str, claim: Claim) -> Result<()> {
+        match self.state() {
+            State::RoundInProgress => {
+                let turn = self.player_id_to_turn(player_id)?;
+                self.round_mut().claim(claim, turn)?;
+                self.advance();
+                Ok(())
+            }
+            _ => Err(Error::NotAcceptingPlay),
+        }
+    }
+
+    // deal the next round once the one being played is over, unless it ended the game
+    fn advance(&mut self) {
+        let index = self.rounds.len() - 1;
+        let round = self.round();
+        if round.is_over() {
+            let winner = self
+                .rules
+                .instant_winner(round.get_calls(), round.tricks_won());
+            if let Some(seat) = winner {
+                debug!(seat, round = index, "special call won the game outright");
+                self.end = Some(EndReason::InstantWin { seat, round: index });
+                self.state = State::Over;
+            } else if let Some(seat) = self.target_reached() {
+                debug!(seat, round = index, "target score reached");
+                self.end = Some(EndReason::TargetReached { seat, round: index });
+                self.state = State::Over;
+            } else if index == self.rules.rounds() - 1 {
+                self.end = Some(EndReason::AllRounds);
+                self.state = State::Over;
+            } else {
+                let next = self.deal(index + 1);
+                self.rounds.push(next);
+            }
+        }
+    }
+
+    // seat that has reached the target score of the table, the one furthest past it if several
+    // have
+    fn target_reached(&self) -> Option<usize> {
+        let target = self.rules.target?;
+        let scores = self.scores();
+        (0..self.seats())
+            .filter(|seat| scores[*seat] >= target)
+            .max_by(|a, b| scores[*a].total_cmp(&scores[*b]))
+    }
+
+    /// Show the players the totals of the match the game is part of
+    pub(crate) fn with_standings(mut self, standings: Vec<Standing>) -> Self {
+        self.standings = standings;
+        self
+    }
+
     pub(crate) fn turn(&self) -> Result<String> {
         match self.state() {
             State::RoundInProgress => {
-                let turn = self
-                    .rounds
-                    .iter()
-                    .rev()
-                    .find_map(|r| r.as_ref())
-                    .expect("must always have Some(round)")
-                    .turn()?;
+                let turn = self.round().turn()?;
                 Ok(self.players[turn]
                     .as_ref()
                     .expect("must have all players in this state")
@@ -153,16 +377,27 @@ impl Game {
         }
     }
 
+    /// Players still to call in the round being played, in the order they are asked
+    pub(crate) fn yet_to_call(&self) -> Vec<String> {
+        match self.state() {
+            State::RoundInProgress => self
+                .round()
+                .yet_to_call()
+                .map(|turn| {
+                    self.players[turn]
+                        .clone()
+                        .expect("must have all players in this state")
+                })
+                .collect(),
+            _ => vec![],
+        }
+    }
+
     pub(crate) fn get_valid_moves(&self, player: &str) -> Result<Vec<Card>> {
         match self.state() {
             State::RoundInProgress => {
                 let turn = self.player_id_to_turn(player)?;
-                self.rounds
-                    .iter()
-                    .rev()
-                    .find_map(|r| r.as_ref())
-                    .expect("must always have Some(round)")
-                    .get_valid_moves(turn)
+                self.round().get_valid_moves(turn)
             }
             _ => Err(Error::NotAcceptingPlay),
         }
@@ -176,29 +411,83 @@ impl Game {
         self.state() == State::Over
     }
 
+    /// Points collected by each seat over the rounds that are over, scored by the ruleset
+    pub(crate) fn scores(&self) -> [f32; 4] {
+        let rounds: Vec<RoundOutcome> = self
+            .rounds
+            .iter()
+            .filter(|round| round.is_over())
+            .map(|round| (*round.get_calls(), round.tricks_won()))
+            .collect();
+        self.ruleset.total(&self.rules, &rounds)
+    }
+
+    pub(crate) fn end_reason(&self) -> Option<EndReason> {
+        self.end
+    }
+
+    pub(crate) fn record(&self) -> Record {
+        Record {
+            seed: self.seed,
+            players: self.players.iter().flatten().cloned().collect(),
+            chosen_seats: (0..self.seats())
+                .filter(|seat| self.chosen[*seat])
+                .collect(),
+            rules: self.rules,
+            ruleset: self.ruleset.ruleset(),
+            end: self.end,
+            rounds: self
+                .rounds
+                .iter()
+                .map(|round| RoundRecord {
+                    hands: round.dealt(),
+                    calls: *round.get_calls(),
+                    trump: round.trump(),
+                    tricks: round.get_tricks().iter().flatten().cloned().collect(),
+                    claimed: *round.claimed(),
+                })
+                .collect(),
+        }
+    }
+
     pub(crate) fn build_view_for(&self, player: &Player) -> Result<GameView> {
         match self.state() {
             State::Lobby => Ok(GameView {
                 players: self.players.iter().flatten().cloned().collect(),
+                rules: self.rules,
+                ruleset: self.ruleset.ruleset(),
+                seats: self.seat_map(),
+                partner: None,
+                seat: None,
+                standings: self.standings.clone(),
                 rounds: vec![],
             }),
             _ => {
                 // there is more to do here
+                let turn = self.player_id_to_turn(player)?;
                 let mut rounds = vec![];
-                for round in self.rounds.iter().flatten() {
+                for round in self.rounds.iter() {
                     let roundview = RoundView {
-                        calls: *round.get_calls(),
-                        hand: round
-                            .get_hand(self.player_id_to_turn(player)?)
-                            .filter(|_| true)
-                            .cloned()
-                            .collect(),
+                        calls: round.visible_calls(turn),
+                        hand: round.get_hand(turn).iter().collect(),
+                        trump: round.trump(),
                         tricks: round.get_tricks().iter().flatten().cloned().collect(),
+                        claimed: *round.claimed(),
                     };
                     rounds.push(roundview);
                 }
+                let seat = usize::from(turn);
                 Ok(GameView {
                     players: self.players.iter().flatten().cloned().collect(),
+                    rules: self.rules,
+                    ruleset: self.ruleset.ruleset(),
+                    seats: self.seat_map(),
+                    partner: self
+                        .rules
+                        .partner(seat)
+                        .and_then(|partner| self.players[partner].clone()),
+                    seat: Some(seat),
+                    standings: self.standings.clone(),
                     rounds,
                 })
             }
@@ -235,6 +524,283 @@ mod test {
         }
     }
 
+    #[test]
+    fn same_seed_seats_and_deals_the_same() {
+        let mut games = [Game::with_seed(42), Game::with_seed(42)];
+        for game in games.iter_mut() {
+            for player in 0..4 {
+                game.add_player(&player.to_string()).unwrap();
+            }
+        }
+        let [a, b] = games.map(|game| game.record());
+        assert_eq!(a.players, b.players);
+        assert_eq!(a.rounds[0].hands, b.rounds[0].hands);
+    }
+
+    #[test]
+    fn record_keeps_dealt_hands_and_scores_a_finished_game() {
+        let mut game = Game::default();
+        for player in 0..4 {
+            game.add_player(&player.to_string()).unwrap();
+        }
+        let dealt = game.record().rounds[0].hands.clone();
+        for _ in 0..ROUNDS {
+            for _ in 0..4 {
+                let player = game.turn().unwrap();
+                game.call(&player, Call::new(1).unwrap()).unwrap();
+            }
+            for _ in 0..52 {
+                let player = game.turn().unwrap();
+                let moves = game.get_valid_moves(&player).unwrap();
+                game.play(&player, moves[0]).unwrap();
+            }
+        }
+        let record = game.record();
+        assert_eq!(record.rounds[0].hands, dealt);
+        assert!(record.rounds.iter().all(|round| round.is_over()));
+        let tricks: u8 = record.rounds[0].tricks_won().iter().sum();
+        assert_eq!(tricks, 13);
+        assert!(
+            record
+                .scores()
+                .iter()
+                .all(|score| *score >= -(ROUNDS as f32))
+        );
+    }
+
+    #[test]
+    fn three_players_share_the_deck_without_the_dropped_card() {
+        let mut game = Game::with_seed(3).with_rules(Rules::default().with_seats(Seats::Three));
+        for player in 0..3 {
+            game.add_player(&player.to_string()).unwrap();
+        }
+        assert!(game.add_player("3").is_err());
+        for round in 0..ROUNDS {
+            let hands = &game.record().rounds[round].hands;
+            assert!(hands[..3].iter().all(|hand| hand.len() == 17));
+            assert!(hands[3].is_empty());
+            assert!(hands.iter().flatten().all(|card| *card != DROPPED));
+            // the round is started by each of the three in turn
+            assert_eq!(
+                game.build_view_for(&game.turn().unwrap()).unwrap().turn(),
+                Some(round % 3)
+            );
+            for _ in 0..3 {
+                let player = game.turn().unwrap();
+                game.call(&player, Call::new(2).unwrap()).unwrap();
+            }
+            for _ in 0..17 * 3 {
+                let player = game.turn().unwrap();
+                let moves = game.get_valid_moves(&player).unwrap();
+                game.play(&player, moves[0]).unwrap();
+            }
+        }
+        assert!(game.is_over());
+        let record = game.record();
+        assert_eq!(record.players.len(), 3);
+        // the players may still look at the game once it is over
+        let view = game.build_view_for(&record.players[0]).unwrap();
+        assert_eq!(view.rounds.len(), 5);
+        let tricks: u8 = record.rounds[0].tricks_won().iter().sum();
+        assert_eq!(tricks, 17);
+        assert!(
+            record.rounds[0]
+                .tricks
+                .iter()
+                .all(|trick| trick.plays().count() == 3)
+        );
+    }
+
+    #[test]
+    fn partners_see_each_other_and_share_their_scores() {
+        let mut game = Game::with_seed(9).with_rules(Rules::default().with_partners());
+        for player in 0..4 {
+            game.add_player(&player.to_string()).unwrap();
+        }
+        let record = game.record();
+        for (seat, player) in record.players.iter().enumerate() {
+            let view = game.build_view_for(player).unwrap();
+            assert_eq!(view.partner.as_ref(), Some(&record.players[(seat + 2) % 4]));
+        }
+        while let Ok(player) = game.turn() {
+            let view = game.build_view_for(&player).unwrap();
+            if view.rounds.last().unwrap().tricks.is_empty() {
+                game.call(&player, Call::new(2).unwrap()).unwrap();
+            } else {
+                let moves = game.get_valid_moves(&player).unwrap();
+                game.play(&player, moves[0]).unwrap();
+            }
+        }
+        let scores = game.record().scores();
+        assert_eq!(scores[0], scores[2]);
+        assert_eq!(scores[1], scores[3]);
+    }
+
+    #[test]
+    fn highest_caller_picks_the_trump_before_the_first_trick() {
+        let rules = Rules::default().with_trump(Trump::Chosen);
+        let mut game = Game::with_seed(5).with_rules(rules);
+        for player in 0..4 {
+            game.add_player(&player.to_string()).unwrap();
+        }
+        let mut highest = None;
+        for value in [2, 4, 4, 1] {
+            let player = game.turn().unwrap();
+            highest = highest.or((value == 4).then(|| player.clone()));
+            game.call(&player, Call::new(value).unwrap()).unwrap();
+        }
+        assert!(game.is_choosing_trump());
+        let chooser = game.turn().unwrap();
+        assert_eq!(Some(&chooser), highest.as_ref());
+        let view = game.build_view_for(&chooser).unwrap();
+        assert_eq!(view.rounds[0].trump, None);
+        assert_eq!(view.players.get(view.turn().unwrap()), Some(&chooser));
+        assert_eq!(game.get_valid_moves(&chooser), Err(Error::NotAcceptingPlay));
+        let other = game
+            .record()
+            .players
+            .into_iter()
+            .find(|p| *p != chooser)
+            .unwrap();
+        assert_eq!(game.choose_trump(&other, Hearts), Err(Error::NotYourTurn));
+        game.choose_trump(&chooser, Hearts).unwrap();
+        assert_eq!(
+            game.choose_trump(&chooser, Hearts),
+            Err(Error::NotAcceptingTrump)
+        );
+        assert_eq!(game.record().rounds[0].trump, Some(Hearts));
+        let player = game.turn().unwrap();
+        assert_eq!(
+            game.build_view_for(&player).unwrap().rounds[0].tricks[0].trump(),
+            Hearts
+        );
+    }
+
+    #[test]
+    fn rotating_trump_changes_every_round() {
+        let rules = Rules::default().with_trump(Trump::Rotating);
+        let mut game = Game::with_seed(6).with_rules(rules);
+        for player in 0..4 {
+            game.add_player(&player.to_string()).unwrap();
+        }
+        for _ in 0..2 {
+            for _ in 0..4 {
+                let player = game.turn().unwrap();
+                game.call(&player, Call::new(1).unwrap()).unwrap();
+            }
+            for _ in 0..52 {
+                let player = game.turn().unwrap();
+                let moves = game.get_valid_moves(&player).unwrap();
+                game.play(&player, moves[0]).unwrap();
+            }
+        }
+        let record = game.record();
+        let trumps: Vec<_> = record.rounds.iter().map(|round| round.trump).collect();
+        assert_eq!(trumps, vec![Some(Spades), Some(Hearts), Some(Diamonds)]);
+        assert!(
+            record.rounds[2]
+                .hands
+                .iter()
+                .all(|hand| hand.iter().any(|card| card.get_suit() == Diamonds))
+        );
+    }
+
+    #[test]
+    fn sealed_calls_are_hidden_until_everyone_has_called() {
+        let mut game = Game::with_seed(3).with_rules(Rules::default().with_sealed_calls());
+        for player in 0..4 {
+            game.add_player(&player.to_string()).unwrap();
+        }
+        let mut players = game.yet_to_call();
+        assert_eq!(players.len(), 4);
+        // the calls come in backwards, which would be out of turn at any other table
+        players.reverse();
+        let (last, first) = (players[3].clone(), players[0].clone());
+        game.call(&first, Call::new(3).unwrap()).unwrap();
+        assert_eq!(
+            game.call(&first, Call::new(4).unwrap()),
+            Err(Error::AlreadyCalled)
+        );
+        let view = game.build_view_for(&last).unwrap();
+        assert_eq!(view.rounds[0].calls, [None; 4]);
+        assert_eq!(view.turn(), view.seat);
+        let view = game.build_view_for(&first).unwrap();
+        assert_eq!(view.rounds[0].calls.iter().flatten().count(), 1);
+        assert_eq!(view.turn(), None);
+        for player in &players[1..] {
+            game.call(player, Call::new(2).unwrap()).unwrap();
+        }
+        assert!(game.yet_to_call().is_empty());
+        let view = game.build_view_for(&last).unwrap();
+        assert_eq!(view.rounds[0].calls.iter().flatten().count(), 4);
+        assert!(game.get_valid_moves(&game.turn().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn views_show_the_standings_of_the_match() {
+        let standings = vec![Standing {
+            player: "2".to_string(),
+            total: 7.2,
+            wins: 1,
+        }];
+        let mut game = Game::default().with_standings(standings.clone());
+        game.add_player("0").unwrap();
+        assert_eq!(
+            game.build_view_for(&"0".to_string()).unwrap().standings,
+            standings
+        );
+        for player in 1..4 {
+            game.add_player(&player.to_string()).unwrap();
+        }
+        assert_eq!(
+            game.build_view_for(&"3".to_string()).unwrap().standings,
+            standings
+        );
+    }
+
+    #[test]
+    fn players_keep_the_seats_they_ask_for() {
+        let mut game = Game::with_seed(11);
+        game.add_player("a").unwrap();
+        game.add_player("b").unwrap();
+        // a player who did not ask for the seat makes room
+        game.add_player_at("c", Some(0)).unwrap();
+        assert_eq!(game.add_player_at("d", Some(0)), Err(Error::SeatTaken));
+        assert_eq!(game.add_player_at("d", Some(4)), Err(Error::NoSuchSeat));
+        assert_eq!(
+            game.seat_map(),
+            vec![
+                Some("c".to_string()),
+                Some("b".to_string()),
+                Some("a".to_string()),
+                None
+            ]
+        );
+        game.add_player_at("d", Some(3)).unwrap();
+        let record = game.record();
+        assert_eq!(record.players[0], "c");
+        assert_eq!(record.players[3], "d");
+        assert_eq!(record.chosen_seats, vec![0, 3]);
+        let view = game.build_view_for(&"a".to_string()).unwrap();
+        assert_eq!(view.seats, game.seat_map());
+    }
+
+    #[test]
+    fn fixed_seating_keeps_the_order_players_joined_in() {
+        let order: Vec<String> = (0..4).map(|player| player.to_string()).collect();
+        let seated = |rules: Rules| {
+            let mut game = Game::with_seed(5).with_rules(rules);
+            for player in order.iter() {
+                game.add_player(player).unwrap();
+            }
+            game.record().players
+        };
+        let fixed = Rules::default().with_seating(Seating::Fixed);
+        assert_eq!(seated(fixed), order);
+        assert_ne!(seated(Rules::default()), order);
+        assert_eq!(seated(Rules::default()), seated(Rules::default()));
+    }
+
     #[test]
     fn cannot_add_same_player_twice() {
         let mut game = Game::default();
@@ -291,18 +857,18 @@ mod test {
         for i in 0..4 {
             game.add_player(&i.to_string()).unwrap();
         }
-        let round = game.rounds[0].as_mut().unwrap();
+        let round = &game.rounds[0];
         for i in 0..4 {
             assert!(
                 round
                     .get_hand(Turn::new(i))
-                    .filter(|_| true)
+                    .iter()
                     .any(|c| c.get_suit() == Spades),
             );
             assert!(
                 round
                     .get_hand(Turn::new(i))
-                    .filter(|_| true)
+                    .iter()
                     .any(|c| c.get_rank() >= Jack)
             );
         }
@@ -355,4 +921,59 @@ mod test {
         // ^ what card is used doesn't really matter
         assert_eq!(action, Err(Error::NotYourTurn));
     }
+
+    #[test]
+    fn nil_is_only_accepted_when_the_table_allows_it() {
+        for rules in [Rules::default(), Rules::default().with_nil()] {
+            let mut game = Game::default().with_rules(rules);
+            for player in 0..4 {
+                game.add_player(&player.to_string()).unwrap();
+            }
+            let player = game.turn().unwrap();
+            let called = game.call(&player, Call::nil());
+            if rules.nil {
+                assert!(called.is_ok());
+                assert!(game.build_view_for(&player).unwrap().rules.nil);
+            } else {
+                assert_eq!(called, Err(Error::NilNotAllowed));
+            }
+        }
+    }
+
+    #[test]
+    fn instant_win_ends_the_game_early() {
+        let special = SpecialCall {
+            from: 1,
+            ..SpecialCall::default()
+        };
+        for rules in [
+            Rules::default(),
+            Rules::default().with_special_calls(special),
+        ] {
+            let mut game = Game::with_seed(1).with_rules(rules);
+            for player in 0..4 {
+                game.add_player(&player.to_string()).unwrap();
+            }
+            while !game.is_over() {
+                let player = game.turn().unwrap();
+                match game.get_valid_moves(&player) {
+                    Ok(moves) => game.play(&player, moves[0]).unwrap(),
+                    Err(_) => game.call(&player, Call::new(1).unwrap()).unwrap(),
+                }
+            }
+            let record = game.record();
+            match record.end.unwrap() {
+                EndReason::InstantWin { seat, round } => {
+                    assert!(rules.special.is_some());
+                    assert_eq!((round, record.rounds.len()), (0, 1));
+                    assert!(record.rounds[0].tricks_won()[seat] >= 1);
+                }
+                EndReason::AllRounds => {
+                    assert!(rules.special.is_none());
+                    assert_eq!(record.rounds.len(), ROUNDS);
+                }
+                EndReason::TargetReached { .. } => panic!("the table plays no target score"),
+            }
+        }
+    }
 }
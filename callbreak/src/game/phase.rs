@@ -0,0 +1,345 @@
+//! Typed phases of a game, each with only the actions that are valid in it. Every action is taken
+//! for the player whose turn it is and hands back the phase the game moves on to, or the phase it
+//! was in along with why the action was turned down.
+
+use super::{Call, CallBreak, Card, Claim, EndReason, Game, Record, Rules, Suit, TrickTakingRules};
+use crate::agent::Game as GameView;
+use crate::{Error, Result};
+
+/// Action turned down, along with the phase the game is still in. The phases keep the game
+/// boxed, so that this stays small
+pub type Rejected<S> = (S, Error);
+
+/// Game waiting for its players to join
+#[derive(Debug, Clone)]
+pub struct Lobby<R = CallBreak>(Box<Game<R>>);
+
+/// Round waiting on the calls of its players
+#[derive(Debug, Clone)]
+pub struct Calling<R = CallBreak>(Box<Game<R>>);
+
+/// Round waiting on the highest caller to pick the trump, see [`Trump::Chosen`]
+///
+/// [`Trump::Chosen`]: super::Trump::Chosen
+#[derive(Debug, Clone)]
+pub struct ChoosingTrump<R = CallBreak>(Box<Game<R>>);
+
+/// Round whose tricks are being played
+#[derive(Debug, Clone)]
+pub struct Playing<R = CallBreak>(Box<Game<R>>);
+
+/// Game that is over
+#[derive(Debug, Clone)]
+pub struct Finished<R = CallBreak>(Box<Game<R>>);
+
+/// Phase a game is in once a player has joined
+#[derive(Debug, Clone)]
+pub enum Joined<R = CallBreak> {
+    Lobby(Lobby<R>),
+    Calling(Calling<R>),
+}
+
+/// Phase a round is in once a call is made
+#[derive(Debug, Clone)]
+pub enum Called<R = CallBreak> {
+    Calling(Calling<R>),
+    ChoosingTrump(ChoosingTrump<R>),
+    Playing(Playing<R>),
+}
+
+/// Phase a game is in once a card is played or a claim is settled
+#[derive(Debug, Clone)]
+pub enum Played<R = CallBreak> {
+    Playing(Playing<R>),
+    /// The round is over and the next one is dealt
+    Calling(Calling<R>),
+    Finished(Finished<R>),
+}
+
+impl Default for Lobby {
+    fn default() -> Self {
+        Self(Box::default())
+    }
+}
+
+impl Lobby {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Game whose seating and deals all follow from `seed`
+    pub fn with_seed(seed: u64) -> Self {
+        Self(Box::new(Game::with_seed(seed)))
+    }
+
+    /// Game played by another `ruleset` than Call Break
+    pub fn with_ruleset<R: TrickTakingRules>(self, ruleset: R) -> Lobby<R> {
+        Lobby(Box::new(self.0.with_ruleset(ruleset)))
+    }
+}
+
+impl<R: TrickTakingRules> Lobby<R> {
+    /// Play by these house rules rather than the standard ones
+    pub fn with_rules(self, rules: Rules) -> Self {
+        Self(Box::new(self.0.with_rules(rules)))
+    }
+
+    /// Seat the player, the first round is dealt once the table is full
+    pub fn join(self, player: &str) -> std::result::Result<Joined<R>, Rejected<Self>> {
+        self.join_at(player, None)
+    }
+
+    /// Seat the player in `seat` if they asked for one, see [`Seating`]
+    ///
+    /// [`Seating`]: super::Seating
+    pub fn join_at(
+        mut self,
+        player: &str,
+        seat: Option<usize>,
+    ) -> std::result::Result<Joined<R>, Rejected<Self>> {
+        match self.0.add_player_at(player, seat) {
+            Ok(()) if self.0.is_ready() => Ok(Joined::Calling(Calling(self.0))),
+            Ok(()) => Ok(Joined::Lobby(self)),
+            Err(error) => Err((self, error)),
+        }
+    }
+
+    pub fn players(&self) -> Vec<String> {
+        self.0.record().players
+    }
+
+    /// Who sits in each seat so far
+    pub fn seats(&self) -> Vec<Option<String>> {
+        self.0.seat_map()
+    }
+}
+
+// phase of the game in progress after an action, which must have left it in one of these
+fn called<R: TrickTakingRules>(game: Box<Game<R>>) -> Called<R> {
+    if game.is_calling() {
+        Called::Calling(Calling(game))
+    } else if game.is_choosing_trump() {
+        Called::ChoosingTrump(ChoosingTrump(game))
+    } else {
+        Called::Playing(Playing(game))
+    }
+}
+
+fn played<R: TrickTakingRules>(game: Box<Game<R>>) -> Played<R> {
+    if game.is_over() {
+        Played::Finished(Finished(game))
+    } else if game.is_calling() {
+        Played::Calling(Calling(game))
+    } else {
+        Played::Playing(Playing(game))
+    }
+}
+
+impl<R: TrickTakingRules> Calling<R> {
+    /// Make the call of the player on turn
+    pub fn call(mut self, call: Call) -> std::result::Result<Called<R>, Rejected<Self>> {
+        let player = self.turn();
+        match self.0.call(&player, call) {
+            Ok(()) => Ok(called(self.0)),
+            Err(error) => Err((self, error)),
+        }
+    }
+}
+
+impl<R: TrickTakingRules> ChoosingTrump<R> {
+    /// Pick the trump as the highest caller, which is always accepted
+    pub fn choose_trump(mut self, trump: Suit) -> Playing<R> {
+        let player = self.turn();
+        self.0
+            .choose_trump(&player, trump)
+            .expect("the highest caller may pick any suit");
+        Playing(self.0)
+    }
+}
+
+impl<R: TrickTakingRules> Playing<R> {
+    /// Cards the player on turn may play
+    pub fn valid_moves(&self) -> Vec<Card> {
+        self.0
+            .get_valid_moves(&self.turn())
+            .expect("the player on turn must have valid moves")
+    }
+
+    /// Play the card of the player on turn
+    pub fn play(mut self, card: Card) -> std::result::Result<Played<R>, Rejected<Self>> {
+        let player = self.turn();
+        match self.0.play(&player, card) {
+            Ok(()) => Ok(played(self.0)),
+            Err(error) => Err((self, error)),
+        }
+    }
+
+    /// Settle the rest of the round with a claim of the player on turn, see [`Claim`]
+    pub fn claim(mut self, claim: Claim) -> std::result::Result<Played<R>, Rejected<Self>> {
+        let player = self.turn();
+        match self.0.claim(&player, claim) {
+            Ok(()) => Ok(played(self.0)),
+            Err(error) => Err((self, error)),
+        }
+    }
+}
+
+impl<R: TrickTakingRules> Finished<R> {
+    /// Why the game ended
+    pub fn end_reason(&self) -> EndReason {
+        self.0
+            .end_reason()
+            .expect("a finished game must have ended for a reason")
+    }
+}
+
+// actions that every phase of a game in progress shares
+macro_rules! in_progress {
+    ($($phase:ident),*) => {
+        $(
+            impl<R: TrickTakingRules> $phase<R> {
+                /// Player whose action is awaited
+                pub fn turn(&self) -> String {
+                    self.0.turn().expect("a game in progress must have a turn")
+                }
+
+                /// What `player` can see of the game
+                pub fn view_for(&self, player: &str) -> Result<GameView> {
+                    self.0.build_view_for(&player.to_string())
+                }
+
+                /// View of the player on turn
+                pub fn view(&self) -> GameView {
+                    self.view_for(&self.turn())
+                        .expect("the player on turn must be in the game")
+                }
+            }
+        )*
+    };
+}
+
+in_progress!(Calling, ChoosingTrump, Playing);
+
+// actions that every phase shares
+macro_rules! any_phase {
+    ($($phase:ident),*) => {
+        $(
+            impl<R: TrickTakingRules> $phase<R> {
+                /// Everything that has happened in the game so far, hidden cards included
+                pub fn record(&self) -> Record {
+                    self.0.record()
+                }
+
+                /// Points collected by each seat over the rounds that are over
+                pub fn scores(&self) -> [f32; 4] {
+                    self.0.scores()
+                }
+            }
+        )*
+    };
+}
+
+any_phase!(Lobby, Calling, ChoosingTrump, Playing, Finished);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Seating, Trump};
+
+    fn seated(lobby: Lobby) -> Calling {
+        let mut joined = Joined::Lobby(lobby);
+        for player in 0..4 {
+            let Joined::Lobby(lobby) = joined else {
+                panic!("the table must not fill before the last player joins");
+            };
+            joined = lobby.join(&player.to_string()).unwrap();
+        }
+        let Joined::Calling(calling) = joined else {
+            panic!("the first round must be dealt once the table is full");
+        };
+        calling
+    }
+
+    #[test]
+    fn plays_a_game_through_its_phases() {
+        let mut calling = seated(Lobby::with_seed(3));
+        let finished = 'game: loop {
+            let mut called = calling.call(Call::new(2).unwrap()).unwrap();
+            while let Called::Calling(next) = called {
+                called = next.call(Call::new(2).unwrap()).unwrap();
+            }
+            let Called::Playing(mut playing) = called else {
+                panic!("spades are trump, so there is nothing to choose");
+            };
+            calling = loop {
+                let card = playing.valid_moves()[0];
+                match playing.play(card).unwrap() {
+                    Played::Playing(next) => playing = next,
+                    Played::Calling(next) => break next,
+                    Played::Finished(finished) => break 'game finished,
+                }
+            };
+        };
+        assert_eq!(finished.end_reason(), EndReason::AllRounds);
+        assert_eq!(finished.record().rounds.len(), 5);
+        assert_eq!(finished.scores(), finished.record().scores());
+    }
+
+    #[test]
+    fn rejected_actions_hand_the_phase_back() {
+        let lobby = Lobby::new().join("a").unwrap();
+        let Joined::Lobby(lobby) = lobby else {
+            panic!("one player does not fill the table");
+        };
+        let (lobby, error) = lobby.join("a").unwrap_err();
+        assert_eq!(error, Error::PlayerAlreadyInGame);
+        assert_eq!(lobby.players(), vec!["a".to_string()]);
+
+        let calling = seated(Lobby::with_seed(4));
+        let turn = calling.turn();
+        let (calling, error) = calling.call(Call::nil()).unwrap_err();
+        assert_eq!(error, Error::NilNotAllowed);
+        assert_eq!(calling.turn(), turn);
+    }
+
+    #[test]
+    fn players_may_join_in_the_seat_they_ask_for() {
+        let lobby = Lobby::with_seed(2).with_rules(Rules::default().with_seating(Seating::Fixed));
+        let Joined::Lobby(lobby) = lobby.join_at("teacher", Some(2)).unwrap() else {
+            panic!("one player does not fill the table");
+        };
+        let (lobby, error) = lobby.join_at("student", Some(2)).unwrap_err();
+        assert_eq!(error, Error::SeatTaken);
+        assert_eq!(
+            lobby.seats(),
+            vec![None, None, Some("teacher".to_string()), None]
+        );
+        let mut joined = Joined::Lobby(lobby);
+        for player in 0..3 {
+            let Joined::Lobby(lobby) = joined else {
+                panic!("the table must not fill before the last player joins");
+            };
+            joined = lobby.join(&player.to_string()).unwrap();
+        }
+        let Joined::Calling(calling) = joined else {
+            panic!("the first round must be dealt once the table is full");
+        };
+        assert_eq!(calling.view().seats[2], Some("teacher".to_string()));
+        assert_eq!(calling.record().players, ["0", "1", "teacher", "2"]);
+    }
+
+    #[test]
+    fn highest_caller_chooses_the_trump_in_its_own_phase() {
+        let lobby = Lobby::with_seed(5).with_rules(Rules::default().with_trump(Trump::Chosen));
+        let mut called = Called::Calling(seated(lobby));
+        while let Called::Calling(calling) = called {
+            called = calling.call(Call::new(3).unwrap()).unwrap();
+        }
+        let Called::ChoosingTrump(choosing) = called else {
+            panic!("the trump must be chosen before the first trick");
+        };
+        let playing = choosing.choose_trump(Suit::Hearts);
+        assert_eq!(playing.view().rounds[0].trump, Some(Suit::Hearts));
+        assert_eq!(playing.valid_moves().len(), 13);
+    }
+}
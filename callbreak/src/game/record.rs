@@ -0,0 +1,72 @@
+use super::{Call, Card, EndReason, Rules, Ruleset, Suit, Trick, score};
+use serde::{Deserialize, Serialize};
+
+/// Everything that happened in a game, with nothing hidden
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Record {
+    /// Seed the game was dealt with, if it was seeded
+    pub seed: Option<u64>,
+    /// Players in the order of their seats
+    pub players: Vec<String>,
+    /// Seats the players asked for, which were kept out of the shuffle
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chosen_seats: Vec<usize>,
+    #[serde(default)]
+    pub rules: Rules,
+    /// Ruleset the game was played and scored by
+    #[serde(default)]
+    pub ruleset: Ruleset,
+    pub rounds: Vec<RoundRecord>,
+    /// Why the game ended, once it is over
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub end: Option<EndReason>,
+}
+
+/// A single round of a [`Record`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoundRecord {
+    /// Hands of each seat as they were dealt
+    pub hands: [Vec<Card>; 4],
+    pub calls: [Option<Call>; 4],
+    /// Trump of the round, none if it was never chosen
+    #[serde(default = "spades")]
+    pub trump: Option<Suit>,
+    pub tricks: Vec<Trick>,
+    /// Tricks each seat was given by a claim that settled the rest of the round
+    #[serde(default, skip_serializing_if = "score::is_unclaimed")]
+    pub claimed: [u8; 4],
+}
+
+// records from before the trump could change were all played with spades
+fn spades() -> Option<Suit> {
+    Some(Suit::Spades)
+}
+
+impl RoundRecord {
+    pub fn is_over(&self) -> bool {
+        score::is_round_over(&self.tricks, &self.claimed)
+    }
+
+    /// Number of tricks won by each seat, played or claimed
+    pub fn tricks_won(&self) -> [u8; 4] {
+        score::tricks_won(&self.tricks, &self.claimed)
+    }
+
+    /// Points each seat earned in the round of a game played by `ruleset` and `rules`, once it
+    /// is over
+    pub fn scores(&self, ruleset: &Ruleset, rules: &Rules) -> Option<[f32; 4]> {
+        score::round_scores(ruleset, rules, &self.calls, &self.tricks, &self.claimed)
+    }
+}
+
+impl Record {
+    /// Points each seat has collected over the rounds that are over
+    pub fn scores(&self) -> [f32; 4] {
+        let rounds = self.rounds.iter();
+        score::total(
+            &self.ruleset,
+            &self.rules,
+            rounds.map(|round| (&round.calls, round.tricks.as_slice(), &round.claimed)),
+        )
+    }
+}
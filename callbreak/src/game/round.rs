@@ -1,74 +1,185 @@
-use super::{Call, Hand, Trick, Turn};
+use super::claim::{self, CLAIM_TRICKS, Claim, Memo};
+use super::{Call, Hand, MAX_TRICKS, Rules, Suit, Trick, Turn, tricks_in_round, trump};
+use super::{CallBreak, TrickTakingRules};
 use super::{Card, Deck};
 use crate::{Error, Result};
+use rand::Rng;
 use serde::Serialize;
 use std::array;
 
 #[derive(Debug, Clone, Serialize)]
-pub(crate) struct Round {
+pub(crate) struct Round<R = CallBreak> {
     starter: Turn,
     hands: [Hand; 4],
     calls: [Option<Call>; 4],
-    tricks: [Option<Trick>; 13],
+    tricks: [Option<Trick>; MAX_TRICKS],
+    // tricks each seat was given by a claim instead of playing them out
+    claimed: [u8; 4],
+    // players at the table, the last seat is left empty at a table of three
+    seats: usize,
+    // whether the seats across from each other play as partners
+    partners: bool,
+    // whether the calls may come in any order, see [`Rules::sealed`]
+    sealed: bool,
+    // none until the highest caller has chosen it, at tables that leave it to them
+    trump: Option<Suit>,
+    // what may be played to a trick, and who takes it
+    #[serde(skip)]
+    ruleset: R,
+    // INFO: kept up to date on every call and play rather than worked out from the arrays above
+    #[serde(skip)]
+    state: State,
+    #[serde(skip)]
+    calls_made: u8,
+    // slot of the trick being played, or of the last one once the round is over
+    #[serde(skip)]
+    slot: usize,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 enum State {
     Calling,
+    ChoosingTrump,
     TrickInProgress,
     Over,
 }
 
 impl Round {
-    fn state(&self) -> State {
-        if self.calls.iter().any(|call| call.is_none()) {
-            State::Calling
-        } else if self
-            .tricks
-            .last()
-            .expect("must have the last slot available by definition")
-            .as_ref()
-            .is_some_and(|t| t.is_over())
-        {
-            State::Over
-        } else {
-            State::TrickInProgress
-        }
+    #[cfg(test)]
+    pub(crate) fn new(starter: Turn) -> Self {
+        Self::deal(starter, &mut rand::rng())
     }
 
-    pub(crate) fn new(starter: Turn) -> Self {
-        let mut hands = vec![];
-        'dealing: loop {
-            let deck: Vec<Card> = Deck::new().collect();
-            hands = vec![];
-            for i in 0..4 {
-                if let Ok(hand) = Hand::try_from(&deck[i * 13..(i + 1) * 13]) {
-                    hands.push(hand);
-                } else {
-                    continue 'dealing;
-                }
+    pub(crate) fn deal<R: Rng + ?Sized>(starter: Turn, rng: &mut R) -> Self {
+        Self::deal_for(4, starter, Some(Suit::Spades), rng)
+    }
+
+    /// Round dealt to a table of `seats` with `trump`, or with the trump left to the highest
+    /// caller
+    pub(crate) fn deal_for<R: Rng + ?Sized>(
+        seats: usize,
+        starter: Turn,
+        trump: Option<Suit>,
+        rng: &mut R,
+    ) -> Self {
+        let size = tricks_in_round(seats);
+        loop {
+            let deck = Deck::for_seats(seats, rng);
+            let mut hands: [Hand; 4] = Default::default();
+            let dealt = deck
+                .cards()
+                .chunks(size)
+                .zip(hands.iter_mut())
+                .all(|(cards, hand)| match Hand::dealt(cards, size, trump) {
+                    Ok(dealt) => {
+                        *hand = dealt;
+                        true
+                    }
+                    Err(_) => false,
+                });
+            if dealt {
+                let mut round = Self::from_hands(seats, starter, hands);
+                round.trump = trump;
+                return round;
             }
-            break;
         }
+    }
+
+    /// Round about to be called at a table of `seats` with the hands as they were dealt, a table
+    /// of three leaves the last hand empty
+    pub(crate) fn from_hands(seats: usize, starter: Turn, hands: [Hand; 4]) -> Self {
         Round {
             starter,
-            hands: hands.try_into().expect("must be exactly 4 hands"),
+            hands,
             calls: [None; 4],
             tricks: array::from_fn(|_| None),
+            claimed: [0; 4],
+            seats,
+            partners: false,
+            sealed: false,
+            trump: Some(Suit::Spades),
+            ruleset: CallBreak,
+            state: State::Calling,
+            calls_made: 0,
+            slot: 0,
+        }
+    }
+
+    /// Round played by another `ruleset` than Call Break
+    pub(crate) fn with_ruleset<R: TrickTakingRules>(self, ruleset: R) -> Round<R> {
+        Round {
+            starter: self.starter,
+            hands: self.hands,
+            calls: self.calls,
+            tricks: self.tricks,
+            claimed: self.claimed,
+            seats: self.seats,
+            partners: self.partners,
+            sealed: self.sealed,
+            trump: self.trump,
+            ruleset,
+            state: self.state,
+            calls_made: self.calls_made,
+            slot: self.slot,
+        }
+    }
+}
+
+impl<R: TrickTakingRules> Round<R> {
+    fn state(&self) -> State {
+        self.state
+    }
+
+    fn trick(&self) -> &Trick {
+        self.tricks[self.slot]
+            .as_ref()
+            .expect("must have an active trick in this state")
+    }
+
+    /// Round played by the house `rules` of the table, as far as they change the play
+    pub(crate) fn with_rules(mut self, rules: &Rules) -> Self {
+        self.partners = rules.partner(0).is_some();
+        self.sealed = rules.sealed;
+        self
+    }
+
+    /// Round played with `trump` rather than spades, e.g. to replay a recorded round
+    pub(crate) fn with_trump(mut self, trump: Suit) -> Self {
+        self.trump = Some(trump);
+        self
+    }
+
+    // trick at this table, started by `starter`
+    fn new_trick(&self, starter: Turn) -> Trick {
+        let trump = self.trump.expect("trump must be settled before a trick");
+        let trick = Trick::for_seats(starter, self.seats).with_trump(trump);
+        if self.partners {
+            trick.with_partners()
+        } else {
+            trick
         }
     }
 
     pub(crate) fn call(&mut self, call: Call, turn: Turn) -> Result<()> {
         match self.state() {
             State::Calling => {
-                if self.turn()? != turn {
+                // sealed calls are taken from any seat that has not called yet
+                if self.sealed && usize::from(turn) < self.seats {
+                    if self.calls[turn].is_some() {
+                        return Err(Error::AlreadyCalled);
+                    }
+                } else if self.turn()? != turn {
                     return Err(Error::NotYourTurn);
-                } else {
-                    self.calls[turn] = Some(call);
                 }
-                // if all calls are made, start a trick
-                if turn.next() == self.starter {
-                    self.tricks[0] = Some(Trick::new(self.starter))
+                self.calls[turn] = Some(call);
+                self.calls_made += 1;
+                // if all calls are made, start a trick once there is a trump
+                if self.calls_made as usize == self.seats {
+                    if self.trump.is_some() {
+                        self.start();
+                    } else {
+                        self.state = State::ChoosingTrump;
+                    }
                 }
                 Ok(())
             }
@@ -76,20 +187,34 @@ impl Round {
         }
     }
 
+    /// Settle the trump of the round, which is up to the highest caller
+    pub(crate) fn choose_trump(&mut self, trump: Suit, turn: Turn) -> Result<()> {
+        if self.state() != State::ChoosingTrump {
+            return Err(Error::NotAcceptingTrump);
+        }
+        if turn != self.turn()? {
+            return Err(Error::NotYourTurn);
+        }
+        self.trump = Some(trump);
+        self.start();
+        Ok(())
+    }
+
+    fn start(&mut self) {
+        self.tricks[0] = Some(self.new_trick(self.starter));
+        self.state = State::TrickInProgress;
+    }
+
     pub(crate) fn play(&mut self, card: Card, turn: Turn) -> Result<()> {
         match self.state() {
             State::TrickInProgress => {
-                let slot = self
-                    .tricks
-                    .iter()
-                    .position(|t| t.as_ref().is_some_and(|t| !t.is_over()))
-                    .expect("must have an active trick in this state");
+                let slot = self.slot;
 
                 let winner = {
                     if turn != self.turn()? {
                         return Err(Error::NotYourTurn);
                     }
-                    if !self.get_valid_moves(turn)?.contains(&card) {
+                    if !self.valid_moves(turn)?.contains(card) {
                         return Err(Error::InvalidPlay);
                     }
                     // TODO: if hand.play() passes trick.play() must not fail.
@@ -99,19 +224,22 @@ impl Round {
                         .as_mut()
                         .expect("current trick must be available");
                     self.hands[turn].play(card)?;
-                    trick.play(card)?;
+                    trick.play_by(card, |card, winning, trump| {
+                        self.ruleset.beats(card, winning, trump)
+                    })?;
                     trick
                         .winner()
                         .expect("a started trick must have a winner")
                         .0
                 };
 
-                if slot != 12
-                    && self.tricks[slot]
-                        .as_ref()
-                        .is_some_and(|trick| trick.is_over())
-                {
-                    self.tricks[slot + 1] = Some(Trick::new(winner))
+                if self.trick().is_over() {
+                    if slot == tricks_in_round(self.seats) - 1 {
+                        self.state = State::Over;
+                    } else {
+                        self.tricks[slot + 1] = Some(self.new_trick(winner));
+                        self.slot += 1;
+                    }
                 }
                 Ok(())
             }
@@ -119,49 +247,95 @@ impl Round {
         }
     }
 
+    /// Take the claim of the seat on turn if it holds whatever the others do, and end the round
+    /// with the claimed tricks given to the claimant and the rest to the other side
+    pub(crate) fn claim(&mut self, claim: Claim, turn: Turn) -> Result<()> {
+        if self.state() != State::TrickInProgress {
+            return Err(Error::NotAcceptingPlay);
+        }
+        if turn != self.turn()? {
+            return Err(Error::NotYourTurn);
+        }
+        let left = tricks_in_round(self.seats) - self.slot;
+        if left > CLAIM_TRICKS {
+            return Err(Error::ClaimTooEarly);
+        }
+        let tricks = match claim {
+            Claim::All => left,
+            Claim::Tricks(tricks) => tricks as usize,
+        };
+        if tricks > left {
+            return Err(Error::ClaimExceedsTricksLeft);
+        }
+        if (claim::solve(self, turn, &mut Memo::new()) as usize) < tricks {
+            return Err(Error::ClaimNotGuaranteed);
+        }
+        // INFO: the tricks given up go round the seats of the other side from the claimant's
+        // left, the trick being played is left as far as it got
+        let others: Vec<Turn> = (1..self.seats)
+            .map(|i| turn.after(i, self.seats))
+            .filter(|other| self.partner(turn) != Some(*other))
+            .collect();
+        self.claimed[turn] += tricks as u8;
+        for other in others.iter().cycle().take(left - tricks) {
+            self.claimed[*other] += 1;
+        }
+        self.state = State::Over;
+        Ok(())
+    }
+
     pub(crate) fn get_valid_moves(&self, turn: Turn) -> Result<Vec<Card>> {
+        Ok(self.valid_moves(turn)?.iter().collect())
+    }
+
+    /// Cards the seat may play, as a bitset so that nothing is allocated
+    pub(crate) fn valid_moves(&self, turn: Turn) -> Result<Hand> {
         match self.state() {
             State::TrickInProgress => {
                 if turn != self.turn()? {
                     return Err(Error::NotYourTurn);
                 }
-                let slot = self
-                    .tricks
-                    .iter()
-                    .position(|t| t.as_ref().is_some_and(|t| !t.is_over()))
-                    .expect("must have an active trick in this state");
-                let cards: Vec<Card> = self.hands[turn].filter(|_| true).cloned().collect();
-                Ok(self.tricks[slot].as_ref().unwrap().valid_play_from(&cards))
+                Ok(self.ruleset.valid_moves(self.trick(), &self.hands[turn]))
             }
             _ => Err(Error::NotAcceptingPlay),
         }
     }
 
+    /// Trick being played, or the last one once the round is over
+    pub(crate) fn current_trick(&self) -> Option<&Trick> {
+        self.tricks[self.slot].as_ref()
+    }
+
     pub(crate) fn turn(&self) -> Result<Turn> {
         match self.state() {
-            State::Calling => {
-                let mut next = self.starter;
-                while self.calls[next].is_some() {
-                    // loop must terminate because it is calling
-                    next = next.next();
-                }
-                Ok(next)
-            }
-            State::TrickInProgress => {
-                let slot = self
-                    .tricks
-                    .iter()
-                    .position(|t| t.as_ref().is_some_and(|t| !t.is_over()))
-                    .expect("must have an active trick in this state");
-                self.tricks[slot]
-                    .as_ref()
-                    .expect("active trick must be Some(trick)")
-                    .turn()
-            }
+            // sealed calls may come in any order, the first seat yet to call is the one asked
+            State::Calling => Ok(self
+                .yet_to_call()
+                .next()
+                .expect("must have a seat yet to call while calling")),
+            State::ChoosingTrump => Ok(trump::chooser(&self.calls, self.starter, self.seats)),
+            State::TrickInProgress => self.trick().turn(),
             State::Over => Err(Error::RoundIsOver),
         }
     }
 
+    /// Seats that are still to call, in the order they are asked
+    pub(crate) fn yet_to_call(&self) -> impl Iterator<Item = Turn> + '_ {
+        (0..self.seats)
+            .map(|i| self.starter.after(i, self.seats))
+            .filter(|turn| self.calls[*turn].is_none())
+    }
+
+    /// Calls as the seat may see them, which is only their own until all are in when the calls
+    /// are sealed
+    pub(crate) fn visible_calls(&self, turn: Turn) -> [Option<Call>; 4] {
+        if self.sealed && self.is_calling() {
+            array::from_fn(|seat| self.calls[seat].filter(|_| seat == usize::from(turn)))
+        } else {
+            self.calls
+        }
+    }
+
     pub(crate) fn get_hand(&self, turn: Turn) -> &Hand {
         &self.hands[turn]
     }
@@ -174,6 +348,47 @@ impl Round {
         &self.tricks
     }
 
+    /// Number of tricks won by each seat, played or claimed
+    pub(crate) fn tricks_won(&self) -> [u8; 4] {
+        let mut won = Trick::count_wins(self.tricks.iter().flatten());
+        for (won, claimed) in won.iter_mut().zip(self.claimed) {
+            *won += claimed;
+        }
+        won
+    }
+
+    /// Tricks each seat was given by a claim that settled the round
+    pub(crate) fn claimed(&self) -> &[u8; 4] {
+        &self.claimed
+    }
+
+    /// Seat across the table from `seat`, when the round is played in partnerships
+    pub(crate) fn partner(&self, seat: Turn) -> Option<Turn> {
+        self.partners.then(|| seat.after(2, 4))
+    }
+
+    /// Hands as they were dealt, with the cards played so far put back
+    pub(crate) fn dealt(&self) -> [Vec<Card>; 4] {
+        let mut hands = self.hands.clone().map(|hand| hand.bits());
+        for (turn, card) in self.tricks.iter().flatten().flat_map(|t| t.plays()) {
+            hands[turn] |= card.bit();
+        }
+        hands.map(|hand| Hand::from_bits(hand).iter().collect())
+    }
+
+    pub(crate) fn is_calling(&self) -> bool {
+        self.state() == State::Calling
+    }
+
+    pub(crate) fn is_choosing_trump(&self) -> bool {
+        self.state() == State::ChoosingTrump
+    }
+
+    /// Trump of the round, once it is settled
+    pub(crate) fn trump(&self) -> Option<Suit> {
+        self.trump
+    }
+
     pub(crate) fn is_over(&self) -> bool {
         self.state() == State::Over
     }
@@ -249,4 +464,108 @@ mod test {
             }
         }
     }
+
+    // a round with its calls made and played up to `left` tricks from the end
+    fn round_with_tricks_left(left: usize) -> Round {
+        use rand::{SeedableRng, rngs::StdRng};
+        let mut starter = Turn::new(0);
+        let mut round = Round::deal(starter, &mut StdRng::seed_from_u64(7));
+        for _ in 0..4 {
+            round.call(Call::new(3).unwrap(), starter).unwrap();
+            starter = starter.next();
+        }
+        for _ in 0..(13 - left) * 4 {
+            let turn = round.turn().unwrap();
+            let moves = round.get_valid_moves(turn).unwrap();
+            round.play(*moves.first().unwrap(), turn).unwrap();
+        }
+        round
+    }
+
+    #[test]
+    fn must_err_on_claim_early_in_the_round() {
+        let mut round = round_with_tricks_left(CLAIM_TRICKS + 1);
+        let turn = round.turn().unwrap();
+        assert_eq!(
+            round.claim(Claim::Tricks(0), turn),
+            Err(Error::ClaimTooEarly)
+        );
+        assert_eq!(
+            round.claim(Claim::Tricks(0), turn.next()),
+            Err(Error::NotYourTurn)
+        );
+    }
+
+    // a round played in partnerships up to `left` tricks from the end
+    fn partnership_round_with_tricks_left(seed: u64, left: usize) -> Round {
+        use rand::{SeedableRng, rngs::StdRng};
+        let rules = Rules::default().with_partners();
+        let mut round =
+            Round::deal(Turn::new(0), &mut StdRng::seed_from_u64(seed)).with_rules(&rules);
+        let mut starter = Turn::new(0);
+        for _ in 0..4 {
+            round.call(Call::new(3).unwrap(), starter).unwrap();
+            starter = starter.next();
+        }
+        for _ in 0..(13 - left) * 4 {
+            let turn = round.turn().unwrap();
+            let moves = round.get_valid_moves(turn).unwrap();
+            round.play(*moves.first().unwrap(), turn).unwrap();
+        }
+        round
+    }
+
+    #[test]
+    fn partners_claim_together_and_are_given_what_they_claimed() {
+        let round = partnership_round_with_tricks_left(0, 4);
+        let turn = round.turn().unwrap();
+        let partner = round.partner(turn).unwrap();
+        let before = round.tricks_won();
+        let side = |won: [u8; 4]| won[turn] + won[partner];
+
+        // the tricks are only sure with the partner's cards on the claimant's side
+        let mut alone = round.clone();
+        alone.partners = false;
+        assert_eq!(
+            alone.claim(Claim::All, turn),
+            Err(Error::ClaimNotGuaranteed)
+        );
+        let mut all = round.clone();
+        all.claim(Claim::All, turn).unwrap();
+        assert!(all.is_over());
+        assert_eq!(side(all.tricks_won()) - side(before), 4);
+
+        // claiming fewer than are sure gives the rest to the other side
+        let mut one = round.clone();
+        one.claim(Claim::Tricks(1), turn).unwrap();
+        assert_eq!(side(one.tricks_won()) - side(before), 1);
+        assert_eq!(one.tricks_won().iter().sum::<u8>(), 13);
+    }
+
+    #[test]
+    fn claim_ends_the_round_only_when_it_holds() {
+        let round = round_with_tricks_left(CLAIM_TRICKS);
+        let turn = round.turn().unwrap();
+        let sure = claim::solve(&round, turn, &mut Memo::new());
+        let before = round.tricks_won()[turn];
+
+        let mut claimed = round.clone();
+        let too_many = Claim::Tricks(CLAIM_TRICKS as u8 + 1);
+        assert_eq!(
+            claimed.claim(too_many, turn),
+            Err(Error::ClaimExceedsTricksLeft)
+        );
+        if (sure as usize) < CLAIM_TRICKS {
+            assert_eq!(
+                claimed.claim(Claim::All, turn),
+                Err(Error::ClaimNotGuaranteed)
+            );
+        }
+        assert!(!claimed.is_over());
+
+        claimed.claim(Claim::Tricks(sure), turn).unwrap();
+        assert!(claimed.is_over());
+        assert_eq!(claimed.tricks_won()[turn] - before, sure);
+        assert_eq!(claimed.tricks_won().iter().sum::<u8>(), 13);
+    }
 }
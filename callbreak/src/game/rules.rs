@@ -0,0 +1,320 @@
+use super::call::contract_score;
+use super::{Call, MAX_ROUNDS, ROUNDS, Trump};
+use crate::Error;
+use serde::{Deserialize, Serialize};
+
+/// House rules a table may play by on top of the standard game, all off by default
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Rules {
+    #[serde(default)]
+    pub seats: Seats,
+    /// How the players who did not ask for a seat are seated once the table is full
+    #[serde(default)]
+    pub seating: Seating,
+    /// Allow calling nil, a promise to take no tricks at all, see [`Call::nil`]
+    #[serde(default)]
+    pub nil: bool,
+    /// Play in teams of two, the players sitting across from each other scoring their calls
+    /// together, see [`Rules::partner`]. Only at a table of four
+    #[serde(default)]
+    pub partners: bool,
+    /// How the trump suit of every round is settled
+    #[serde(default)]
+    pub trump: Trump,
+    /// Everyone calls without seeing the others' calls, which are shown together once all are in
+    #[serde(default)]
+    pub sealed: bool,
+    /// Reward big calls that are made and punish the ones that are missed
+    #[serde(default)]
+    pub special: Option<SpecialCall>,
+    /// Play rounds until a seat has this many points, rather than five of them
+    #[serde(default)]
+    pub target: Option<f32>,
+}
+
+/// Number of players at the table
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "usize", into = "usize")]
+pub enum Seats {
+    /// Played with the 2 of clubs left out of the deck, so 17 cards each
+    Three,
+    #[default]
+    Four,
+}
+
+impl Seats {
+    pub fn count(self) -> usize {
+        match self {
+            Self::Three => 3,
+            Self::Four => 4,
+        }
+    }
+}
+
+impl TryFrom<usize> for Seats {
+    type Error = Error;
+    fn try_from(seats: usize) -> Result<Self, Self::Error> {
+        match seats {
+            3 => Ok(Self::Three),
+            4 => Ok(Self::Four),
+            _ => Err(Error::UnsupportedSeats),
+        }
+    }
+}
+
+impl From<Seats> for usize {
+    fn from(seats: Seats) -> Self {
+        seats.count()
+    }
+}
+
+/// How the players are seated, those who asked for a seat keeping it either way
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Seating {
+    /// Drawn at random, or from the seed of the game so that it can be replayed
+    #[default]
+    Shuffled,
+    /// In the order the players joined, e.g. for tournaments and teaching
+    Fixed,
+}
+
+/// What making a special call is worth
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Reward {
+    /// The game is won there and then
+    InstantWin,
+    /// Points on top of what the call scores anyway
+    Bonus(f32),
+}
+
+/// Rule for calls of [`SpecialCall::from`] tricks or more
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SpecialCall {
+    pub from: u8,
+    pub reward: Reward,
+    /// Missing the call costs twice as much
+    pub double_loss: bool,
+}
+
+impl Default for SpecialCall {
+    /// Making 8 or more wins outright and missing it costs double, as at most tables
+    fn default() -> Self {
+        Self {
+            from: 8,
+            reward: Reward::InstantWin,
+            double_loss: true,
+        }
+    }
+}
+
+/// Why a game came to an end
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+pub enum EndReason {
+    /// Every round was played
+    AllRounds,
+    /// A seat made a special call that wins the game outright
+    InstantWin { seat: usize, round: usize },
+    /// A seat reached the target score of the table, see [`Rules::target`]
+    TargetReached { seat: usize, round: usize },
+}
+
+impl Rules {
+    pub fn with_seats(mut self, seats: Seats) -> Self {
+        self.seats = seats;
+        self
+    }
+
+    pub fn with_seating(mut self, seating: Seating) -> Self {
+        self.seating = seating;
+        self
+    }
+
+    pub fn with_nil(mut self) -> Self {
+        self.nil = true;
+        self
+    }
+
+    pub fn with_partners(mut self) -> Self {
+        self.partners = true;
+        self
+    }
+
+    pub fn with_trump(mut self, trump: Trump) -> Self {
+        self.trump = trump;
+        self
+    }
+
+    pub fn with_sealed_calls(mut self) -> Self {
+        self.sealed = true;
+        self
+    }
+
+    pub fn with_target(mut self, target: f32) -> Self {
+        self.target = Some(target);
+        self
+    }
+
+    /// Most rounds a game lasts, which is as many as it takes to reach the target if there is one
+    pub fn rounds(&self) -> usize {
+        if self.target.is_some() {
+            MAX_ROUNDS
+        } else {
+            ROUNDS
+        }
+    }
+
+    pub fn with_special_calls(mut self, special: SpecialCall) -> Self {
+        self.special = Some(special);
+        self
+    }
+
+    // the special call rule, if the call is big enough for it to apply
+    fn special(&self, call: Call) -> Option<SpecialCall> {
+        self.special
+            .filter(|special| !call.is_nil() && call.value() >= special.from)
+    }
+
+    /// Points earned for taking `tricks` on the call at this table, see [`Call::score`]
+    pub fn score(&self, call: Call, tricks: u8) -> f32 {
+        let points = call.score(tricks);
+        match self.special(call) {
+            Some(special) if call.is_made(tricks) => match special.reward {
+                Reward::Bonus(bonus) => points + bonus,
+                Reward::InstantWin => points,
+            },
+            Some(special) if special.double_loss => points * 2.0,
+            _ => points,
+        }
+    }
+
+    /// Seat across the table from `seat`, when playing in partnerships
+    pub fn partner(&self, seat: usize) -> Option<usize> {
+        (self.partners && self.seats == Seats::Four).then_some((seat + 2) % 4)
+    }
+
+    /// Points each seat earned for taking `won` tricks on `calls`, once every call is made.
+    /// Partners both get the points of their team
+    pub fn scores(&self, calls: &[Option<Call>; 4], won: [u8; 4]) -> Option<[f32; 4]> {
+        let mut scores = [0.0; 4];
+        for (seat, score) in scores.iter_mut().enumerate().take(self.seats.count()) {
+            *score = self.score(calls[seat]?, won[seat]);
+        }
+        for seat in 0..2 {
+            if let Some(partner) = self.partner(seat) {
+                let team =
+                    self.team_score([calls[seat]?, calls[partner]?], [won[seat], won[partner]]);
+                (scores[seat], scores[partner]) = (team, team);
+            }
+        }
+        Some(scores)
+    }
+
+    // nil calls are scored on their own, while the other calls of the team add up to a single
+    // contract that is made with the tricks of both. special calls still go by the caller's own
+    // tricks, as they do for an instant win
+    fn team_score(&self, calls: [Call; 2], won: [u8; 2]) -> f32 {
+        let mut points = 0.0;
+        let (mut contract, mut taken) = (0, 0);
+        for (call, won) in calls.into_iter().zip(won) {
+            if call.is_nil() {
+                points += call.score(won);
+            } else {
+                contract += call.value();
+                taken += won;
+            }
+        }
+        if contract > 0 {
+            points += contract_score(contract, taken);
+        }
+        for (call, won) in calls.into_iter().zip(won) {
+            match self.special(call) {
+                Some(special) if call.is_made(won) => {
+                    if let Reward::Bonus(bonus) = special.reward {
+                        points += bonus;
+                    }
+                }
+                Some(special) if special.double_loss => points -= call.value() as f32,
+                _ => {}
+            }
+        }
+        points
+    }
+
+    /// Seat that won the game outright by making a special call, once the round is over
+    pub fn instant_winner(&self, calls: &[Option<Call>; 4], won: [u8; 4]) -> Option<usize> {
+        (0..self.seats.count()).find(|seat| {
+            calls[*seat].is_some_and(|call| {
+                self.special(call).is_some_and(|special| {
+                    special.reward == Reward::InstantWin && call.is_made(won[*seat])
+                })
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn special_calls_are_rewarded_and_punished() {
+        let eight = Call::new(8).unwrap();
+        let seven = Call::new(7).unwrap();
+        let standard = Rules::default();
+        assert_eq!(standard.score(eight, 5), -8.0);
+        assert_eq!(
+            standard.instant_winner(&[Some(eight); 4], [8, 0, 0, 0]),
+            None
+        );
+
+        let instant = Rules::default().with_special_calls(SpecialCall::default());
+        assert_eq!(instant.score(eight, 5), -16.0);
+        assert_eq!(instant.score(seven, 5), -7.0);
+        assert_eq!(instant.score(eight, 9), 8.1);
+        let calls = [Some(seven), Some(eight), Some(seven), Some(seven)];
+        assert_eq!(instant.instant_winner(&calls, [0, 8, 5, 0]), Some(1));
+        assert_eq!(instant.instant_winner(&calls, [7, 6, 0, 0]), None);
+
+        let bonus = Rules::default().with_special_calls(SpecialCall {
+            from: 8,
+            reward: Reward::Bonus(10.0),
+            double_loss: false,
+        });
+        assert_eq!(bonus.score(eight, 8), 18.0);
+        assert_eq!(bonus.score(eight, 7), -8.0);
+        assert_eq!(bonus.instant_winner(&calls, [0, 8, 5, 0]), None);
+    }
+
+    #[test]
+    fn partners_score_their_calls_together() {
+        let call = |value| Some(Call::new(value).unwrap());
+        let calls = [call(3), call(2), call(4), call(5)];
+        let won = [2, 3, 6, 2];
+        assert_eq!(
+            Rules::default().scores(&calls, won),
+            Some([-3.0, 2.1, 4.2, -5.0])
+        );
+        let partners = Rules::default().with_partners();
+        assert_eq!(partners.partner(1), Some(3));
+        // 3 + 4 made with 8 tricks, 2 + 5 missed with 5
+        assert_eq!(partners.scores(&calls, won), Some([7.1, -7.0, 7.1, -7.0]));
+        assert_eq!(
+            partners.scores(&[None, call(2), call(4), call(5)], won),
+            None
+        );
+
+        let nil = [Some(Call::nil()), call(2), call(4), call(5)];
+        let partners = partners.with_nil();
+        assert_eq!(
+            partners.scores(&nil, [0, 3, 8, 2]),
+            Some([9.4, -7.0, 9.4, -7.0])
+        );
+
+        let three = partners.with_seats(Seats::Three);
+        assert_eq!(three.partner(0), None);
+        assert_eq!(three.scores(&calls, won), Some([-3.0, 2.1, 4.2, 0.0]));
+    }
+}
@@ -0,0 +1,430 @@
+use super::{Call, Card, Hand, Rules, Suit, Trick, trick};
+use crate::{Error, Result};
+use serde::{Deserialize, Deserializer, Serialize, de};
+use std::fmt::Debug;
+
+/// Calls and tricks won by each seat in a round
+pub type RoundOutcome = ([Option<Call>; 4], [u8; 4]);
+
+/// Rules of a trick-taking game: what may be played to a trick and who takes it, what may be
+/// called, and how the calls are scored. The table itself, its seats, partnerships and trump, is
+/// still set up by [`Rules`]
+pub trait TrickTakingRules: Debug + Clone + Default {
+    /// Ruleset to keep with the record of a game and send with its views, so that they are scored
+    /// the way the game is
+    fn ruleset(&self) -> Ruleset;
+
+    /// Cards of the hand that may be played to the trick
+    fn valid_moves(&self, trick: &Trick, hand: &Hand) -> Hand;
+
+    /// Whether `card` takes the trick from the `winning` one, which the highest card of the
+    /// suit led or the highest trump does by default
+    fn beats(&self, card: Card, winning: Card, trump: Suit) -> bool {
+        trick::beats(card, winning, trump)
+    }
+
+    /// Whether `call` may be made at a table playing by `rules`
+    fn check_call(&self, call: Call, rules: &Rules) -> Result<()>;
+
+    /// Points a single `call` earns for taking `tricks`, as a player weighs their call before
+    /// knowing what the others take
+    fn call_score(&self, call: Call, tricks: u8, rules: &Rules) -> f32;
+
+    /// Points a trick taken towards a made call is about worth, to put the scores of different
+    /// rulesets on the same scale
+    fn trick_value(&self) -> f32 {
+        1.0
+    }
+
+    /// Points each seat earned for taking `won` tricks on `calls`, once every call is made
+    fn round_scores(
+        &self,
+        rules: &Rules,
+        calls: &[Option<Call>; 4],
+        won: [u8; 4],
+    ) -> Option<[f32; 4]>;
+
+    /// Points collected by each seat over the `rounds` that are over. The rounds are added up,
+    /// unless the rules carry something over from one round to the next
+    fn total(&self, rules: &Rules, rounds: &[RoundOutcome]) -> [f32; 4] {
+        let mut total = [0.0; 4];
+        for (calls, won) in rounds {
+            for (total, score) in total
+                .iter_mut()
+                .zip(self.round_scores(rules, calls, *won).unwrap_or_default())
+            {
+                *total += score;
+            }
+        }
+        total
+    }
+}
+
+/// Call Break as the rest of the crate plays it, with the house rules of the table
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CallBreak;
+
+impl TrickTakingRules for CallBreak {
+    fn ruleset(&self) -> Ruleset {
+        Ruleset::CallBreak
+    }
+
+    fn valid_moves(&self, trick: &Trick, hand: &Hand) -> Hand {
+        trick.valid_moves(hand)
+    }
+
+    fn check_call(&self, call: Call, rules: &Rules) -> Result<()> {
+        if call.is_nil() && !rules.nil {
+            return Err(Error::NilNotAllowed);
+        }
+        Ok(())
+    }
+
+    fn call_score(&self, call: Call, tricks: u8, rules: &Rules) -> f32 {
+        rules.score(call, tricks)
+    }
+
+    fn round_scores(
+        &self,
+        rules: &Rules,
+        calls: &[Option<Call>; 4],
+        won: [u8; 4],
+    ) -> Option<[f32; 4]> {
+        rules.scores(calls, won)
+    }
+}
+
+/// Spades: suit must be followed but a trick need not be beaten, a made call earns ten points a
+/// trick and one for every extra trick, or bag, and collecting too many bags costs points.
+/// Nil may always be called
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Spades {
+    /// Points won for making a nil call, and lost for missing it
+    pub nil: f32,
+    /// Bags that cost [`Spades::bag_penalty`] every time that many are collected, at least one
+    #[serde(deserialize_with = "bag_limit")]
+    pub bag_limit: u8,
+    pub bag_penalty: f32,
+}
+
+fn bag_limit<'de, D: Deserializer<'de>>(deserializer: D) -> std::result::Result<u8, D::Error> {
+    match u8::deserialize(deserializer)? {
+        0 => Err(de::Error::custom("bag limit must be at least one")),
+        limit => Ok(limit),
+    }
+}
+
+impl Default for Spades {
+    /// A hundred for nil and a hundred lost for every ten bags, as at most tables
+    fn default() -> Self {
+        Self {
+            nil: 100.0,
+            bag_limit: 10,
+            bag_penalty: 100.0,
+        }
+    }
+}
+
+impl Spades {
+    // seats that score together, each seat on its own unless playing in partnerships
+    fn sides(rules: &Rules) -> Vec<Vec<usize>> {
+        let seats = rules.seats.count();
+        (0..seats)
+            .filter(|seat| rules.partner(*seat).is_none_or(|partner| partner > *seat))
+            .map(|seat| rules.partner(seat).into_iter().chain([seat]).collect())
+            .collect()
+    }
+
+    // points and bags of a side for taking `won` tricks on `calls`. nil calls are scored on
+    // their own, while the others add up to a single contract
+    fn side(&self, calls: &[Option<Call>; 4], won: [u8; 4], seats: &[usize]) -> Option<(f32, u8)> {
+        let mut points = 0.0;
+        let (mut contract, mut taken) = (0, 0);
+        for &seat in seats {
+            let call = calls[seat]?;
+            if call.is_nil() {
+                points += if call.is_made(won[seat]) {
+                    self.nil
+                } else {
+                    -self.nil
+                };
+            } else {
+                contract += call.value();
+                taken += won[seat];
+            }
+        }
+        if contract == 0 {
+            return Some((points, 0));
+        }
+        if taken >= contract {
+            let bags = taken - contract;
+            Some((points + 10.0 * contract as f32 + bags as f32, bags))
+        } else {
+            Some((points - 10.0 * contract as f32, 0))
+        }
+    }
+}
+
+impl TrickTakingRules for Spades {
+    fn ruleset(&self) -> Ruleset {
+        Ruleset::Spades(*self)
+    }
+
+    // INFO: spades may be led at any time, as at many tables, so a card the Call Break rules
+    // allow is always allowed here too
+    fn valid_moves(&self, trick: &Trick, hand: &Hand) -> Hand {
+        if trick.is_over() {
+            return Hand::default();
+        }
+        let (_, Some(led)) = trick.starter() else {
+            return hand.clone();
+        };
+        let suit = hand.bits() & led.get_suit().mask();
+        if suit == 0 {
+            hand.clone()
+        } else {
+            Hand::from_bits(suit)
+        }
+    }
+
+    fn check_call(&self, _call: Call, _rules: &Rules) -> Result<()> {
+        Ok(())
+    }
+
+    fn call_score(&self, call: Call, tricks: u8, _rules: &Rules) -> f32 {
+        let mut calls = [None; 4];
+        calls[0] = Some(call);
+        let (points, _) = self
+            .side(&calls, [tricks, 0, 0, 0], &[0])
+            .expect("the call is in");
+        points
+    }
+
+    fn trick_value(&self) -> f32 {
+        10.0
+    }
+
+    fn round_scores(
+        &self,
+        rules: &Rules,
+        calls: &[Option<Call>; 4],
+        won: [u8; 4],
+    ) -> Option<[f32; 4]> {
+        let mut scores = [0.0; 4];
+        for seats in Self::sides(rules) {
+            let (points, _) = self.side(calls, won, &seats)?;
+            for seat in seats {
+                scores[seat] = points;
+            }
+        }
+        Some(scores)
+    }
+
+    fn total(&self, rules: &Rules, rounds: &[RoundOutcome]) -> [f32; 4] {
+        let mut total = [0.0; 4];
+        for seats in Self::sides(rules) {
+            let (mut points, mut bags) = (0.0, 0);
+            for (calls, won) in rounds {
+                let Some((earned, extra)) = self.side(calls, *won, &seats) else {
+                    continue;
+                };
+                points += earned;
+                // every time the bags reach the limit they are paid for and start over, a limit
+                // of zero set in code never costs anything
+                bags += extra;
+                while self.bag_limit > 0 && bags >= self.bag_limit {
+                    points -= self.bag_penalty;
+                    bags -= self.bag_limit;
+                }
+            }
+            for seat in seats {
+                total[seat] = points;
+            }
+        }
+        total
+    }
+}
+
+/// Any of the rulesets of the crate, as a game is recorded and shown to its players
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "name", rename_all = "kebab-case")]
+pub enum Ruleset {
+    #[default]
+    CallBreak,
+    Spades(Spades),
+}
+
+impl TrickTakingRules for Ruleset {
+    fn ruleset(&self) -> Ruleset {
+        *self
+    }
+
+    fn valid_moves(&self, trick: &Trick, hand: &Hand) -> Hand {
+        match self {
+            Self::CallBreak => CallBreak.valid_moves(trick, hand),
+            Self::Spades(spades) => spades.valid_moves(trick, hand),
+        }
+    }
+
+    fn beats(&self, card: Card, winning: Card, trump: Suit) -> bool {
+        match self {
+            Self::CallBreak => CallBreak.beats(card, winning, trump),
+            Self::Spades(spades) => spades.beats(card, winning, trump),
+        }
+    }
+
+    fn check_call(&self, call: Call, rules: &Rules) -> Result<()> {
+        match self {
+            Self::CallBreak => CallBreak.check_call(call, rules),
+            Self::Spades(spades) => spades.check_call(call, rules),
+        }
+    }
+
+    fn call_score(&self, call: Call, tricks: u8, rules: &Rules) -> f32 {
+        match self {
+            Self::CallBreak => CallBreak.call_score(call, tricks, rules),
+            Self::Spades(spades) => spades.call_score(call, tricks, rules),
+        }
+    }
+
+    fn trick_value(&self) -> f32 {
+        match self {
+            Self::CallBreak => CallBreak.trick_value(),
+            Self::Spades(spades) => spades.trick_value(),
+        }
+    }
+
+    fn round_scores(
+        &self,
+        rules: &Rules,
+        calls: &[Option<Call>; 4],
+        won: [u8; 4],
+    ) -> Option<[f32; 4]> {
+        match self {
+            Self::CallBreak => CallBreak.round_scores(rules, calls, won),
+            Self::Spades(spades) => spades.round_scores(rules, calls, won),
+        }
+    }
+
+    fn total(&self, rules: &Rules, rounds: &[RoundOutcome]) -> [f32; 4] {
+        match self {
+            Self::CallBreak => CallBreak.total(rules, rounds),
+            Self::Spades(spades) => spades.total(rules, rounds),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::Difficulty;
+    use crate::game::Rank::*;
+    use crate::game::Suit::{Clubs, Hearts};
+    use crate::game::Turn;
+    use crate::host::bot_game;
+
+    fn call(value: u8) -> Option<Call> {
+        Some(Call::new(value).unwrap())
+    }
+
+    #[test]
+    fn bots_play_a_game_of_spades() {
+        let spades = Spades::default();
+        let host = bot_game(7, Difficulty::Beginner, spades);
+        assert!(host.is_over());
+        let record = host.record();
+        let rounds: Vec<RoundOutcome> = record
+            .rounds
+            .iter()
+            .map(|round| (round.calls, round.tricks_won()))
+            .collect();
+        assert_eq!(rounds.len(), 5);
+        assert_eq!(host.scores(), spades.total(&record.rules, &rounds));
+        assert_eq!(record.ruleset, Ruleset::Spades(spades));
+        assert_eq!(host.scores(), record.scores());
+    }
+
+    #[test]
+    fn spades_need_not_beat_the_trick() {
+        let mut trick = Trick::new(Turn::new(0));
+        trick.play(Card::new(Ten, Hearts)).unwrap();
+        let hand = Hand::new([
+            Card::new(Two, Hearts),
+            Card::new(Ace, Hearts),
+            Card::new(Ace, Suit::Spades),
+        ]);
+        let follow = Hand::new([Card::new(Two, Hearts), Card::new(Ace, Hearts)]);
+        assert_eq!(Spades::default().valid_moves(&trick, &hand), follow);
+        assert_eq!(
+            CallBreak.valid_moves(&trick, &hand),
+            Hand::new([Card::new(Ace, Hearts)])
+        );
+        let void = Hand::new([Card::new(Two, Clubs), Card::new(Ace, Suit::Spades)]);
+        assert_eq!(Spades::default().valid_moves(&trick, &void), void);
+    }
+
+    #[test]
+    fn single_calls_are_weighed_by_the_ruleset() {
+        let rules = Rules::default();
+        let four = Call::new(4).unwrap();
+        assert_eq!(Spades::default().call_score(four, 5, &rules), 41.0);
+        assert_eq!(Spades::default().call_score(four, 3, &rules), -40.0);
+        assert_eq!(Spades::default().call_score(Call::nil(), 0, &rules), 100.0);
+        assert_eq!(CallBreak.call_score(four, 5, &rules), four.score(5));
+        assert_eq!(Ruleset::Spades(Spades::default()).trick_value(), 10.0);
+        assert_eq!(Ruleset::CallBreak.trick_value(), 1.0);
+    }
+
+    #[test]
+    fn spades_scores_tens_bags_and_nil() {
+        let spades = Spades::default();
+        let rules = Rules::default();
+        let calls = [call(3), Some(Call::nil()), call(4), call(5)];
+        assert_eq!(
+            spades.round_scores(&rules, &calls, [5, 0, 4, 4]),
+            Some([32.0, 100.0, 40.0, -50.0])
+        );
+        assert!(spades.check_call(Call::nil(), &rules).is_ok());
+        assert_eq!(
+            CallBreak.check_call(Call::nil(), &rules),
+            Err(Error::NilNotAllowed)
+        );
+        // partners share a contract of 7, and the nil of seat 1 is scored on its own
+        let partners = rules.with_partners();
+        assert_eq!(
+            spades.round_scores(&partners, &calls, [5, 1, 4, 3]),
+            Some([72.0, -150.0, 72.0, -150.0])
+        );
+    }
+
+    #[test]
+    fn spades_charges_for_every_ten_bags() {
+        let spades = Spades::default();
+        let rules = Rules::default();
+        // seat 0 collects 4 bags a round, which pass 10 in the third
+        let round = ([call(1), call(4), call(4), call(4)], [5, 4, 4, 0]);
+        let total = spades.total(&rules, &[round, round]);
+        assert_eq!(total, [28.0, 80.0, 80.0, -80.0]);
+        let total = spades.total(&rules, &[round, round, round]);
+        assert_eq!(total, [42.0 - 100.0, 120.0, 120.0, -120.0]);
+        assert_eq!(CallBreak.total(&rules, &[round]), [1.4, 4.0, 4.0, -4.0]);
+    }
+
+    #[test]
+    fn bag_limit_of_zero_is_rejected() {
+        let json = r#"{"name": "spades", "nil": 100.0, "bag_limit": 0, "bag_penalty": 100.0}"#;
+        assert!(serde_json::from_str::<Ruleset>(json).is_err());
+        let json = json.replace("\"bag_limit\": 0", "\"bag_limit\": 10");
+        assert_eq!(
+            serde_json::from_str::<Ruleset>(&json).unwrap(),
+            Ruleset::Spades(Spades::default())
+        );
+        // one set in code rather than read in still lets the game be scored
+        let spades = Spades {
+            bag_limit: 0,
+            ..Spades::default()
+        };
+        let round = ([call(1), call(4), call(4), call(4)], [5, 4, 4, 0]);
+        assert_eq!(spades.total(&Rules::default(), &[round])[0], 14.0);
+    }
+}
@@ -0,0 +1,53 @@
+use super::{Call, RoundOutcome, Rules, Trick, TrickTakingRules, tricks_in_round};
+
+/// Whether every trick of a round has been played, or settled by a claim
+pub(crate) fn is_round_over(tricks: &[Trick], claimed: &[u8; 4]) -> bool {
+    let played = tricks.iter().filter(|trick| trick.is_over()).count();
+    let claimed: usize = claimed.iter().map(|tricks| *tricks as usize).sum();
+    tricks
+        .first()
+        .is_some_and(|first| played + claimed == tricks_in_round(first.seats()))
+}
+
+/// Whether no trick of the round was settled by a claim, to leave the claims out of records
+pub(crate) fn is_unclaimed(claimed: &[u8; 4]) -> bool {
+    claimed.iter().all(|tricks| *tricks == 0)
+}
+
+/// Number of tricks won by each seat, whether they were played or settled by a claim
+pub(crate) fn tricks_won(tricks: &[Trick], claimed: &[u8; 4]) -> [u8; 4] {
+    let mut won = Trick::count_wins(tricks);
+    for (won, claimed) in won.iter_mut().zip(claimed) {
+        *won += claimed;
+    }
+    won
+}
+
+/// Points each seat earned in a round, scored by the `ruleset` at a table playing by `rules`,
+/// once it is over
+pub(crate) fn round_scores(
+    ruleset: &impl TrickTakingRules,
+    rules: &Rules,
+    calls: &[Option<Call>; 4],
+    tricks: &[Trick],
+    claimed: &[u8; 4],
+) -> Option<[f32; 4]> {
+    if !is_round_over(tricks, claimed) {
+        return None;
+    }
+    ruleset.round_scores(rules, calls, tricks_won(tricks, claimed))
+}
+
+/// Points collected by each seat over the rounds that are over, scored by the `ruleset`
+pub(crate) fn total<'a>(
+    ruleset: &impl TrickTakingRules,
+    rules: &Rules,
+    rounds: impl IntoIterator<Item = (&'a [Option<Call>; 4], &'a [Trick], &'a [u8; 4])>,
+) -> [f32; 4] {
+    let rounds: Vec<RoundOutcome> = rounds
+        .into_iter()
+        .filter(|(_, tricks, claimed)| is_round_over(tricks, claimed))
+        .map(|(calls, tricks, claimed)| (*calls, tricks_won(tricks, claimed)))
+        .collect();
+    ruleset.total(rules, &rounds)
+}
@@ -1,12 +1,95 @@
-use super::{Card, Suit, Turn};
+use super::{Card, Hand, Suit, Turn};
 use crate::{Error, Result};
 use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "TrickData")]
 pub struct Trick {
     starter: Turn,
     cards: [Option<Card>; 4],
+    // players at the table, the last seat is left empty at a table of three
+    #[serde(skip_serializing_if = "is_standard")]
+    seats: u8,
+    // whether opposite seats play as partners, who need not beat each other
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    partners: bool,
+    #[serde(skip_serializing_if = "is_spades")]
+    trump: Suit,
+    // INFO: the fields below follow from the ones above and are kept up to date on every play so
+    // that nothing needs to be worked out again on the hot path of a search
+    #[serde(skip)]
+    played: u8,
+    #[serde(skip)]
+    winner: Option<Turn>,
+}
+
+// what a trick looks like on the wire
+#[derive(Deserialize)]
+struct TrickData {
+    starter: Turn,
+    cards: [Option<Card>; 4],
+    #[serde(default = "standard")]
+    seats: u8,
+    #[serde(default)]
+    partners: bool,
+    #[serde(default = "spades")]
+    trump: Suit,
+}
+
+fn standard() -> u8 {
+    4
+}
+
+fn is_standard(seats: &u8) -> bool {
+    *seats == standard()
+}
+
+fn spades() -> Suit {
+    Suit::Spades
+}
+
+fn is_spades(trump: &Suit) -> bool {
+    *trump == spades()
+}
+
+impl TryFrom<TrickData> for Trick {
+    type Error = Error;
+
+    fn try_from(data: TrickData) -> Result<Self> {
+        let seats = data.seats as usize;
+        if !(3..=4).contains(&seats) {
+            return Err(Error::UnsupportedSeats);
+        }
+        if usize::from(data.starter) >= seats {
+            return Err(Error::NoSuchSeat);
+        }
+        let mut trick = Trick::for_seats(data.starter, seats);
+        trick.partners = data.partners;
+        trick.trump = data.trump;
+        let mut turn = data.starter;
+        while let Some(card) = data.cards[turn] {
+            trick.play(card)?;
+            if trick.is_over() {
+                break;
+            }
+            turn = turn.after(1, seats);
+        }
+        // the cards have to be played in turn from the starter, with none left over
+        if data.cards.iter().flatten().count() != trick.played as usize {
+            return Err(Error::InvalidPlay);
+        }
+        Ok(trick)
+    }
+}
+
+/// Whether `card` takes the trick from the `winning` card, the standard way
+pub(crate) fn beats(card: Card, winning: Card, trump: Suit) -> bool {
+    if card.get_suit() == winning.get_suit() {
+        card.get_rank() > winning.get_rank()
+    } else {
+        card.get_suit() == trump
+    }
 }
 
 impl Trick {
@@ -14,134 +97,157 @@ impl Trick {
     // so that we can verify stuff like [None, Some(), None, Some()]
     // during deserialization, which should not happen.
     // on second thought, why should this support deserialization at all?!
+    #[cfg(test)]
     pub(crate) fn new(starter: Turn) -> Self {
+        Self::for_seats(starter, 4)
+    }
+
+    /// Trick at a table of `seats`
+    pub(crate) fn for_seats(starter: Turn, seats: usize) -> Self {
         Trick {
             starter,
             cards: [None; 4],
+            seats: seats as u8,
+            partners: false,
+            trump: Suit::Spades,
+            played: 0,
+            winner: None,
         }
     }
 
     pub(crate) fn play(&mut self, card: Card) -> Result<()> {
-        if self.is_over() {
-            Err(Error::NotAcceptingPlay)
-        } else {
-            let next = self.turn().expect("must have next available when not over");
-            // TODO: may be a trick should not accept a duplicate card either?
-            self.cards[next] = Some(card);
-            Ok(())
+        self.play_by(card, beats)
+    }
+
+    /// Play `card`, which takes the trick when it `beats` the card winning it so far
+    pub(crate) fn play_by(
+        &mut self,
+        card: Card,
+        beats: impl Fn(Card, Card, Suit) -> bool,
+    ) -> Result<()> {
+        let next = self.turn()?;
+        // TODO: may be a trick should not accept a duplicate card either?
+        self.cards[next] = Some(card);
+        self.played += 1;
+        let beaten = self
+            .winner()
+            .is_none_or(|(_, winning)| beats(card, winning, self.trump));
+        if beaten {
+            self.winner = Some(next);
         }
+        Ok(())
     }
 
     pub(crate) fn turn(&self) -> Result<Turn> {
         if self.is_over() {
             Err(Error::NotAcceptingPlay)
         } else {
-            let mut turn = self.starter;
-            while self.cards[turn].is_some() {
-                // this loop must terminate because the trick is not over
-                turn = turn.next();
-            }
-            Ok(turn)
+            Ok(self
+                .starter
+                .after(self.played as usize, self.seats as usize))
         }
     }
 
     pub(crate) fn is_over(&self) -> bool {
-        self.cards.iter().all(|c| c.is_some())
+        self.played == self.seats
+    }
+
+    /// Trick between partners sitting across from each other, see [`Rules::partners`]
+    ///
+    /// [`Rules::partners`]: super::Rules::partners
+    pub(crate) fn with_partners(mut self) -> Self {
+        self.partners = true;
+        self
+    }
+
+    /// Trick played with `trump` rather than spades as the trump suit
+    pub(crate) fn with_trump(mut self, trump: Suit) -> Self {
+        self.trump = trump;
+        self
+    }
+
+    pub fn trump(&self) -> Suit {
+        self.trump
+    }
+
+    /// Players at the table the trick is played at
+    pub(crate) fn seats(&self) -> usize {
+        self.seats as usize
     }
 
     pub(crate) fn starter(&self) -> (Turn, Option<Card>) {
         (self.starter, self.cards[self.starter])
     }
 
-    pub(crate) fn winner(&self) -> Option<(Turn, Card)> {
-        // if there is a spade card, return the max spade card by rank
-        // else return the max ranked card of the starter suit
-        match self.starter() {
-            (_, None) => None,
-            (_, Some(starter)) => {
-                let winning_suit = if self
-                    .cards
-                    .iter()
-                    .flatten()
-                    .any(|card| card.get_suit() == Suit::Spades)
-                {
-                    Suit::Spades
-                } else {
-                    starter.get_suit()
-                };
-                let winner = self
-                    .cards
-                    .iter()
-                    .enumerate()
-                    .filter_map(|(i, opt)| opt.as_ref().map(|card| (i, card)))
-                    .filter(|(_, card)| card.get_suit() == winning_suit)
-                    .max_by_key(|(_, card)| card.get_rank())
-                    .expect("must have a winner");
-                Some((Turn::new(winner.0), *winner.1))
-            }
+    /// Cards played so far along with who played them, in the order they were played
+    pub(crate) fn plays(&self) -> impl Iterator<Item = (Turn, Card)> + '_ {
+        let mut turn = self.starter;
+        (0..self.played).map(move |_| {
+            let play = (
+                turn,
+                self.cards[turn].expect("must have the cards played so far"),
+            );
+            turn = turn.after(1, self.seats as usize);
+            play
+        })
+    }
+
+    /// Number of finished tricks won by each seat
+    pub(crate) fn count_wins<'a>(tricks: impl IntoIterator<Item = &'a Trick>) -> [u8; 4] {
+        let mut won = [0; 4];
+        for trick in tricks.into_iter().filter(|t| t.is_over()) {
+            let (winner, _) = trick.winner().expect("a finished trick must have a winner");
+            won[winner] += 1;
         }
+        won
     }
 
+    pub(crate) fn winner(&self) -> Option<(Turn, Card)> {
+        // the highest spade if the trick was cut, or else the highest card of the suit led
+        self.winner.map(|turn| {
+            (
+                turn,
+                self.cards[turn].expect("the winner must have played a card"),
+            )
+        })
+    }
+
+    #[cfg(test)]
     pub(crate) fn valid_play_from(&self, cards: &[Card]) -> Vec<Card> {
+        let hand = Hand::new(cards.iter().copied());
+        let moves = self.valid_moves(&hand);
+        cards
+            .iter()
+            .copied()
+            .filter(|card| moves.contains(*card))
+            .collect()
+    }
+
+    /// Cards of the hand that may be played to the trick
+    pub(crate) fn valid_moves(&self, hand: &Hand) -> Hand {
         if self.is_over() {
-            return vec![];
+            return Hand::default();
         }
+        let (Some((winning, winner)), (_, Some(starter))) = (self.winner(), self.starter()) else {
+            return hand.clone();
+        };
 
-        let starter = if let (_, Some(card)) = self.starter() {
-            card
+        let cards = hand.bits();
+        let suit = cards & starter.get_suit().mask();
+        let trumps = cards & self.trump.mask();
+        // a partner who is winning the trick does not have to be beaten, or trumped
+        let partner_winning =
+            self.partners && self.turn().is_ok_and(|turn| turn.after(2, 4) == winning);
+        // the first of these the hand can follow is what it must play
+        let candidates = if partner_winning {
+            [suit, cards, 0, 0]
+        } else if starter.get_suit() == winner.get_suit() {
+            [cards & winner.above(), suit, trumps, cards]
         } else {
-            return cards.to_owned();
-        };
-        let winner = self
-            .winner()
-            .expect("must have a winner when there is a starter")
-            .1;
-
-        let candidates: [Vec<Card>; 4] = match (starter.get_suit(), winner.get_suit()) {
-            (s, w) if s == w => [
-                cards
-                    .iter()
-                    .filter(|card| card.get_suit() == s && card.get_rank() > winner.get_rank())
-                    .cloned()
-                    .collect(),
-                cards
-                    .iter()
-                    .filter(|card| card.get_suit() == s)
-                    .cloned()
-                    .collect(),
-                // if s = spades this is already handled above but simplifies code for other suits
-                cards
-                    .iter()
-                    .filter(|card| card.get_suit() == Suit::Spades)
-                    .cloned()
-                    .collect(),
-                cards.iter().filter(|_| true).cloned().collect(),
-            ],
-            (s, w) if s != w => [
-                // implies w == Spades and s!= Spades
-                cards
-                    .iter()
-                    .filter(|card| card.get_suit() == s)
-                    .cloned()
-                    .collect(),
-                cards
-                    .iter()
-                    .filter(|card| card.get_suit() == w && card.get_rank() > winner.get_rank())
-                    .cloned()
-                    .collect(),
-                cards.iter().filter(|_| true).cloned().collect(),
-                vec![],
-            ],
-            (s, w) => panic!(
-                "this starter: {:?} winner: {:?} combination must not be possible",
-                s, w
-            ),
+            // the trick has been cut with a trump, which only a higher trump beats
+            [suit, cards & winner.above(), cards, 0]
         };
-
-        match candidates.iter().find(|v| !v.is_empty()) {
-            Some(v) => v.clone(),
-            _ => vec![],
-        }
+        Hand::from_bits(candidates.into_iter().find(|c| *c != 0).unwrap_or(0))
     }
 }
 
@@ -165,7 +271,6 @@ impl Display for Trick {
 mod tests {
 
     use super::*;
-    use crate::game::Hand;
     use crate::game::Rank::*;
     use crate::game::Suit::*;
 
@@ -209,6 +314,76 @@ mod tests {
         assert!(starter.is_some_and(|c| c == card));
     }
 
+    #[test]
+    fn trick_of_three_is_over_after_three_plays_and_wraps_around() {
+        let mut trick = Trick::for_seats(Turn::new(2), 3);
+        trick.play(Card::new(Queen, Clubs)).unwrap();
+        assert_eq!(trick.turn(), Ok(Turn::new(0)));
+        trick.play(Card::new(King, Clubs)).unwrap();
+        trick.play(Card::new(Two, Spades)).unwrap();
+        assert!(trick.is_over());
+        assert_eq!(
+            trick.play(Card::new(Ace, Clubs)),
+            Err(Error::NotAcceptingPlay)
+        );
+        assert_eq!(trick.winner(), Some((Turn::new(1), Card::new(Two, Spades))));
+    }
+
+    #[test]
+    fn partner_who_is_winning_need_not_be_beaten() {
+        let hand = Hand::new([
+            Card::new(Ace, Clubs),
+            Card::new(Three, Clubs),
+            Card::new(Two, Spades),
+            Card::new(Four, Hearts),
+        ]);
+        let mut trick = Trick::new(Turn::new(0));
+        trick.play(Card::new(King, Clubs)).unwrap();
+        trick.play(Card::new(Five, Clubs)).unwrap();
+        assert_eq!(trick.valid_moves(&hand), Hand::new([Card::new(Ace, Clubs)]));
+
+        let mut trick = Trick::new(Turn::new(0)).with_partners();
+        trick.play(Card::new(King, Clubs)).unwrap();
+        trick.play(Card::new(Five, Clubs)).unwrap();
+        assert_eq!(
+            trick.valid_moves(&hand),
+            Hand::new([Card::new(Ace, Clubs), Card::new(Three, Clubs)])
+        );
+        // void in the suit led, any card will do
+        let hand = Hand::new([Card::new(Two, Spades), Card::new(Four, Hearts)]);
+        assert_eq!(trick.valid_moves(&hand), hand);
+        // but an opponent who is winning still has to be beaten
+        trick.play(Card::new(Ace, Clubs)).unwrap();
+        assert_eq!(
+            trick.valid_moves(&hand),
+            Hand::new([Card::new(Two, Spades)])
+        );
+    }
+
+    #[test]
+    fn any_suit_can_be_trump() {
+        let mut trick = Trick::new(Turn::new(0)).with_trump(Hearts);
+        trick.play(Card::new(Queen, Clubs)).unwrap();
+        trick.play(Card::new(Ace, Spades)).unwrap();
+        assert_eq!(
+            trick.winner(),
+            Some((Turn::new(0), Card::new(Queen, Clubs)))
+        );
+        // void in clubs, so a heart has to be played, and it takes the trick
+        let hand = Hand::new([Card::new(Two, Hearts), Card::new(King, Spades)]);
+        assert_eq!(
+            trick.valid_moves(&hand),
+            Hand::new([Card::new(Two, Hearts)])
+        );
+        trick.play(Card::new(Two, Hearts)).unwrap();
+        assert_eq!(trick.winner(), Some((Turn::new(2), Card::new(Two, Hearts))));
+        let json = serde_json::to_string(&trick).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Trick>(&json).unwrap().trump(),
+            Hearts
+        );
+    }
+
     #[test]
     fn winner_is_none_when_no_play() {
         let trick = Trick::new(random_turn());
@@ -272,9 +447,7 @@ mod tests {
         trick.play(Card::new(Seven, Diamonds)).unwrap();
 
         let hand = hand_with_0_clubs_234qk_diamonds_234qk_hearts_23k_spades();
-        // FIXME: this is a temporary fix. should look into just having &[Card] returned from the
-        // fixture above to begin with
-        let hand: Vec<Card> = hand.filter(|_| true).cloned().collect();
+        let hand: Vec<Card> = hand.iter().collect();
         assert!(trick.valid_play_from(&hand).len() == 2)
     }
 
@@ -284,7 +457,7 @@ mod tests {
         trick.play(Card::new(Ace, Diamonds)).unwrap();
 
         let hand = hand_with_0_clubs_234qk_diamonds_234qk_hearts_23k_spades();
-        let hand: Vec<Card> = hand.filter(|_| true).cloned().collect();
+        let hand: Vec<Card> = hand.iter().collect();
         assert!(trick.valid_play_from(&hand).len() == 5)
     }
 
@@ -294,7 +467,7 @@ mod tests {
         trick.play(Card::new(Ace, Clubs)).unwrap();
 
         let hand = hand_with_0_clubs_234qk_diamonds_234qk_hearts_23k_spades();
-        let hand: Vec<Card> = hand.filter(|_| true).cloned().collect();
+        let hand: Vec<Card> = hand.iter().collect();
         assert!(trick.valid_play_from(&hand).len() == 3)
     }
 
@@ -305,7 +478,43 @@ mod tests {
         trick.play(Card::new(Five, Spades)).unwrap();
 
         let hand = hand_with_0_clubs_234qk_diamonds_234qk_hearts_23k_spades();
-        let hand: Vec<Card> = hand.filter(|_| true).cloned().collect();
+        let hand: Vec<Card> = hand.iter().collect();
         assert!(trick.valid_play_from(&hand).len() == 1)
     }
+
+    #[test]
+    fn deserialized_trick_knows_its_turn_and_winner() {
+        let mut trick = Trick::new(Turn::new(2));
+        trick.play(Card::new(Queen, Clubs)).unwrap();
+        trick.play(Card::new(Two, Spades)).unwrap();
+        let json = serde_json::to_string(&trick).unwrap();
+        let trick: Trick = serde_json::from_str(&json).unwrap();
+        assert_eq!(trick.turn(), Ok(Turn::new(0)));
+        assert_eq!(trick.winner(), Some((Turn::new(3), Card::new(Two, Spades))));
+        assert_eq!(trick.plays().count(), 2);
+    }
+
+    #[test]
+    fn malformed_trick_is_rejected_when_deserialized() {
+        let mut trick = Trick::new(Turn::new(2));
+        trick.play(Card::new(Queen, Clubs)).unwrap();
+        trick.play(Card::new(Two, Spades)).unwrap();
+        let valid = serde_json::to_value(&trick).unwrap();
+        let malformed = |field: &str, value: serde_json::Value| {
+            let mut json = valid.clone();
+            json[field] = value;
+            serde_json::from_value::<Trick>(json).is_err()
+        };
+        assert!(malformed("seats", 0.into()));
+        assert!(malformed("seats", 5.into()));
+        assert!(malformed("starter", 7.into()));
+        // a card from a seat whose turn has not come yet
+        let mut cards = valid["cards"].clone();
+        cards[1] = cards[2].clone();
+        assert!(malformed("cards", cards));
+        // a card from the empty seat at a table of three
+        let mut json = valid.clone();
+        json["seats"] = 3.into();
+        assert!(serde_json::from_value::<Trick>(json).is_err());
+    }
 }
@@ -0,0 +1,77 @@
+use super::{Call, Card, Suit, Turn};
+use serde::{Deserialize, Serialize};
+
+/// How the trump suit of a round is settled
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Trump {
+    /// Spades every round, as in the standard game
+    #[default]
+    Spades,
+    /// Picked by the highest caller once every call is in
+    Chosen,
+    /// A different suit every round, starting from spades
+    Rotating,
+}
+
+// order the suits take their turn as trump
+const ROTATION: [Suit; 4] = [Suit::Spades, Suit::Hearts, Suit::Diamonds, Suit::Clubs];
+
+impl Trump {
+    /// Trump of the `round`th round of a game, none when it is left to the highest caller
+    pub fn for_round(self, round: usize) -> Option<Suit> {
+        match self {
+            Self::Spades => Some(Suit::Spades),
+            Self::Chosen => None,
+            Self::Rotating => Some(ROTATION[round % ROTATION.len()]),
+        }
+    }
+}
+
+/// Seat that picks the trump: the highest caller, or the first of them to call on a tie
+pub(crate) fn chooser(calls: &[Option<Call>; 4], starter: Turn, seats: usize) -> Turn {
+    (0..seats)
+        .map(|i| starter.after(i, seats))
+        .rev()
+        .max_by_key(|turn| calls[*turn].map(|call| call.value()))
+        .expect("a table must have seats")
+}
+
+/// The card as it would be with `trump` and spades swapped, so that what is worked out for
+/// spades as trump holds for any trump
+pub(crate) fn as_spades(card: Card, trump: Suit) -> Card {
+    let suit = match card.get_suit() {
+        suit if suit == trump => Suit::Spades,
+        Suit::Spades => trump,
+        suit => suit,
+    };
+    Card::new(card.get_rank(), suit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Rank;
+
+    #[test]
+    fn highest_caller_chooses_and_the_earliest_wins_a_tie() {
+        let call = |value| Some(Call::new(value).unwrap());
+        let calls = [call(3), call(5), call(2), call(5)];
+        assert_eq!(chooser(&calls, Turn::new(0), 4), Turn::new(1));
+        assert_eq!(chooser(&calls, Turn::new(2), 4), Turn::new(3));
+        assert_eq!(chooser(&calls, Turn::new(2), 3), Turn::new(1));
+    }
+
+    #[test]
+    fn rotating_trump_goes_through_every_suit() {
+        let trumps: Vec<Suit> = (0..5)
+            .filter_map(|r| Trump::Rotating.for_round(r))
+            .collect();
+        assert_eq!(trumps[0], Suit::Spades);
+        assert_eq!(trumps[4], Suit::Spades);
+        assert_eq!(Trump::Chosen.for_round(2), None);
+        let ace = Card::new(Rank::Ace, Suit::Hearts);
+        assert_eq!(as_spades(ace, Suit::Hearts).get_suit(), Suit::Spades);
+        assert_eq!(as_spades(ace, Suit::Clubs), ace);
+    }
+}
@@ -4,10 +4,11 @@ use std::{
     ops::{Index, IndexMut},
 };
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[serde(transparent)]
 pub(crate) struct Turn(usize);
 
+#[cfg(test)]
 const MAX_TURN: usize = 3;
 
 impl Turn {
@@ -15,6 +16,13 @@ impl Turn {
         Turn(value % 4)
     }
 
+    /// Seat `offset` places after this one at a table of `seats`
+    pub fn after(&self, offset: usize, seats: usize) -> Self {
+        Self((self.0 + offset) % seats)
+    }
+
+    /// Seat after this one at a table of four
+    #[cfg(test)]
     pub fn next(&self) -> Self {
         let next = (self.0 + 1) % 4;
         assert!(next <= MAX_TURN);
@@ -22,6 +30,12 @@ impl Turn {
     }
 }
 
+impl From<Turn> for usize {
+    fn from(turn: Turn) -> Self {
+        turn.0
+    }
+}
+
 impl Display for Turn {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
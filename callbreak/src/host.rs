@@ -1,15 +1,32 @@
 use crate::Game;
 use crate::Result;
-use crate::agent::AgentKind;
+use crate::agent::Standing;
+use crate::agent::{AgentKind, Bot, Game as GameView, Move, Results};
+use crate::dataset::{Decision, GameData, PlayerData, RoundResult, VERSION};
+use crate::env::{self, ACTIONS, Action, OBSERVATION};
+use crate::game::tricks_in_round;
+use crate::game::{Call, CallBreak, EndReason, Record, Rules, Suit, TrickTakingRules};
 use tracing::debug;
 
-#[derive(Default, Debug)]
-pub struct Host {
+#[derive(Debug)]
+pub struct Host<R = CallBreak> {
     // TODO: I don't currently know the full implications of using Box<dyn> here. there seems to be
     // an altenative to use AgentKind enum with all options which seems to have performance
     // trade-offs
     agents: Vec<(String, AgentKind)>,
-    game: Game,
+    game: Game<R>,
+    // every decision taken so far, when they are being recorded
+    decisions: Option<Vec<Decision>>,
+}
+
+impl Default for Host {
+    fn default() -> Self {
+        Self {
+            agents: vec![],
+            game: Game::default(),
+            decisions: None,
+        }
+    }
 }
 
 impl Host {
@@ -17,14 +34,164 @@ impl Host {
         Self::default()
     }
 
+    /// Host whose seating and deals all follow from `seed`, so that a game can be replayed
+    pub fn with_seed(seed: u64) -> Self {
+        Self {
+            game: Game::with_seed(seed),
+            ..Self::default()
+        }
+    }
+
+    /// Host of a game played by another `ruleset` than Call Break, e.g. [`Spades`]
+    ///
+    /// [`Spades`]: crate::game::Spades
+    pub fn with_ruleset<R: TrickTakingRules>(self, ruleset: R) -> Host<R> {
+        Host {
+            agents: self.agents,
+            game: self.game.with_ruleset(ruleset),
+            decisions: self.decisions,
+        }
+    }
+}
+
+impl<R: TrickTakingRules> Host<R> {
+    /// Play by these house rules rather than the standard ones
+    pub fn with_rules(mut self, rules: Rules) -> Self {
+        self.game = self.game.with_rules(rules);
+        self
+    }
+
+    /// Show the players the totals of the match the game is part of
+    pub(crate) fn with_standings(mut self, standings: Vec<Standing>) -> Self {
+        self.game = self.game.with_standings(standings);
+        self
+    }
+
+    /// Record every decision taken, along with what the player could see, for a dataset
+    pub fn recording(mut self) -> Self {
+        self.decisions = Some(vec![]);
+        self
+    }
+
+    /// Decisions taken so far, if the host is recording them
+    pub fn dataset(&self) -> Option<GameData> {
+        let decisions = self.decisions.clone()?;
+        let record = self.game.record();
+        let players = record
+            .players
+            .iter()
+            .map(|id| PlayerData {
+                id: id.clone(),
+                agent: self
+                    .agents
+                    .iter()
+                    .find(|(other, _)| other == id)
+                    .map_or(String::new(), |(_, agent)| agent.to_string()),
+            })
+            .collect();
+        Some(GameData {
+            seed: record.seed,
+            version: VERSION.to_string(),
+            players,
+            decisions,
+        })
+    }
+
+    // let every other agent know why the player took their last decision, if they explained it
+    fn share_explanation(&mut self, player: &str) {
+        let Some((_, agent)) = self.agents.iter_mut().find(|(id, _)| id == player) else {
+            return;
+        };
+        let Some(explanation) = agent.take_explanation() else {
+            return;
+        };
+        for (_, other) in self.agents.iter_mut().filter(|(id, _)| id != player) {
+            other.share_explanation(&explanation);
+        }
+    }
+
+    fn note(&mut self, round: usize, view: &GameView, legal: Vec<u8>, action: Action) {
+        let Some(decisions) = self.decisions.as_mut() else {
+            return;
+        };
+        let seat = view.turn().expect("must be a decision of the seat to act");
+        let mut observation = [0.0; OBSERVATION];
+        env::encode(view, seat, &mut observation);
+        decisions.push(Decision {
+            seat: seat as u8,
+            round: round as u8,
+            observation: observation.to_vec(),
+            legal,
+            action: usize::from(action) as u8,
+            result: None,
+        });
+    }
+
+    // fill in how the round went for every decision taken in it
+    fn note_result(&mut self, round: usize) {
+        let Some(decisions) = self.decisions.as_mut() else {
+            return;
+        };
+        let record = self.game.record();
+        let Some(played) = record.rounds.get(round) else {
+            return;
+        };
+        let (won, scores) = (
+            played.tricks_won(),
+            played.scores(&record.ruleset, &record.rules),
+        );
+        for decision in decisions.iter_mut().filter(|d| d.round as usize == round) {
+            let seat = decision.seat as usize;
+            decision.result = played.calls[seat]
+                .zip(scores)
+                .map(|(call, scores)| RoundResult {
+                    call: call.value(),
+                    tricks: won[seat],
+                    score: scores[seat],
+                    trump: played.trump.unwrap_or(Suit::Spades),
+                });
+        }
+    }
+
+    /// Everything that has happened in the game so far, hidden cards included
+    pub fn record(&self) -> Record {
+        self.game.record()
+    }
+
     // add an agent
     pub fn add_agent(&mut self, id: String, agent: AgentKind) -> Result<()> {
-        debug!(?id, ?agent, "attempting to add player to the game");
-        self.game.add_player(&id)?;
+        self.add_agent_at(id, None, agent)
+    }
+
+    /// Add an agent who asked for `seat`, which no agent added later can take from them
+    pub fn add_agent_at(
+        &mut self,
+        id: String,
+        seat: Option<usize>,
+        agent: AgentKind,
+    ) -> Result<()> {
+        debug!(?id, ?seat, ?agent, "attempting to add player to the game");
+        self.game.add_player_at(&id, seat)?;
         self.agents.push((id, agent));
         Ok(())
     }
 
+    /// Send every agent their last view of the game along with how it went
+    pub fn announce(&mut self, results: Results) {
+        for (id, agent) in self.agents.iter_mut() {
+            let view = self
+                .game
+                .build_view_for(id)
+                .expect("must have a view for every agent");
+            agent.announce(&view, &results);
+        }
+    }
+
+    /// Take the agents back out, e.g. to seat them at another table
+    pub fn into_agents(self) -> Vec<(String, AgentKind)> {
+        self.agents
+    }
+
     pub fn is_ready(&self) -> bool {
         self.game.is_ready()
     }
@@ -33,14 +200,94 @@ impl Host {
         self.game.is_over()
     }
 
+    /// Points collected by each seat so far, scored by the ruleset of the game
+    pub fn scores(&self) -> [f32; 4] {
+        self.game.scores()
+    }
+
+    /// Why the game ended, once it is over
+    pub fn end_reason(&self) -> Option<EndReason> {
+        self.game.end_reason()
+    }
+
+    // ask the player for their call, then make and note it. a call the game turns down is
+    // replaced by the one of a bot
+    fn request_call(&mut self, round: usize, player: &str, playerview: &GameView) {
+        debug!(?player, "requesting call from ");
+        let (_, agent) = self
+            .agents
+            .iter_mut()
+            .find(|(id, _)| id == player)
+            .expect("player must be in agents list");
+        debug!(?playerview);
+        let mut call = agent.call(playerview);
+        if let Err(reason) = self.game.call(player, call) {
+            debug!(
+                ?player,
+                ?call,
+                ?reason,
+                "call turned down, a bot calls instead"
+            );
+            call = AgentKind::Bot(Bot::default()).call(playerview);
+            self.game
+                .call(player, call)
+                .expect("a bot only makes the calls the table allows");
+        }
+        // nil is the last of the actions, legal wherever the ruleset allows it
+        let nil = self
+            .game
+            .ruleset()
+            .check_call(Call::nil(), &self.game.rules());
+        let calls = if nil.is_ok() { ACTIONS } else { ACTIONS - 1 };
+        self.note(
+            round,
+            playerview,
+            (52..calls as u8).collect(),
+            Action::Call(call),
+        );
+    }
+
     pub fn run(&mut self) {
-        // FIXME: should return an error if there are not currently 4 players
-        for _round in 0..5 {
+        // FIXME: should return an error if the table is not full
+        let seats = self.game.rules().seats.count();
+        for round in 0..self.game.rules().rounds() {
             // request a call
-            debug!(?_round);
-            for _turn in 0..4 {
-                let player = self.game.turn().expect("the next turn must be available");
-                debug!(?player, "requesting call from ");
+            debug!(?round);
+            if self.game.rules().sealed {
+                // every player is shown the round before any call is in, and the calls are only
+                // explained once they have all been made
+                let players = self.game.yet_to_call();
+                let views: Vec<GameView> = players
+                    .iter()
+                    .map(|player| {
+                        self.game
+                            .build_view_for(player)
+                            .expect("must have a view for this player")
+                    })
+                    .collect();
+                for (player, view) in players.iter().zip(views.iter()) {
+                    self.request_call(round, player, view);
+                }
+                for player in players.iter() {
+                    self.share_explanation(player);
+                }
+            } else {
+                for _turn in 0..seats {
+                    let player = self.game.turn().expect("the next turn must be available");
+                    let playerview = self
+                        .game
+                        .build_view_for(&player)
+                        .expect("must have a view for this player");
+                    self.request_call(round, &player, &playerview);
+                    self.share_explanation(&player);
+                }
+            }
+
+            // the highest caller picks the trump, at tables that leave it to them
+            // INFO: the pick is not noted in the dataset, which only has actions for calls and cards
+            if self.game.is_choosing_trump() {
+                let player = self.game.turn().expect("the chooser must have the turn");
+                debug!(?player, "requesting trump from");
                 let (_, agent) = self
                     .agents
                     .iter_mut()
@@ -50,17 +297,17 @@ impl Host {
                     .game
                     .build_view_for(&player)
                     .expect("must have a view for this player");
-                debug!(?playerview);
-                let call = agent.call(&playerview);
-                self.game.call(&player, call).expect(
-                    "FIXME: if this errors, return error to agent. should make unfallible bot",
-                );
+                let trump = agent.choose_trump(&playerview);
+                self.share_explanation(&player);
+                self.game
+                    .choose_trump(&player, trump)
+                    .expect("any suit is a valid trump for the chooser");
             }
 
             // request a break
-            for _trick in 0..13 {
+            'tricks: for _trick in 0..tricks_in_round(seats) {
                 debug!(?_trick);
-                for _turn in 0..4 {
+                for _turn in 0..seats {
                     let player = self.game.turn().expect("the next turn must be available");
                     debug!(?player, "requesting break from");
                     let (_, agent) = self
@@ -74,13 +321,77 @@ impl Host {
                         .expect("must have a view for this player");
                     // FIXME: figure out a better way of displaying playerview
                     debug!(?playerview);
-                    let play = agent.play(&playerview);
+                    // a claim that is turned down is sent back, and the player asked again
+                    let mut refused = false;
+                    let play = loop {
+                        let next = if refused {
+                            agent.play_after_refused_claim(&playerview)
+                        } else {
+                            agent.play(&playerview)
+                        };
+                        match next {
+                            Move::Play(card) => break Some(card),
+                            Move::Claim(claim) => match self.game.claim(&player, claim) {
+                                Ok(()) => break None,
+                                Err(reason) => {
+                                    debug!(?player, ?claim, ?reason, "claim turned down");
+                                    agent.reject_claim(reason);
+                                    refused = true;
+                                }
+                            },
+                        }
+                    };
+                    self.share_explanation(&player);
+                    let Some(play) = play else {
+                        debug!(?player, "claim settled the rest of the round");
+                        break 'tricks;
+                    };
+                    let moves = self.game.get_valid_moves(&player).unwrap();
                     // FIXME: temporary fix to get rid of dead code warning. but if this condition
                     // is not met, the user is messing around with us. swap out with a bot.
-                    assert!(self.game.get_valid_moves(&player).unwrap().contains(&play));
+                    assert!(moves.contains(&play));
+                    let mut legal: Vec<u8> = moves
+                        .into_iter()
+                        .map(|card| usize::from(Action::Play(card)) as u8)
+                        .collect();
+                    legal.sort();
+                    self.note(round, &playerview, legal, Action::Play(play));
                     self.game.play(&player, play).expect("FIXME: if this errors, return error to agent or make a move from the unfallible bot");
                 }
             }
+            self.note_result(round);
+            // a special call or the target score may end the game before the last round
+            if self.game.is_over() {
+                debug!(reason = ?self.game.end_reason(), "game is over");
+                break;
+            }
         }
     }
 }
+
+#[cfg(test)]
+impl<R: TrickTakingRules> Host<R> {
+    /// Fill every seat with a bot of `difficulty`, the bot of seat `i` seeded with `i`
+    pub(crate) fn with_bots(mut self, difficulty: crate::agent::Difficulty) -> Self {
+        for i in 0..self.game.rules().seats.count() {
+            let bot = Bot::new(difficulty, crate::agent::Style::Balanced).with_seed(i as u64);
+            self.add_agent(format!("bot{i}"), AgentKind::Bot(bot))
+                .unwrap();
+        }
+        self
+    }
+}
+
+/// Game of `ruleset` dealt from `seed` and played out by bots of `difficulty`
+#[cfg(test)]
+pub(crate) fn bot_game<R: TrickTakingRules>(
+    seed: u64,
+    difficulty: crate::agent::Difficulty,
+    ruleset: R,
+) -> Host<R> {
+    let mut host = Host::with_seed(seed)
+        .with_ruleset(ruleset)
+        .with_bots(difficulty);
+    host.run();
+    host
+}
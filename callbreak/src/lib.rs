@@ -1,7 +1,13 @@
 pub mod agent;
+pub mod analysis;
+pub mod dataset;
+pub mod duplicate;
+pub mod env;
 mod error;
 pub mod game;
 mod host;
+pub mod matches;
+pub mod stats;
 
 pub use error::Error;
 pub use host::Host;
@@ -0,0 +1,218 @@
+//! Matches: a series of games between the same players, with their points added up from one
+//! game to the next.
+
+use crate::agent::{AgentKind, Results, Standing};
+use crate::game::{Record, Rules, Seating};
+use crate::stats::Meter;
+use crate::{Error, Host, Result};
+use tracing::debug;
+
+/// Host for a match of several games between the same agents, who keep the seats of the first
+/// game unless they are drawn again for every game
+#[derive(Debug)]
+pub struct Match {
+    games: usize,
+    seed: Option<u64>,
+    rules: Rules,
+    reshuffle: bool,
+    agents: Vec<(String, AgentKind)>,
+    records: Vec<Record>,
+}
+
+impl Match {
+    /// Match of `games` games
+    pub fn new(games: usize) -> Self {
+        Self {
+            games,
+            seed: None,
+            rules: Rules::default(),
+            reshuffle: false,
+            agents: vec![],
+            records: vec![],
+        }
+    }
+
+    /// Match whose seating and deals all follow from `seed`, every game being dealt differently
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Play every game by these house rules, e.g. to a target score
+    pub fn with_rules(mut self, rules: Rules) -> Self {
+        self.rules = rules;
+        self
+    }
+
+    /// Draw the seats again before every game rather than keeping those of the first
+    pub fn reshuffled(mut self) -> Self {
+        self.reshuffle = true;
+        self
+    }
+
+    pub fn add_agent(&mut self, id: String, agent: AgentKind) -> Result<()> {
+        if self.is_ready() {
+            return Err(Error::NotAcceptingNewPlayers);
+        }
+        if self.agents.iter().any(|(other, _)| *other == id) {
+            return Err(Error::PlayerAlreadyInGame);
+        }
+        self.agents.push((id, agent));
+        Ok(())
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.agents.len() == self.rules.seats.count()
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.records.len() == self.games
+    }
+
+    /// Play every game of the match, one after the other
+    pub fn run(&mut self) {
+        // FIXME: should return an error if the table is not full
+        while !self.is_over() {
+            let game = self.records.len();
+            debug!(game, "starting game of the match");
+            let host = match self.seed {
+                Some(seed) => Host::with_seed(seed.wrapping_add(game as u64)),
+                None => Host::new(),
+            };
+            // the first game draws the seats, which the rematches keep unless reshuffled
+            let rules = if game > 0 && !self.reshuffle {
+                self.rules.with_seating(Seating::Fixed)
+            } else {
+                self.rules
+            };
+            let mut host = host.with_rules(rules).with_standings(self.standings());
+            for (id, agent) in self.agents.drain(..) {
+                host.add_agent(id, agent)
+                    .expect("must be able to add the same agents to every game");
+            }
+            host.run();
+            let record = host.record();
+            self.records.push(record.clone());
+            host.announce(Results {
+                luck: Meter::default().measure(&record).summaries,
+                standings: self.standings(),
+                ..Results::default()
+            });
+            self.agents = host.into_agents();
+            // INFO: the agents are put back in the order they sat in, so that seating them in the
+            // order they are added keeps their seats
+            self.agents
+                .sort_by_key(|(id, _)| record.players.iter().position(|player| player == id));
+        }
+    }
+
+    /// Records of the games that have been played so far
+    pub fn records(&self) -> &[Record] {
+        &self.records
+    }
+
+    /// Points of every player over the games played so far, the most first
+    pub fn standings(&self) -> Vec<Standing> {
+        let mut standings: Vec<Standing> = self
+            .agents
+            .iter()
+            .map(|(id, _)| Standing {
+                player: id.clone(),
+                total: 0.0,
+                wins: 0,
+            })
+            .collect();
+        for record in self.records.iter() {
+            let scores = record.scores();
+            let best = scores[..record.players.len()]
+                .iter()
+                .copied()
+                .fold(f32::MIN, f32::max);
+            for (seat, player) in record.players.iter().enumerate() {
+                let index = match standings.iter().position(|s| &s.player == player) {
+                    Some(index) => index,
+                    None => {
+                        standings.push(Standing {
+                            player: player.clone(),
+                            total: 0.0,
+                            wins: 0,
+                        });
+                        standings.len() - 1
+                    }
+                };
+                standings[index].total += scores[seat];
+                if scores[seat] == best {
+                    standings[index].wins += 1;
+                }
+            }
+        }
+        standings.sort_by(|a, b| b.total.total_cmp(&a.total));
+        standings
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::{Bot, Difficulty, Style};
+    use crate::game::EndReason;
+
+    fn played(mut series: Match) -> Match {
+        for i in 0..4 {
+            let bot = Bot::new(Difficulty::Beginner, Style::Balanced).with_seed(i);
+            series
+                .add_agent(i.to_string(), AgentKind::Bot(bot))
+                .unwrap();
+        }
+        series.run();
+        series
+    }
+
+    #[test]
+    fn rematches_keep_the_seats_and_add_up_the_points() {
+        let played = played(Match::new(3).with_seed(8));
+        let records = played.records();
+        assert_eq!(records.len(), 3);
+        assert!(records.iter().all(|r| r.players == records[0].players));
+        assert_ne!(records[0].rounds[0].hands, records[1].rounds[0].hands);
+        let standings = played.standings();
+        assert_eq!(standings.len(), 4);
+        let total: f32 = records.iter().flat_map(|r| r.scores()).sum();
+        let summed: f32 = standings.iter().map(|s| s.total).sum();
+        assert!((total - summed).abs() < 1e-3);
+        assert!(standings.iter().map(|s| s.wins).sum::<usize>() >= 3);
+        assert!(standings.windows(2).all(|w| w[0].total >= w[1].total));
+    }
+
+    #[test]
+    fn reshuffled_matches_draw_the_seats_again() {
+        let played = played(Match::new(4).with_seed(2).reshuffled());
+        let records = played.records();
+        assert!(records.iter().any(|r| r.players != records[0].players));
+    }
+
+    #[test]
+    fn games_to_a_target_score_end_once_someone_gets_there() {
+        let rules = Rules::default().with_target(12.0);
+        let played = played(Match::new(2).with_seed(6).with_rules(rules));
+        for record in played.records() {
+            let Some(EndReason::TargetReached { seat, round }) = record.end else {
+                panic!("a game to a target must end when it is reached");
+            };
+            assert_eq!(record.rounds.len(), round + 1);
+            assert!(record.scores()[seat] >= 12.0);
+        }
+    }
+
+    #[test]
+    fn cannot_add_fifth_agent() {
+        let mut series = Match::new(2);
+        for i in 0..4 {
+            series
+                .add_agent(i.to_string(), AgentKind::Bot(Bot::default()))
+                .unwrap();
+        }
+        let result = series.add_agent("4".to_string(), AgentKind::Bot(Bot::default()));
+        assert_eq!(result, Err(Error::NotAcceptingNewPlayers));
+    }
+}
@@ -0,0 +1,264 @@
+//! How good the cards each player was dealt were, against what they made of them.
+//!
+//! A hand is worth the tricks it can be expected to take. Whatever it is worth beyond the average
+//! hand of its deal is put down to luck and taken off the points the player scored, leaving a luck adjusted
+//! score that ratings can use without being fooled by who was dealt the spades.
+
+use crate::agent::heuristic;
+use crate::analysis::play_out;
+use crate::game::trump::as_spades;
+use crate::game::{Card, Hand, Record, Round, RoundRecord, Rules, Ruleset, TrickTakingRules, Turn};
+use rand::{SeedableRng, rngs::StdRng};
+use serde::{Deserialize, Serialize};
+
+/// How the tricks a hand is worth are worked out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Evaluation {
+    /// From the hand alone, as a caller would see it
+    Hand,
+    /// By playing the actual deal out this many times with every hand known, which also counts
+    /// how the other hands fit with it
+    Deal { rollouts: usize },
+}
+
+/// Luck of a seat in a single round
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SeatLuck {
+    pub player: String,
+    /// Tricks the hand was expected to take
+    pub expected: f64,
+    pub taken: u8,
+    pub points: f32,
+    /// Tricks the hand was worth beyond the average hand of the deal, so the luck of the seats
+    /// of a round adds up to nothing
+    pub luck: f64,
+    /// Points with the luck taken off
+    pub adjusted: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoundLuck {
+    /// In the order of the seats
+    pub seats: Vec<SeatLuck>,
+}
+
+/// Totals of a single player over the rounds that are over
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Summary {
+    pub player: String,
+    pub rounds: usize,
+    pub expected: f64,
+    pub taken: u32,
+    pub points: f32,
+    pub luck: f64,
+    pub adjusted: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Report {
+    /// In the order of the seats
+    pub summaries: Vec<Summary>,
+    pub rounds: Vec<RoundLuck>,
+}
+
+/// Measures the luck of the deals of finished games
+#[derive(Debug, Clone)]
+pub struct Meter {
+    evaluation: Evaluation,
+    seed: u64,
+}
+
+impl Default for Meter {
+    fn default() -> Self {
+        Self::new(Evaluation::Hand)
+    }
+}
+
+impl Meter {
+    pub fn new(evaluation: Evaluation) -> Self {
+        Self {
+            evaluation,
+            seed: 0,
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn measure(&self, record: &Record) -> Report {
+        let mut rng = StdRng::seed_from_u64(self.seed);
+        let rounds: Vec<RoundLuck> = record
+            .rounds
+            .iter()
+            .enumerate()
+            .filter_map(|(index, played)| {
+                let points = played.scores(&record.ruleset, &record.rules)?;
+                let expected =
+                    self.expected(index, played, &record.ruleset, &record.rules, &mut rng)?;
+                let taken = played.tricks_won();
+                // INFO: comparing against the deal rather than a fixed 13 / 4 keeps whatever the
+                // evaluation gets wrong for every hand out of the luck
+                let average = expected.iter().sum::<f64>() / record.rules.seats.count() as f64;
+                // a trick of luck is worth what a trick of a made call scores
+                let trick = record.ruleset.trick_value() as f64;
+                let seats = (0..record.rules.seats.count())
+                    .map(|seat| {
+                        let luck = expected[seat] - average;
+                        SeatLuck {
+                            player: record.players[seat].clone(),
+                            expected: expected[seat],
+                            taken: taken[seat],
+                            points: points[seat],
+                            luck,
+                            adjusted: points[seat] as f64 - luck * trick,
+                        }
+                    })
+                    .collect();
+                Some(RoundLuck { seats })
+            })
+            .collect();
+        let summaries = record
+            .players
+            .iter()
+            .enumerate()
+            .map(|(seat, player)| {
+                let seats = rounds.iter().map(|round| &round.seats[seat]);
+                Summary {
+                    player: player.clone(),
+                    rounds: rounds.len(),
+                    expected: seats.clone().map(|s| s.expected).sum(),
+                    taken: seats.clone().map(|s| s.taken as u32).sum(),
+                    points: seats.clone().map(|s| s.points).sum(),
+                    luck: seats.clone().map(|s| s.luck).sum(),
+                    adjusted: seats.map(|s| s.adjusted).sum(),
+                }
+            })
+            .collect();
+        Report { summaries, rounds }
+    }
+
+    // tricks each seat's hand was expected to take, None when the round never got to be played
+    fn expected(
+        &self,
+        index: usize,
+        played: &RoundRecord,
+        ruleset: &Ruleset,
+        rules: &Rules,
+        rng: &mut StdRng,
+    ) -> Option<[f64; 4]> {
+        match self.evaluation {
+            Evaluation::Hand => {
+                // the heuristic counts spades as trump, so the hand is shown to it that way
+                let trump = played.trump?;
+                Some(played.hands.each_ref().map(|hand| {
+                    let hand: Vec<Card> = hand.iter().map(|card| as_spades(*card, trump)).collect();
+                    heuristic(&hand)
+                        .iter()
+                        .enumerate()
+                        .map(|(tricks, chance)| tricks as f64 * chance)
+                        .sum()
+                }))
+            }
+            Evaluation::Deal { rollouts } => {
+                let seats = rules.seats.count();
+                let starter = Turn::new(index % seats);
+                let mut dealt =
+                    Round::from_hands(seats, starter, played.hands.clone().map(Hand::new))
+                        .with_ruleset(*ruleset)
+                        .with_rules(rules)
+                        .with_trump(played.trump?);
+                for i in 0..seats {
+                    let turn = starter.after(i, seats);
+                    dealt.call(played.calls[turn]?, turn).ok()?;
+                }
+                let rollouts = rollouts.max(1);
+                let mut total = [0.0; 4];
+                for _ in 0..rollouts {
+                    let mut round = dealt.clone();
+                    play_out(&mut round, rng);
+                    let won = round.tricks_won();
+                    for (seat, total) in total.iter_mut().enumerate() {
+                        *total += won[Turn::new(seat)] as f64;
+                    }
+                }
+                Some(total.map(|total| total / rollouts as f64))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::agent::Difficulty;
+    use crate::game::CallBreak;
+    use crate::host::bot_game;
+
+    fn record(seed: u64) -> Record {
+        bot_game(seed, Difficulty::Beginner, CallBreak).record()
+    }
+
+    #[test]
+    fn luck_of_a_deal_evens_out_over_the_table() {
+        let record = record(1);
+        for evaluation in [Evaluation::Hand, Evaluation::Deal { rollouts: 8 }] {
+            let report = Meter::new(evaluation).measure(&record);
+            assert_eq!(report.rounds.len(), 5);
+            for round in report.rounds.iter() {
+                let luck: f64 = round.seats.iter().map(|seat| seat.luck).sum();
+                assert!(luck.abs() < 1e-9);
+            }
+        }
+        // every trick of a deal played out goes to someone
+        let report = Meter::new(Evaluation::Deal { rollouts: 8 }).measure(&record);
+        for round in report.rounds.iter() {
+            let expected: f64 = round.seats.iter().map(|seat| seat.expected).sum();
+            assert!((expected - 13.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn luck_of_spades_is_taken_off_at_ten_points_a_trick() {
+        let host = bot_game(3, Difficulty::Beginner, crate::game::Spades::default());
+        let report = Meter::new(Evaluation::Deal { rollouts: 4 }).measure(&host.record());
+        for seat in report.rounds.iter().flat_map(|round| round.seats.iter()) {
+            let adjusted = seat.points as f64 - seat.luck * 10.0;
+            assert!((seat.adjusted - adjusted).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn hands_are_valued_with_the_trump_of_their_round() {
+        use crate::game::Suit;
+        let mut record = record(1);
+        let spades = Meter::default().measure(&record).rounds[0].seats[0].expected;
+        record.rounds[0].trump = Some(Suit::Hearts);
+        let hearts = Meter::default().measure(&record).rounds[0].seats[0].expected;
+        let hand: Vec<Card> = record.rounds[0].hands[0]
+            .iter()
+            .map(|card| as_spades(*card, Suit::Hearts))
+            .collect();
+        let expected: f64 = heuristic(&hand)
+            .iter()
+            .enumerate()
+            .map(|(tricks, chance)| tricks as f64 * chance)
+            .sum();
+        assert!((hearts - expected).abs() < 1e-9);
+        assert!((hearts - spades).abs() > 1e-3);
+    }
+
+    #[test]
+    fn adjusted_points_take_the_luck_off() {
+        let record = record(2);
+        let report = Meter::default().measure(&record);
+        let scores = record.scores();
+        for (seat, summary) in report.summaries.iter().enumerate() {
+            assert_eq!(summary.rounds, 5);
+            assert!((summary.points - scores[seat]).abs() < 1e-3);
+            assert!((summary.adjusted - (summary.points as f64 - summary.luck)).abs() < 1e-3);
+            assert!((0.0..=65.0).contains(&summary.expected));
+        }
+    }
+}